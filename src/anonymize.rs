@@ -0,0 +1,164 @@
+//! Hashing the names and paths of a tree, so that it can be shared without revealing them.
+
+use std::{
+    hash::{BuildHasher, RandomState},
+    mem,
+    path::{Component, PathBuf},
+};
+
+use serde_json::Value;
+use termtree::Tree;
+
+use crate::{intern::Interner, plan::Input, visit, TreeNode};
+
+/// The words of addresses which are kept as is.
+const KEYWORDS: [&str; 4] = ["module", "data", "var", "local"];
+
+/// Replaces names with hashes which are consistent within a run, but can't be correlated across
+/// runs or reversed by hashing guesses.
+pub struct Anonymizer {
+    state: RandomState,
+}
+
+impl Anonymizer {
+    pub fn new() -> Self {
+        Anonymizer {
+            state: RandomState::new(),
+        }
+    }
+
+    fn word(&self, word: &str) -> String {
+        format!("{:08x}", self.state.hash_one(word) as u32)
+    }
+
+    /// Replace the names and string literals of an address-like `text`, e.g. `module.net["eu"]`,
+    /// keeping keywords, numbers and punctuation.
+    pub fn text(&self, text: &str) -> String {
+        let is_word = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-');
+        let mut anonymized = String::with_capacity(text.len());
+        let mut chars = text.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            if c == '"' {
+                let mut literal = String::new();
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => literal.extend(chars.next().map(|(_, c)| c)),
+                        c => literal.push(c),
+                    }
+                }
+                anonymized.push('"');
+                anonymized.push_str(&self.word(&literal));
+                anonymized.push('"');
+            } else if is_word(c) {
+                let mut end = start + c.len_utf8();
+                while let Some((index, c)) = chars.next_if(|(_, c)| is_word(*c)) {
+                    end = index + c.len_utf8();
+                }
+                let word = &text[start..end];
+                if KEYWORDS.contains(&word) || word.chars().all(|c| c.is_ascii_digit()) {
+                    anonymized.push_str(word);
+                } else {
+                    anonymized.push_str(&self.word(word));
+                }
+            } else {
+                anonymized.push(c);
+            }
+        }
+        anonymized
+    }
+
+    fn value(&self, value: &mut Value) {
+        match value {
+            Value::String(string) => *string = self.word(string),
+            Value::Array(array) => array.iter_mut().for_each(|value| self.value(value)),
+            Value::Object(object) => {
+                *object = mem::take(object)
+                    .into_iter()
+                    .map(|(key, mut value)| {
+                        self.value(&mut value);
+                        (self.word(&key), value)
+                    })
+                    .collect();
+            }
+            Value::Null | Value::Bool(_) | Value::Number(_) => {}
+        }
+    }
+
+    /// Anonymize the module names, addresses, paths, sources, instance keys, references,
+    /// owners, label values and markers of every node of `tree`, preserving its structure.
+    pub fn tree(&self, tree: &mut Tree<TreeNode>, interner: &mut Interner) {
+        visit(tree, &mut |node| {
+            if !node.address.is_empty() {
+                node.name = interner.str(&self.text(&node.name));
+                node.address = interner.str(&self.text(&node.address));
+            }
+            let source: PathBuf = node
+                .source
+                .components()
+                .map(|component| match component {
+                    Component::Normal(name) => self.word(&name.to_string_lossy()).into(),
+                    component => component.as_os_str().to_owned(),
+                })
+                .collect();
+            node.source = interner.path(source);
+            // Links would reveal the real paths
+            node.hyperlink = None;
+            if let Some(call_source) = &mut node.call_source {
+                *call_source = interner.str(&self.text(call_source));
+            }
+            if let Some(remote) = &mut node.remote {
+                *remote = interner.str(&self.text(remote));
+            }
+            if let Some(for_each) = &mut node.for_each {
+                *for_each = mem::take(for_each)
+                    .into_iter()
+                    .map(|(key, mut value)| {
+                        self.value(&mut value);
+                        (self.word(&key), value)
+                    })
+                    .collect();
+            }
+            for reference in node
+                .count_references
+                .iter_mut()
+                .chain(&mut node.for_each_references)
+                .flatten()
+            {
+                *reference = self.text(reference);
+            }
+            if let Some(instance) = &mut node.instance {
+                *instance = self.text(instance);
+            }
+            // Markers may name files, outputs or git refs
+            for marker in &mut node.markers {
+                *marker = interner.str(&self.text(marker));
+            }
+            for owner in &mut node.owners {
+                *owner = interner.str(&self.text(owner));
+            }
+            for value in node.labels.values_mut() {
+                *value = interner.str(&self.word(value));
+            }
+            for address in &mut node.depends_on {
+                *address = interner.str(&self.text(address));
+            }
+            for (name, input) in &mut node.inputs {
+                *name = self.word(name);
+                match input {
+                    Input::Constant(value) => self.value(value),
+                    Input::References(references) => {
+                        for reference in references {
+                            *reference = self.text(reference);
+                        }
+                    }
+                    Input::Computed | Input::Sensitive => {}
+                }
+            }
+            for (child, parent) in &mut node.provider_mappings {
+                *child = self.text(child);
+                *parent = self.text(parent);
+            }
+        });
+    }
+}
@@ -0,0 +1,51 @@
+//! Limits on the number of resources within modules, checked against the tree.
+
+use termtree::Tree;
+
+use crate::{
+    config::{Budget, Level},
+    glob,
+    intern::Interner,
+    TreeNode,
+};
+
+/// A module whose subtree exceeds a budget.
+pub struct Violation {
+    pub address: String,
+    pub resources: usize,
+    pub max_resources: usize,
+    pub level: Level,
+}
+
+/// Check the resource count of every module's subtree, of planned instances where known, against
+/// the budgets matching its address, marking the offending modules. Returns the resource count of
+/// `tree`.
+pub fn check(
+    tree: &mut Tree<TreeNode>,
+    budgets: &[Budget],
+    violations: &mut Vec<Violation>,
+    interner: &mut Interner,
+) -> usize {
+    let mut resources: usize = tree.root.resources().values().sum();
+    for leaf in &mut tree.leaves {
+        resources += check(leaf, budgets, violations, interner);
+    }
+    for budget in budgets {
+        if !tree.root.address.is_empty()
+            && glob::matches(&budget.module, &tree.root.address)
+            && resources > budget.max_resources
+        {
+            tree.root.markers.push(interner.str(&format!(
+                "over budget: {resources}/{} resources",
+                budget.max_resources
+            )));
+            violations.push(Violation {
+                address: tree.root.address.to_string(),
+                resources,
+                max_resources: budget.max_resources,
+                level: budget.level,
+            });
+        }
+    }
+    resources
+}
@@ -0,0 +1,121 @@
+//! Caching plans by everything which can affect them, to reuse them while a project is unchanged.
+
+use std::{
+    collections::BTreeSet,
+    env, fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+
+use crate::{environment, hcl, impact::EXTENSIONS, installed, scan::Scanner, Args};
+
+/// Hash everything which can affect the plan of the project at `project_dir`, whose configuration
+/// is at `dir`: the terraform files of its local modules and of those installed by `terraform init`,
+/// the module manifest, the dependency lock file, variable files and values, the environment
+/// variables terraform reads them from, and the program planning it.
+pub fn fingerprint(args: &Args, project_dir: &Path, dir: &Path) -> anyhow::Result<u64> {
+    let mut hasher = DefaultHasher::new();
+    project_dir.hash(&mut hasher);
+    args.program().hash(&mut hasher);
+    args.binary().hash(&mut hasher);
+    let mut scanner = Scanner::default();
+    let mut pending = vec![dir.to_owned()];
+    let mut dirs = BTreeSet::new();
+    while let Some(dir) = pending.pop() {
+        if !dirs.insert(dir.clone()) {
+            continue;
+        }
+        for block in scanner.module(&dir)?.blocks("module") {
+            let source = block
+                .body
+                .attribute("source")
+                .and_then(|source| hcl::string_literal(&source.expression));
+            if let Some(source) = source.filter(|source| source.starts_with('.')) {
+                let source = dir.join(source);
+                pending.push(source.canonicalize().unwrap_or(source));
+            }
+        }
+    }
+    // Remote modules are only known by the manifest, which also records the versions installed
+    hash_file(
+        &mut hasher,
+        &installed::data_dir(dir)
+            .join("modules")
+            .join("modules.json"),
+    )?;
+    for module in installed::load(dir)? {
+        let module_dir = dir.join(&module.dir);
+        if !module.key.is_empty() && module_dir.is_dir() {
+            dirs.insert(module_dir.canonicalize().unwrap_or(module_dir));
+        }
+    }
+    for dir in &dirs {
+        hash_file(&mut hasher, &dir.join(".terraform.lock.hcl"))?;
+        let mut files: Vec<PathBuf> = fs::read_dir(dir)
+            .with_context(|| format!("failed to read {}", dir.display()))?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                let name = path.to_string_lossy();
+                EXTENSIONS.iter().any(|extension| name.ends_with(extension))
+            })
+            .collect();
+        files.sort();
+        for file in files {
+            hash_file(&mut hasher, &file)?;
+        }
+    }
+    for var_file in &args.var_file {
+        hash_file(&mut hasher, &project_dir.join(var_file))?;
+    }
+    args.var.hash(&mut hasher);
+    args.replace.hash(&mut hasher);
+    args.target.hash(&mut hasher);
+    (args.refresh, args.destroy).hash(&mut hasher);
+    args.tf_arg.hash(&mut hasher);
+    args.workspace.hash(&mut hasher);
+    // The variables of the env files replace those of the environment
+    let mut variables: Vec<(String, String)> = env::vars()
+        .filter(|(name, _)| !args.isolate_env || environment::allowed(name, &args.allow_env))
+        .filter(|(name, _)| !args.environment.iter().any(|(loaded, _)| loaded == name))
+        .chain(args.environment.iter().cloned())
+        .filter(|(name, _)| environment::affects_plan(name))
+        .collect();
+    variables.sort();
+    variables.hash(&mut hasher);
+    Ok(hasher.finish())
+}
+
+/// Hash the path and contents of `path`, if it exists.
+fn hash_file(hasher: &mut DefaultHasher, path: &Path) -> anyhow::Result<()> {
+    path.hash(hasher);
+    match fs::read(path) {
+        Ok(contents) => contents.hash(hasher),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+        Err(error) => {
+            return Err(error).with_context(|| format!("failed to read {}", path.display()))
+        }
+    }
+    Ok(())
+}
+
+/// The file in `cache_dir` caching the plan JSON whose inputs hash to `fingerprint`.
+pub fn entry(cache_dir: &Path, fingerprint: u64) -> PathBuf {
+    cache_dir.join(format!("{fingerprint:016x}.json"))
+}
+
+/// The plan JSON cached at `entry`, if any.
+pub fn load(entry: &Path) -> Option<String> {
+    fs::read_to_string(entry).ok()
+}
+
+/// Cache `json` at `entry`, creating its directory if needed.
+pub fn store(entry: &Path, json: &str) -> anyhow::Result<()> {
+    if let Some(dir) = entry.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    fs::write(entry, json).with_context(|| format!("failed to write {}", entry.display()))
+}
@@ -0,0 +1,90 @@
+//! Finding every call of a module directory across the root modules of a repository, to gauge
+//! the impact of changing a shared module.
+
+use std::{fmt::Write as _, fs, path::Path};
+
+use anyhow::Context as _;
+use termtree::Tree;
+
+use crate::{
+    discover, pool, report, roots_of, runner::Runner, terragrunt, visit, Args, Command, TreeNode,
+};
+
+/// Print the calls of the module at `path` within every root module beneath `dir`, as the root
+/// module's directory and the call's address, e.g. `envs/prod: module.platform.module.vpc`.
+///
+/// Root modules which fail are reported on stderr, failing once the others have been searched.
+pub fn run(args: &Args, path: &Path, dir: &Path) -> anyhow::Result<()> {
+    let path = path
+        .canonicalize()
+        .with_context(|| format!("failed to resolve {}", path.display()))?;
+    let parallelism = args.parallelism.unwrap_or(1) as usize;
+    let roots = match args.runner {
+        Runner::Terraform => discover::roots(dir, parallelism)?,
+        Runner::Terragrunt => terragrunt::units(dir)?,
+    };
+    if roots.is_empty() {
+        anyhow::bail!("no root modules found in {}", dir.display())
+    }
+    let args = Args {
+        command: Some(Command::Callers {
+            path: path.clone(),
+            dir: dir.to_owned(),
+        }),
+        // Concurrent plans would draw over each other's progress
+        no_progress: true,
+        ..args.clone()
+    };
+    let runs = roots_of(&args, &roots);
+    let results = pool::map(&runs, parallelism, |run| {
+        let mut out = String::new();
+        let result = report(&mut run.clone(), &mut out);
+        (result, out)
+    });
+    let mut result = Ok(());
+    let mut out = String::new();
+    let mut calls = 0;
+    let mut callers = 0;
+    for (run, (root_result, addresses)) in runs.iter().zip(results) {
+        let name = run.root_name.clone().unwrap_or_default();
+        if let Err(error) = root_result {
+            let error = error.context(format!("failed to report on {name}"));
+            match result {
+                Ok(()) => result = Err(error),
+                Err(_) => eprintln!("Error: {error:?}"),
+            }
+            continue;
+        }
+        callers += !addresses.is_empty() as usize;
+        for address in addresses.lines() {
+            calls += 1;
+            let _ = writeln!(out, "{name}: {address}");
+        }
+    }
+    print!("{out}");
+    let plural = |count| if count == 1 { "" } else { "s" };
+    eprintln!(
+        "{calls} call{} of {} in {callers} of {} root module{}",
+        plural(calls),
+        path.display(),
+        roots.len(),
+        plural(roots.len())
+    );
+    result
+}
+
+/// The addresses of the calls within `tree`, whose modules are at `base` joined with their
+/// sources, of the module at the canonical path `path`.
+pub fn addresses(tree: &mut Tree<TreeNode>, base: &Path, path: &Path) -> String {
+    let mut out = String::new();
+    visit(tree, &mut |node| {
+        if node.address.is_empty() || node.unresolved {
+            return;
+        }
+        let dir = fs::canonicalize(base.join(&*node.source));
+        if dir.is_ok_and(|dir| dir == *path) {
+            let _ = writeln!(out, "{}", node.address);
+        }
+    });
+    out
+}
@@ -0,0 +1,110 @@
+//! Asserting that the module structure matches a baseline, to gate unreviewed changes in CI.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::Context as _;
+use serde::Deserialize;
+use serde_json::Value;
+use termtree::Tree;
+
+use crate::{
+    diff::{self, Call},
+    glyph::Glyphs,
+    json, strip_instance_keys, TreaformError, TreeNode,
+};
+
+/// A module of a tree exported with `--format json`.
+#[derive(Deserialize)]
+struct Exported {
+    format_version: Option<String>,
+    address: String,
+    /// Set on the module instances of `--expand-instances`, which aren't module calls.
+    instance: Option<String>,
+    source: String,
+    remote_source: Option<String>,
+    #[serde(default)]
+    children: Vec<Exported>,
+}
+
+/// Compare the module calls of `tree` with those of the tree exported with `--format json` at
+/// `baseline`, writing the differences to `out`, one per line prefixed with `glyphs`, and failing
+/// if modules were added, removed, renamed or re-sourced.
+///
+/// Sources are compared as they're shown, remote sources by address and others by directory, so
+/// the baseline should be exported with the same `--paths`. Renames are matched by source alone.
+pub fn run(
+    tree: &Tree<TreeNode>,
+    baseline: &Path,
+    glyphs: Glyphs,
+    out: &mut String,
+) -> anyhow::Result<()> {
+    let json = fs::read_to_string(baseline)
+        .with_context(|| format!("failed to read {}", baseline.display()))?;
+    let exported: Exported = serde_json::from_str(&json)
+        .with_context(|| format!("failed to load {}", baseline.display()))?;
+    let version = exported.format_version.as_deref().unwrap_or_default();
+    if version != json::FORMAT_VERSION {
+        anyhow::bail!(
+            "{} has format version `{version}`, expected {}",
+            baseline.display(),
+            json::FORMAT_VERSION
+        )
+    }
+    let mut old = BTreeMap::new();
+    exported_sources(&exported, &mut old);
+    let mut new = BTreeMap::new();
+    tree_sources(tree, &mut new);
+
+    let (old, new) = (calls(&old), calls(&new));
+    let differences = diff::differences(&old, &new);
+    if differences.is_empty() {
+        out.push_str("The modules match the baseline.\n");
+        return Ok(());
+    }
+    out.push_str(&diff::list(&differences, &old, &new, glyphs));
+    Err(TreaformError::BaselineChanged {
+        differences: differences.len(),
+    }
+    .into())
+}
+
+/// The calls of `sources`, whose inputs are unknown.
+fn calls(sources: &BTreeMap<String, String>) -> BTreeMap<String, Call<'_>> {
+    sources
+        .iter()
+        .map(|(address, source)| {
+            let call = Call {
+                source,
+                inputs: &Value::Null,
+            };
+            (address.clone(), call)
+        })
+        .collect()
+}
+
+/// Collect the sources of the module calls beneath `module`, by address without instance keys.
+fn exported_sources(module: &Exported, sources: &mut BTreeMap<String, String>) {
+    for child in &module.children {
+        // The instances of `--expand-instances` repeat their module call
+        if child.instance.is_none() {
+            let source = child.remote_source.as_ref().unwrap_or(&child.source);
+            sources.insert(strip_instance_keys(&child.address), source.clone());
+        }
+        exported_sources(child, sources);
+    }
+}
+
+/// Collect the sources of the module calls beneath `tree`, by address without instance keys.
+fn tree_sources(tree: &Tree<TreeNode>, sources: &mut BTreeMap<String, String>) {
+    for leaf in &tree.leaves {
+        let node = &leaf.root;
+        if node.instance.is_none() {
+            let source = match &node.remote {
+                Some(remote) => remote.to_string(),
+                None => node.source.display().to_string(),
+            };
+            sources.insert(strip_instance_keys(&node.address), source);
+        }
+        tree_sources(leaf, sources);
+    }
+}
@@ -0,0 +1,120 @@
+//! The owners of each module, from the CODEOWNERS file of its repository.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+
+use crate::glob;
+
+/// The locations GitHub and GitLab look for a CODEOWNERS file, relative to the repository root.
+const LOCATIONS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// The rules of a CODEOWNERS file.
+pub struct CodeOwners {
+    root: PathBuf,
+    rules: Vec<Rule>,
+}
+
+struct Rule {
+    pattern: String,
+    owners: Vec<String>,
+}
+
+impl CodeOwners {
+    /// Find and parse the CODEOWNERS file of the repository rooted at `root`.
+    pub fn discover(root: &Path) -> anyhow::Result<Option<Self>> {
+        for location in LOCATIONS {
+            let path = root.join(location);
+            if path.is_file() {
+                let contents = fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read {}", path.display()))?;
+                return Ok(Some(Self::parse(root.to_owned(), &contents)));
+            }
+        }
+        Ok(None)
+    }
+
+    fn parse(root: PathBuf, contents: &str) -> Self {
+        let rules = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            // GitLab sections, e.g. `[Documentation]`
+            .filter(|line| !line.starts_with('[') && !line.starts_with("^["))
+            .filter_map(|line| {
+                let mut words = line.split_whitespace();
+                let pattern = words.next()?;
+                let owners = words
+                    .take_while(|word| !word.starts_with('#'))
+                    .map(str::to_owned)
+                    .collect();
+                Some(Rule {
+                    pattern: Self::normalize(pattern),
+                    owners,
+                })
+            })
+            .collect();
+        Self { root, rules }
+    }
+
+    /// Convert a gitignore style pattern into a glob anchored at the repository root.
+    fn normalize(pattern: &str) -> String {
+        let mut pattern = if let Some(anchored) = pattern.strip_prefix('/') {
+            anchored.to_owned()
+        } else if pattern.trim_end_matches('/').contains('/') {
+            pattern.to_owned()
+        } else {
+            format!("**/{pattern}")
+        };
+        if pattern.ends_with('/') {
+            pattern.push_str("**");
+        }
+        pattern
+    }
+
+    /// The owners of `file`, as given by the last matching rule.
+    fn owners(&self, file: &Path) -> &[String] {
+        let Ok(relative) = file.strip_prefix(&self.root) else {
+            return &[];
+        };
+        let relative: Vec<_> = relative.iter().filter_map(|part| part.to_str()).collect();
+        let relative = relative.join("/");
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| {
+                glob::matches_path(&rule.pattern, &relative)
+                    || glob::matches_path(&format!("{}/**", rule.pattern), &relative)
+            })
+            .map_or(&[], |rule| &rule.owners)
+    }
+
+    /// The owners of the terraform files in the module at `dir`, matched against the canonical root
+    /// of the repository.
+    pub fn module_owners(&self, dir: &Path) -> Vec<String> {
+        // Symlinks and `..` would otherwise keep it from being within the root
+        let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_owned());
+        let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "tf"))
+            .collect();
+        if files.is_empty() {
+            files.push(dir.join("main.tf"));
+        }
+        let mut owners: Vec<String> = Vec::new();
+        for file in files {
+            for owner in self.owners(&file) {
+                if !owners.contains(owner) {
+                    owners.push(owner.clone());
+                }
+            }
+        }
+        owners
+    }
+}
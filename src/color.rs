@@ -0,0 +1,153 @@
+//! Coloring output, and rendering the tree with its guide lines and module names colored by depth.
+
+use std::{
+    env,
+    fmt::{self, Display, Write as _},
+    io::{self, IsTerminal as _},
+};
+
+use clap::ValueEnum;
+use termtree::Tree;
+
+use crate::{
+    render::{self, Render},
+    TreeNode,
+};
+
+/// The default colors of successive depths.
+pub const DEFAULT_PALETTE: &str = "cyan,green,yellow,magenta,blue,red";
+
+/// The SGR parameters highlighting module names.
+pub const BOLD: &str = "1";
+/// The SGR parameters highlighting `count` and `for_each` instances.
+pub const CYAN: &str = "36";
+/// The SGR parameters highlighting registry sources.
+pub const BLUE: &str = "34";
+/// The SGR parameters highlighting git sources.
+pub const MAGENTA: &str = "35";
+/// The SGR parameters highlighting sources outside the root module's directory.
+pub const YELLOW: &str = "33";
+/// The SGR parameters highlighting the severity of error diagnostics.
+pub const ERROR: &str = "1;31";
+/// The SGR parameters highlighting the severity of warning diagnostics.
+pub const WARNING: &str = "1;33";
+/// The SGR parameters highlighting the lines changed since the last repaint of `watch`.
+pub const CHANGED: &str = "1;32";
+
+/// When to color the output.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Coloring {
+    /// When writing to a terminal and the NO_COLOR environment variable is unset.
+    Auto,
+    /// Always.
+    Always,
+    /// Never.
+    Never,
+}
+
+impl Coloring {
+    /// Whether to color what's written to stdout.
+    pub fn enabled(self) -> bool {
+        match self {
+            Coloring::Auto => io::stdout().is_terminal() && self.allowed(),
+            Coloring::Always => true,
+            Coloring::Never => false,
+        }
+    }
+
+    /// Whether to color what's written to stderr, e.g. diagnostics.
+    pub fn enabled_for_stderr(self) -> bool {
+        match self {
+            Coloring::Auto => io::stderr().is_terminal() && self.allowed(),
+            Coloring::Always => true,
+            Coloring::Never => false,
+        }
+    }
+
+    /// Whether colors asked for by another option, e.g. `--depth-colors`, may be used, even when
+    /// not writing to a terminal.
+    pub fn allowed(self) -> bool {
+        match self {
+            Coloring::Auto => env::var_os("NO_COLOR").is_none(),
+            Coloring::Always => true,
+            Coloring::Never => false,
+        }
+    }
+}
+
+/// Write `text` to `f`, within the ANSI escape sequence selecting the SGR parameters `code` when
+/// `enabled`.
+pub fn styled(
+    f: &mut impl fmt::Write,
+    enabled: bool,
+    code: &str,
+    text: impl Display,
+) -> fmt::Result {
+    if enabled {
+        write!(f, "\x1b[{code}m{text}\x1b[0m")
+    } else {
+        write!(f, "{text}")
+    }
+}
+
+/// A terminal color, as its SGR parameters.
+#[derive(Clone, Debug)]
+pub struct Color(String);
+
+/// Parse a color name, e.g. `cyan` or `bright-red`, or a 256-color index, e.g. `208`.
+pub fn parse(name: &str) -> Result<Color, String> {
+    const NAMES: [&str; 8] = [
+        "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+    ];
+    if let Ok(index) = name.parse::<u8>() {
+        return Ok(Color(format!("38;5;{index}")));
+    }
+    let (base, bright) = match name.strip_prefix("bright-") {
+        Some(base) => (base, true),
+        None => (name, false),
+    };
+    let code = NAMES
+        .iter()
+        .position(|color| *color == base)
+        .ok_or_else(|| format!("unknown color `{name}`, expected a name or a number up to 255"))?;
+    Ok(Color(format!("{}", code + if bright { 90 } else { 30 })))
+}
+
+fn paint(out: &mut String, text: &str, palette: &[Color], depth: usize) {
+    let Color(color) = &palette[depth % palette.len()];
+    let _ = write!(out, "\x1b[{color}m{text}\x1b[0m");
+}
+
+/// Write `node`, coloring its name or instance key.
+fn node(out: &mut String, node: &TreeNode, palette: &[Color], depth: usize) {
+    let text = node.to_string();
+    let chain_len: usize = node
+        .chain
+        .iter()
+        .map(|label| label.len() + " ▸ ".len())
+        .sum();
+    let name_len = match node.instance {
+        Some(_) => text.len(),
+        None => chain_len + node.name.len(),
+    };
+    paint(out, &text[..name_len], palette, depth);
+    out.push_str(&text[name_len..]);
+}
+
+/// The tree drawn like [`Tree`]'s `Display` does, coloring each depth with the next color of
+/// `palette`, which mustn't be empty.
+pub struct DepthColors<'a> {
+    pub palette: &'a [Color],
+}
+
+impl Render for DepthColors<'_> {
+    fn render(&self, tree: &Tree<TreeNode>, out: &mut String) -> anyhow::Result<()> {
+        render::draw(
+            out,
+            tree,
+            &|out, guide, depth| paint(out, guide, self.palette, depth),
+            &|out, tree_node, depth| node(out, tree_node, self.palette, depth),
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+//! Shell completions, which call back into the binary to complete module addresses from the
+//! cached plan.
+
+use std::{
+    env,
+    ffi::OsStr,
+    io,
+    process::{Command, Stdio},
+};
+
+use clap::ValueEnum;
+use clap_complete::{
+    env::{Bash, EnvCompleter, Fish, Zsh},
+    CompletionCandidate,
+};
+
+/// The environment variable through which the registered scripts ask for completions, as read by
+/// `CompleteEnv` in `main`.
+const VAR: &str = "COMPLETE";
+
+/// A shell to generate completions for.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Write the script registering the completions of the program `name` with `shell` to `out`.
+pub fn register(shell: Shell, name: &str, out: &mut dyn io::Write) -> io::Result<()> {
+    let completer: &dyn EnvCompleter = match shell {
+        Shell::Bash => &Bash,
+        Shell::Zsh => &Zsh,
+        Shell::Fish => &Fish,
+    };
+    completer.write_registration(VAR, name, name, name, out)
+}
+
+/// The module addresses starting with `current`, as printed by the `addresses` command, which
+/// reuses the plan of the previous run while the project is unchanged.
+pub fn addresses(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Ok(program) = env::current_exe() else {
+        return Vec::new();
+    };
+    let output = Command::new(program)
+        .arg("addresses")
+        .arg(current)
+        // Print the addresses rather than completing again
+        .env_remove(VAR)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    match output {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(CompletionCandidate::new)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
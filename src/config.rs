@@ -0,0 +1,122 @@
+//! The `.treaform.toml` file of a project: resource budgets and option defaults.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+use serde::Deserialize;
+
+use crate::toml;
+
+/// The name of the configuration file in the project root.
+pub const FILE_NAME: &str = ".treaform.toml";
+
+/// The contents of a `.treaform.toml` file.
+///
+/// Besides budgets, it holds defaults of command line options, which are overridden by the options
+/// given. Relative paths are relative to the project, except `cache_dir`, which is relative to the
+/// file.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Limits on the number of resources within module subtrees.
+    #[serde(default)]
+    pub budgets: Vec<Budget>,
+    /// The default of `--var-file`.
+    pub var_files: Option<Vec<String>>,
+    /// The default of `--binary`.
+    pub binary: Option<PathBuf>,
+    /// The default of `--format`, e.g. `json`.
+    pub format: Option<String>,
+    /// The default of `--color`, e.g. `never`.
+    pub color: Option<String>,
+    /// The default of `--paths`, e.g. `source`.
+    pub paths: Option<String>,
+    /// The default of `--filter`.
+    pub filter: Option<Vec<String>>,
+    /// The default of `--exclude`.
+    pub exclude: Option<Vec<String>>,
+    /// The default of `--cache-dir`.
+    pub cache_dir: Option<PathBuf>,
+    /// The default of `--no-cache`.
+    pub no_cache: Option<bool>,
+}
+
+/// A limit on the number of resources within the subtree of each module whose address matches
+/// `module`: the resource instances of the plan, or the resource blocks without one.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Budget {
+    /// A glob pattern matching module addresses, e.g. `module.sandbox.*`.
+    pub module: String,
+    pub max_resources: usize,
+    #[serde(default)]
+    pub level: Level,
+}
+
+/// How a budget violation is reported.
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    /// Fail after printing the tree.
+    #[default]
+    Error,
+    /// Print a warning.
+    Warn,
+}
+
+impl Config {
+    /// Load the configuration file from the project at `dir`, if there is one.
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        Self::load_file(&dir.join(FILE_NAME))
+    }
+
+    /// The defaults of the project at `dir`, falling back on those of the user's configuration
+    /// file, `$XDG_CONFIG_HOME/treaform/config.toml`.
+    pub fn defaults(dir: &Path) -> anyhow::Result<Self> {
+        let project = Self::load(dir)?;
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .filter(|home| !home.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+        let user = match config_home {
+            Some(config_home) => {
+                Self::load_file(&config_home.join("treaform").join("config.toml"))?
+            }
+            None => Self::default(),
+        };
+        Ok(Config {
+            budgets: project.budgets,
+            var_files: project.var_files.or(user.var_files),
+            binary: project.binary.or(user.binary),
+            format: project.format.or(user.format),
+            color: project.color.or(user.color),
+            paths: project.paths.or(user.paths),
+            filter: project.filter.or(user.filter),
+            exclude: project.exclude.or(user.exclude),
+            cache_dir: project.cache_dir.or(user.cache_dir),
+            no_cache: project.no_cache.or(user.no_cache),
+        })
+    }
+
+    /// Load the configuration file at `path`, if there is one.
+    fn load_file(path: &Path) -> anyhow::Result<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => {
+                return Err(error).with_context(|| format!("failed to read {}", path.display()))
+            }
+        };
+        let value = toml::parse(&contents)
+            .with_context(|| format!("failed to parse {}", path.display()))?;
+        let mut config: Self =
+            serde_json::from_value(value).with_context(|| format!("invalid {}", path.display()))?;
+        if let (Some(cache_dir), Some(parent)) = (&mut config.cache_dir, path.parent()) {
+            *cache_dir = parent.join(&*cache_dir);
+        }
+        Ok(config)
+    }
+}
@@ -0,0 +1,267 @@
+//! The module structure of a project read from its `.tf` files, without planning it.
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+
+use crate::{
+    constraints,
+    hcl::{self, Attribute},
+    installed,
+    plan::{
+        Configuration, CountExpression, ForEachExpression, Module, ModuleCall, ProviderConfig,
+        Resource, Show, Variable,
+    },
+    relative,
+    scan::Scanner,
+    source::{self, Source},
+    TreaformError,
+};
+
+/// A plan of the project at `dir` with only its configuration, read from the `.tf` files of its
+/// modules. Remote modules are read where `terraform init` installed them, unless `local_only`
+/// is set.
+///
+/// Only constant `count` numbers are known, and neither `for_each` keys nor module inputs are.
+pub fn show(dir: &Path, local_only: bool) -> anyhow::Result<Show<'static>> {
+    let installed: HashMap<String, PathBuf> = if local_only {
+        HashMap::new()
+    } else {
+        installed::load(dir)?
+            .into_iter()
+            .map(|module| (module.key, dir.join(module.dir)))
+            .collect()
+    };
+    let mut reader = Reader {
+        root: dir
+            .canonicalize()
+            .with_context(|| format!("failed to resolve {}", dir.display()))?,
+        scanner: Scanner::default(),
+        installed,
+        ancestors: Vec::new(),
+        provider_config: HashMap::new(),
+    };
+    let root_module = reader.module(dir, "")?;
+    Ok(Show {
+        format_version: None,
+        terraform_version: None,
+        configuration: Configuration {
+            provider_config: reader.provider_config,
+            root_module,
+        },
+        resource_changes: Vec::new(),
+        planned_values: None,
+    })
+}
+
+struct Reader {
+    /// The canonical directory of the root module.
+    root: PathBuf,
+    scanner: Scanner,
+    /// The directories of the installed modules, by key.
+    installed: HashMap<String, PathBuf>,
+    /// The canonical directories of the modules being read, to detect modules calling themselves.
+    ancestors: Vec<PathBuf>,
+    /// The providers required or configured by the modules read, by key.
+    provider_config: HashMap<String, ProviderConfig>,
+}
+
+impl Reader {
+    /// The module at `dir`, whose key in the module manifest is `key`.
+    fn module(&mut self, dir: &Path, key: &str) -> anyhow::Result<Module<'static>> {
+        let canonical = dir
+            .canonicalize()
+            .with_context(|| format!("failed to resolve {}", dir.display()))?;
+        if let Some(start) = self.ancestors.iter().position(|dir| *dir == canonical) {
+            let cycle = self.ancestors[start..]
+                .iter()
+                .chain([&canonical])
+                .map(|dir| match relative(dir, &self.root) {
+                    path if path.as_os_str().is_empty() => "./".to_owned(),
+                    path if path.starts_with("..") => path.display().to_string(),
+                    path => format!("./{}", path.display()),
+                })
+                .collect();
+            return Err(TreaformError::ModuleCycle { cycle }.into());
+        }
+        let body = self.scanner.module(&canonical)?;
+        let module_address =
+            (!key.is_empty()).then(|| format!("module.{}", key.replace('.', ".module.")));
+        let mut providers: Vec<(String, Option<String>, Option<String>)> =
+            constraints::required_providers(body)
+                .into_iter()
+                .map(|(name, _, version)| (name, None, version))
+                .collect();
+        for block in body.blocks("provider") {
+            let Some(name) = block.labels.first() else {
+                continue;
+            };
+            let alias = block
+                .body
+                .attribute("alias")
+                .and_then(|alias| hcl::string_literal(&alias.expression));
+            providers.push((name.clone(), alias, None));
+        }
+        for (name, alias, version_constraint) in providers {
+            let local = match &alias {
+                Some(alias) => format!("{name}.{alias}"),
+                None => name.clone(),
+            };
+            let key = match &module_address {
+                Some(address) => format!("{address}:{local}"),
+                None => local,
+            };
+            let config = self
+                .provider_config
+                .entry(key)
+                .or_insert_with(|| ProviderConfig {
+                    name,
+                    alias,
+                    module_address: module_address.clone(),
+                    version_constraint: None,
+                });
+            config.version_constraint = config.version_constraint.take().or(version_constraint);
+        }
+        let variables = body
+            .blocks("variable")
+            .filter_map(|block| {
+                let sensitive = block
+                    .body
+                    .attribute("sensitive")
+                    .is_some_and(|attribute| attribute.expression.trim() == "true");
+                Some((block.labels.first()?.clone(), Variable { sensitive }))
+            })
+            .collect();
+        let resources = body
+            .blocks
+            .iter()
+            .filter(|block| block.kind == "resource" || block.kind == "data")
+            .filter_map(|block| {
+                // The provider is either configured, e.g. `aws.west`, or implied by the type
+                let (kind, name) = (block.labels.first()?, block.labels.get(1)?);
+                let provider = match block.body.attribute("provider") {
+                    Some(provider) => provider.expression.trim().to_owned(),
+                    None => kind
+                        .split_once('_')
+                        .map_or(kind.as_str(), |(name, _)| name)
+                        .to_owned(),
+                };
+                let prefix = if block.kind == "data" { "data." } else { "" };
+                Some(Resource {
+                    address: Cow::Owned(format!("{prefix}{kind}.{name}")),
+                    provider_config_key: Cow::Owned(provider),
+                })
+            })
+            .collect();
+        let mut calls = Vec::new();
+        for block in body.blocks("module") {
+            let (Some(name), Some(source)) = (
+                block.labels.first(),
+                block
+                    .body
+                    .attribute("source")
+                    .and_then(|source| hcl::string_literal(&source.expression)),
+            ) else {
+                continue;
+            };
+            let attribute = |name: &str| block.body.attribute(name).map(|a| a.expression.as_str());
+            calls.push((
+                name.clone(),
+                source,
+                attribute("version").and_then(hcl::string_literal),
+                attribute("count").map(|count| CountExpression {
+                    constant_value: count.trim().parse().ok(),
+                    references: hcl::references(count),
+                }),
+                attribute("for_each").map(|for_each| ForEachExpression {
+                    constant_value: None,
+                    references: hcl::references(for_each),
+                    sensitive: false,
+                }),
+                attribute("depends_on")
+                    .and_then(hcl::list_items)
+                    .unwrap_or_default(),
+                expressions(&block.body.attributes),
+            ));
+        }
+
+        self.ancestors.push(canonical.clone());
+        let mut module_calls = HashMap::new();
+        for (name, source, version, count, for_each, depends_on, expressions) in calls {
+            let child_key = if key.is_empty() {
+                name.clone()
+            } else {
+                format!("{key}.{name}")
+            };
+            let child_dir = match source::parse(&source) {
+                Source::Local(path) => Some(canonical.join(path)),
+                _ => self.installed.get(&child_key).cloned(),
+            };
+            let module = match child_dir {
+                Some(child_dir) => self.module(&child_dir, &child_key)?,
+                None => Module {
+                    module_calls: None,
+                    resources: Vec::new(),
+                    variables: HashMap::new(),
+                },
+            };
+            module_calls.insert(
+                Cow::Owned(name),
+                ModuleCall {
+                    module,
+                    source: Cow::Owned(source),
+                    expressions,
+                    depends_on,
+                    version_constraint: version,
+                    count_expression: count,
+                    for_each_expression: for_each,
+                },
+            );
+        }
+        self.ancestors.pop();
+        Ok(Module {
+            module_calls: Some(module_calls),
+            resources,
+            variables,
+        })
+    }
+}
+
+/// The input variables of a `module` block with `attributes`, as the expressions of the plan
+/// JSON: constant strings, numbers and booleans, and otherwise what they refer to.
+fn expressions(attributes: &[Attribute]) -> serde_json::Value {
+    const META_ARGUMENTS: [&str; 6] = [
+        "source",
+        "version",
+        "count",
+        "for_each",
+        "depends_on",
+        "providers",
+    ];
+    let mut expressions = serde_json::Map::new();
+    for attribute in attributes {
+        if META_ARGUMENTS.contains(&attribute.name.as_str()) {
+            continue;
+        }
+        let references = hcl::references(&attribute.expression);
+        let constant = match hcl::string_literal(&attribute.expression) {
+            Some(string) => Some(serde_json::Value::String(string)),
+            None if references.is_empty() => {
+                serde_json::from_str::<serde_json::Value>(attribute.expression.trim())
+                    .ok()
+                    .filter(|value| value.is_number() || value.is_boolean())
+            }
+            None => None,
+        };
+        let expression = match constant {
+            Some(value) => serde_json::json!({ "constant_value": value }),
+            None => serde_json::json!({ "references": references }),
+        };
+        expressions.insert(attribute.name.clone(), expression);
+    }
+    serde_json::Value::Object(expressions)
+}
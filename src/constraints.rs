@@ -0,0 +1,359 @@
+//! Explaining how the version constraints of every module on a provider combine, and whether the
+//! version in the dependency lock file satisfies them.
+
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    fmt::{self, Write as _},
+    fs, io,
+    path::Path,
+};
+
+use anyhow::Context as _;
+
+use crate::{
+    hcl::{self, Body},
+    installed,
+    scan::Scanner,
+};
+
+/// A version, compared by its numeric segments, e.g. `4.67.0`.
+#[derive(Clone, Debug)]
+pub struct Version(Vec<u64>);
+
+impl Version {
+    pub fn parse(text: &str) -> Option<Self> {
+        // Pre-release and build suffixes are ignored
+        let text = text.trim().trim_start_matches('v');
+        let text = text.split(['-', '+']).next()?;
+        let segments = text
+            .split('.')
+            .map(|segment| segment.parse().ok())
+            .collect::<Option<Vec<u64>>>()?;
+        Some(Version(segments))
+    }
+
+    fn segment(&self, index: usize) -> u64 {
+        self.0.get(index).copied().unwrap_or_default()
+    }
+
+    pub fn compare(&self, other: &Version) -> Ordering {
+        let len = self.0.len().max(other.0.len());
+        (0..len)
+            .map(|index| self.segment(index).cmp(&other.segment(index)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Pad to the usual three segments, e.g. `5.0.0` for the limit of `~> 4.1`
+        let len = self.0.len().max(3);
+        let segments: Vec<String> = (0..len)
+            .map(|index| self.segment(index).to_string())
+            .collect();
+        f.write_str(&segments.join("."))
+    }
+}
+
+/// A single requirement of a constraint, e.g. `~> 4.0`.
+struct Requirement {
+    operator: &'static str,
+    version: Version,
+}
+
+impl Requirement {
+    fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let operator = ["~>", ">=", "<=", "!=", ">", "<", "="]
+            .into_iter()
+            .find(|operator| text.starts_with(operator))
+            .unwrap_or("=");
+        let version = Version::parse(text.strip_prefix(operator).unwrap_or(text))?;
+        Some(Requirement { operator, version })
+    }
+
+    fn allows(&self, version: &Version) -> bool {
+        let ordering = version.compare(&self.version);
+        match self.operator {
+            "=" => ordering.is_eq(),
+            "!=" => ordering.is_ne(),
+            ">" => ordering.is_gt(),
+            ">=" => ordering.is_ge(),
+            "<" => ordering.is_lt(),
+            "<=" => ordering.is_le(),
+            _ => ordering.is_ge() && version.compare(&self.pessimistic_limit()).is_lt(),
+        }
+    }
+
+    /// The exclusive upper bound of `~>`, incrementing the second to last segment given, e.g.
+    /// `5.0` for `~> 4.1` and `4.2` for `~> 4.1.0`.
+    fn pessimistic_limit(&self) -> Version {
+        let mut segments = self.version.0.clone();
+        if segments.len() > 1 {
+            segments.pop();
+        }
+        if let Some(last) = segments.last_mut() {
+            *last += 1;
+        }
+        Version(segments)
+    }
+
+    /// The upper bound, and whether it's inclusive.
+    fn upper_bound(&self) -> Option<(Version, bool)> {
+        match self.operator {
+            "=" | "<=" => Some((self.version.clone(), true)),
+            "<" => Some((self.version.clone(), false)),
+            "~>" => Some((self.pessimistic_limit(), false)),
+            _ => None,
+        }
+    }
+}
+
+/// Whether `version` satisfies the constraint `text`, e.g. `~> 4.0, != 4.2.0`, or `None` if the
+/// constraint is invalid.
+pub fn allows(text: &str, version: &Version) -> Option<bool> {
+    let requirements = text
+        .split(',')
+        .map(Requirement::parse)
+        .collect::<Option<Vec<_>>>()?;
+    Some(
+        requirements
+            .iter()
+            .all(|requirement| requirement.allows(version)),
+    )
+}
+
+/// A version constraint, as declared by one module.
+struct Constraint {
+    /// The module's address, or `root module`.
+    module: String,
+    /// The module's directory, relative to the root module.
+    dir: String,
+    text: String,
+    requirements: Vec<Requirement>,
+}
+
+impl Constraint {
+    fn allows(&self, version: &Version) -> bool {
+        self.requirements
+            .iter()
+            .all(|requirement| requirement.allows(version))
+    }
+
+    /// The tightest upper bound, and whether it's inclusive.
+    fn upper_bound(&self) -> Option<(Version, bool)> {
+        self.requirements
+            .iter()
+            .filter_map(Requirement::upper_bound)
+            .min_by(|(a, a_inclusive), (b, b_inclusive)| {
+                a.compare(b).then(a_inclusive.cmp(b_inclusive))
+            })
+    }
+}
+
+/// The full source address of a provider, e.g. `registry.terraform.io/hashicorp/aws` for
+/// `hashicorp/aws`, or for the local name `aws` when no source is given.
+fn full_source(local_name: &str, source: Option<&str>) -> String {
+    let source = source.map_or_else(|| format!("hashicorp/{local_name}"), str::to_lowercase);
+    match source.split('/').count() {
+        2 => format!("registry.terraform.io/{source}"),
+        _ => source,
+    }
+}
+
+/// The providers in the `required_providers` blocks of a module's `body`, as their local name,
+/// source address and version constraint.
+pub fn required_providers(body: &Body) -> Vec<(String, Option<String>, Option<String>)> {
+    let mut providers = Vec::new();
+    for required in body
+        .blocks("terraform")
+        .flat_map(|block| block.body.blocks("required_providers"))
+    {
+        for attribute in &required.body.attributes {
+            // Either `{ source = "...", version = "..." }` or, before terraform 0.13, a version
+            let (source, version) = match hcl::object_entries(&attribute.expression) {
+                Some(entries) => {
+                    let entry = |key: &str| {
+                        entries
+                            .iter()
+                            .find(|(name, _)| name == key)
+                            .and_then(|(_, value)| hcl::string_literal(value))
+                    };
+                    (entry("source"), entry("version"))
+                }
+                None => (None, hcl::string_literal(&attribute.expression)),
+            };
+            providers.push((attribute.name.clone(), source, version));
+        }
+    }
+    providers
+}
+
+/// Print the version constraints on every provider required by the modules of the project at
+/// `dir`, the binding constraint and whether the locked version satisfies them all.
+pub fn run(dir: &Path) -> anyhow::Result<()> {
+    let modules = installed::load(dir)?;
+    if modules.is_empty() {
+        anyhow::bail!(
+            "no modules are installed in {}, run `terraform init` first",
+            dir.display()
+        )
+    }
+    let mut scanner = Scanner::default();
+    let mut providers: BTreeMap<String, Vec<Constraint>> = BTreeMap::new();
+    for module in &modules {
+        let module_dir = dir.join(&module.dir);
+        let (address, shown_dir) = if module.key.is_empty() {
+            ("root module".to_owned(), "./".to_owned())
+        } else {
+            (
+                format!("module.{}", module.key.replace('.', ".module.")),
+                format!("./{}", module.dir.display()),
+            )
+        };
+        let body = scanner.module(&module_dir)?;
+        let mut declared: Vec<(String, String)> = Vec::new();
+        for (name, source, version) in required_providers(body) {
+            let source = full_source(&name, source.as_deref());
+            providers.entry(source.clone()).or_default();
+            if let Some(version) = version {
+                declared.push((source, version));
+            }
+        }
+        // The deprecated `version` argument of provider configurations
+        for provider in body.blocks("provider") {
+            let version = provider
+                .body
+                .attribute("version")
+                .and_then(|version| hcl::string_literal(&version.expression));
+            if let (Some(name), Some(version)) = (provider.labels.first(), version) {
+                declared.push((full_source(name, None), version));
+            }
+        }
+        for (source, text) in declared {
+            let requirements = text
+                .split(',')
+                .map(|requirement| {
+                    Requirement::parse(requirement).with_context(|| {
+                        format!(
+                            "invalid version constraint `{text}` in {}",
+                            module_dir.display()
+                        )
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?;
+            providers.entry(source).or_default().push(Constraint {
+                module: address.clone(),
+                dir: shown_dir.clone(),
+                text,
+                requirements,
+            });
+        }
+    }
+
+    let locked = lock_file(dir)?;
+    let mut out = String::new();
+    let mut unsatisfied = 0;
+    for (source, constraints) in &providers {
+        let _ = writeln!(out, "{source}");
+        if constraints.is_empty() {
+            out.push_str("  no version constraints\n");
+        }
+        for constraint in constraints {
+            let _ = writeln!(
+                out,
+                "  {} in {} ({})",
+                constraint.text, constraint.module, constraint.dir
+            );
+        }
+        let bounds: Vec<(&Constraint, (Version, bool))> = constraints
+            .iter()
+            .filter_map(|constraint| Some((constraint, constraint.upper_bound()?)))
+            .collect();
+        let tightest =
+            bounds
+                .iter()
+                .map(|(_, bound)| bound)
+                .min_by(|(a, a_inclusive), (b, b_inclusive)| {
+                    a.compare(b).then(a_inclusive.cmp(b_inclusive))
+                });
+        match tightest {
+            Some((limit, inclusive)) => {
+                let binding: Vec<String> = bounds
+                    .iter()
+                    .filter(|(_, (version, bound_inclusive))| {
+                        version.compare(limit).is_eq() && bound_inclusive == inclusive
+                    })
+                    .map(|(constraint, _)| {
+                        format!("`{}` in {}", constraint.text, constraint.module)
+                    })
+                    .collect();
+                let relation = if *inclusive { "up to" } else { "below" };
+                let _ = writeln!(
+                    out,
+                    "  binding: {}, allowing versions {relation} {limit}",
+                    binding.join(" and ")
+                );
+            }
+            None if !constraints.is_empty() => out.push_str("  binding: none, no upper bound\n"),
+            None => {}
+        }
+        match locked.get(source) {
+            Some(version) => {
+                let violated: Vec<String> = Version::parse(version)
+                    .map(|parsed| {
+                        constraints
+                            .iter()
+                            .filter(|constraint| !constraint.allows(&parsed))
+                            .map(|constraint| {
+                                format!("`{}` in {}", constraint.text, constraint.module)
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                if violated.is_empty() {
+                    let _ = writeln!(out, "  locked: {version}, satisfying every constraint");
+                } else {
+                    unsatisfied += 1;
+                    let _ = writeln!(
+                        out,
+                        "  locked: {version}, violating {}",
+                        violated.join(" and ")
+                    );
+                }
+            }
+            None => out.push_str("  locked: not in .terraform.lock.hcl\n"),
+        }
+    }
+    print!("{out}");
+    if unsatisfied > 0 {
+        anyhow::bail!("{unsatisfied} provider(s) locked at versions violating their constraints")
+    }
+    Ok(())
+}
+
+/// The versions selected in the dependency lock file of the project at `dir`, by provider source.
+fn lock_file(dir: &Path) -> anyhow::Result<BTreeMap<String, String>> {
+    let path = dir.join(".terraform.lock.hcl");
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(error) => {
+            return Err(error).with_context(|| format!("failed to read {}", path.display()))
+        }
+    };
+    let body =
+        hcl::parse(&contents).with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(body
+        .blocks("provider")
+        .filter_map(|provider| {
+            let version = provider.body.attribute("version")?;
+            Some((
+                provider.labels.first()?.to_lowercase(),
+                hcl::string_literal(&version.expression)?,
+            ))
+        })
+        .collect())
+}
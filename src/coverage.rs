@@ -0,0 +1,108 @@
+//! Which modules are exercised by native terraform tests, in `.tftest.hcl` files.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+use termtree::Tree;
+
+use crate::{hcl, intern::Interner, TreeNode};
+
+/// The directories of a module holding its test files: the module itself and its `tests`
+/// directory.
+const TEST_DIRS: [&str; 2] = [".", "tests"];
+
+/// Count the `run` blocks exercising each directory, for the test files of the modules at `dirs`.
+///
+/// A run exercises the module whose tests it's in, unless it has a `module` block selecting
+/// another, e.g. a setup module. The counts are keyed by canonical directory.
+pub fn runs(dirs: &[PathBuf]) -> anyhow::Result<HashMap<PathBuf, usize>> {
+    let mut runs = HashMap::new();
+    for dir in dirs {
+        for files_dir in TEST_DIRS.map(|test_dir| dir.join(test_dir)) {
+            let entries = match fs::read_dir(&files_dir) {
+                Ok(entries) => entries,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                Err(error) => {
+                    return Err(error)
+                        .with_context(|| format!("failed to read {}", files_dir.display()))
+                }
+            };
+            let mut files: Vec<PathBuf> = entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.to_string_lossy().ends_with(".tftest.hcl"))
+                .collect();
+            files.sort();
+            for file in files {
+                let source = fs::read_to_string(&file)
+                    .with_context(|| format!("failed to read {}", file.display()))?;
+                let body = hcl::parse(&source)
+                    .with_context(|| format!("failed to parse {}", file.display()))?;
+                for run in body.blocks("run") {
+                    // Module sources in tests are relative to the module under test
+                    let exercised = run
+                        .body
+                        .blocks("module")
+                        .next()
+                        .and_then(|module| module.body.attribute("source"))
+                        .and_then(|source| hcl::string_literal(&source.expression))
+                        .filter(|source| source.starts_with('.'))
+                        .map_or_else(|| dir.clone(), |source| dir.join(source));
+                    let exercised = exercised.canonicalize().unwrap_or(exercised);
+                    *runs.entry(exercised).or_default() += 1;
+                }
+            }
+        }
+    }
+    Ok(runs)
+}
+
+/// Mark each module of `tree`, whose sources are relative to `base`, with the number of test
+/// runs exercising it.
+pub fn annotate(
+    tree: &mut Tree<TreeNode>,
+    base: &Path,
+    runs: &HashMap<PathBuf, usize>,
+    interner: &mut Interner,
+) {
+    let dir = base.join(&*tree.root.source);
+    let dir = dir.canonicalize().unwrap_or(dir);
+    let count = match runs.get(&dir) {
+        // The tests of modules which aren't installed are unknown
+        None if tree.root.unresolved => None,
+        count => Some(count.copied().unwrap_or(0)),
+    };
+    if let Some(count) = count {
+        tree.root.markers.push(match count {
+            0 => interner.str("untested"),
+            1 => interner.str("1 test run"),
+            count => interner.str(&format!("{count} test runs")),
+        });
+    }
+    tree.root.tests = count;
+    for leaf in &mut tree.leaves {
+        annotate(leaf, base, runs, interner);
+    }
+}
+
+/// Collect the entries of the modules of `tree` which `annotate` found untested, as shown.
+pub fn untested(tree: &Tree<TreeNode>, untested: &mut Vec<String>) {
+    if tree.root.tests == Some(0) {
+        let address = if tree.root.address.is_empty() {
+            "root module"
+        } else {
+            &tree.root.address
+        };
+        untested.push(match tree.root.location() {
+            Some(location) => format!("{address} ({location})"),
+            None => address.to_owned(),
+        });
+    }
+    for leaf in &tree.leaves {
+        self::untested(leaf, untested);
+    }
+}
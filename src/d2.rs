@@ -0,0 +1,38 @@
+//! Rendering the tree as a D2 diagram, e.g. for `d2 modules.d2 modules.svg`.
+
+use std::fmt::Write as _;
+
+use termtree::Tree;
+
+use crate::{
+    render::{self, Render},
+    TreeNode,
+};
+
+/// A diagram with a connection from each module to the modules it calls, labelled with their names
+/// and with their locations as tooltips.
+pub struct D2;
+
+impl Render for D2 {
+    fn render(&self, tree: &Tree<TreeNode>, out: &mut String) -> anyhow::Result<()> {
+        out.push_str("direction: down\n");
+        render::graph(
+            out,
+            tree,
+            &|out, id, label, node| {
+                write!(out, "n{id}: {}", escape(label))?;
+                if let Some(location) = node.location() {
+                    write!(out, " {{ tooltip: {} }}", escape(&location))?;
+                }
+                writeln!(out)
+            },
+            &|out, id, child| writeln!(out, "n{id} -> n{child}"),
+        )?;
+        Ok(())
+    }
+}
+
+/// `text` as a double-quoted D2 string.
+fn escape(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
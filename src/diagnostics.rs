@@ -0,0 +1,121 @@
+//! The diagnostics of the machine-readable log of `terraform plan -json`, rendered concisely.
+
+use std::fmt::Write as _;
+
+use serde::Deserialize;
+
+use crate::color;
+
+/// A message of the machine-readable log, of which only diagnostics are of interest.
+#[derive(Deserialize)]
+struct Message {
+    diagnostic: Option<Diagnostic>,
+}
+
+/// A diagnostic reported by terraform.
+#[derive(Deserialize)]
+pub struct Diagnostic {
+    severity: String,
+    summary: String,
+    #[serde(default)]
+    detail: String,
+    range: Option<Range>,
+    snippet: Option<Snippet>,
+}
+
+/// The source range a diagnostic refers to.
+#[derive(Deserialize)]
+struct Range {
+    filename: String,
+    start: Position,
+}
+
+#[derive(Deserialize)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+/// The source code around a diagnostic's range.
+#[derive(Deserialize)]
+struct Snippet {
+    /// The block containing the range, e.g. `resource "aws_s3_bucket" "logs"`.
+    context: Option<String>,
+    code: String,
+    start_line: usize,
+}
+
+impl Diagnostic {
+    fn is_error(&self) -> bool {
+        self.severity == "error"
+    }
+}
+
+/// The diagnostics of the log.
+pub fn parse(log: &str) -> Vec<Diagnostic> {
+    log.lines()
+        .filter_map(|line| serde_json::from_str::<Message>(line).ok())
+        .filter_map(|message| message.diagnostic)
+        .collect()
+}
+
+/// `diagnostics` in the human readable form of `terraform plan -no-color`.
+pub fn plain(diagnostics: &[Diagnostic]) -> String {
+    let mut out = String::new();
+    for diagnostic in diagnostics {
+        let severity = if diagnostic.is_error() {
+            "Error"
+        } else {
+            "Warning"
+        };
+        let _ = writeln!(out, "{severity}: {}\n", diagnostic.summary);
+        if !diagnostic.detail.is_empty() {
+            let _ = writeln!(out, "{}\n", diagnostic.detail);
+        }
+    }
+    out
+}
+
+/// A concise report of the errors among `diagnostics`, with their locations and source lines,
+/// followed by the number of warnings which aren't shown.
+pub fn report(diagnostics: &[Diagnostic], highlight: bool) -> String {
+    let mut out = String::new();
+    for diagnostic in diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.is_error())
+    {
+        let _ = color::styled(&mut out, highlight, color::ERROR, "error");
+        let _ = writeln!(out, ": {}", diagnostic.summary);
+        if let Some(range) = &diagnostic.range {
+            out.push_str("  --> ");
+            let location = format!(
+                "{}:{}:{}",
+                range.filename, range.start.line, range.start.column
+            );
+            let _ = color::styled(&mut out, highlight, color::CYAN, location);
+            if let Some(context) = diagnostic
+                .snippet
+                .as_ref()
+                .and_then(|snippet| snippet.context.as_ref())
+            {
+                let _ = write!(out, ", in {context}");
+            }
+            out.push('\n');
+        }
+        if let Some(snippet) = &diagnostic.snippet {
+            for (line, code) in (snippet.start_line..).zip(snippet.code.lines()) {
+                let _ = writeln!(out, "{line:>6} │ {code}");
+            }
+        }
+        for line in diagnostic.detail.lines() {
+            let _ = writeln!(out, "  {line}");
+        }
+        out.push('\n');
+    }
+    let warnings = diagnostics.iter().filter(|d| !d.is_error()).count();
+    if warnings > 0 {
+        let _ = color::styled(&mut out, highlight, color::WARNING, "warning");
+        let _ = writeln!(out, ": {warnings} warning(s) not shown");
+    }
+    out
+}
@@ -0,0 +1,510 @@
+//! The structural differences between the module calls of two configurations.
+
+use std::{collections::BTreeMap, fmt::Write as _, fs, path::Path};
+
+use anyhow::Context as _;
+use serde_json::Value;
+
+use crate::{
+    configuration,
+    glyph::{Glyphs, Kind},
+    plan::{Module, Show},
+};
+
+/// How the differences are shown.
+#[derive(Clone, Copy, Debug)]
+pub enum View {
+    /// One line per difference.
+    List,
+    /// Both module trees side by side.
+    SideBySide,
+    /// A single module tree of both configurations.
+    Tree,
+}
+
+/// A module call, flattened out of the configuration.
+pub struct Call<'a> {
+    pub source: &'a str,
+    pub inputs: &'a Value,
+}
+
+/// A structural difference between two configurations.
+pub enum Difference<'a> {
+    Added(&'a str),
+    Removed(&'a str),
+    Resourced {
+        address: &'a str,
+        old: &'a str,
+        new: &'a str,
+    },
+    Renamed {
+        old: &'a str,
+        new: &'a str,
+    },
+}
+
+/// Collect every module call beneath `module`, keyed by address.
+fn flatten<'a>(module: &'a Module, parent: &str, calls: &mut BTreeMap<String, Call<'a>>) {
+    for (name, call) in module.module_calls.iter().flatten() {
+        let address = if parent.is_empty() {
+            format!("module.{name}")
+        } else {
+            format!("{parent}.module.{name}")
+        };
+        flatten(&call.module, &address, calls);
+        calls.insert(
+            address,
+            Call {
+                source: &call.source,
+                inputs: &call.expressions,
+            },
+        );
+    }
+}
+
+/// Compare the module calls of two configurations.
+///
+/// Modules which were removed and added with the same source and inputs are reported as renames,
+/// along with their descendants.
+pub fn differences<'a>(
+    old: &'a BTreeMap<String, Call>,
+    new: &'a BTreeMap<String, Call>,
+) -> Vec<Difference<'a>> {
+    let mut removed: Vec<&str> = old
+        .keys()
+        .filter(|address| !new.contains_key(*address))
+        .map(String::as_str)
+        .collect();
+    let mut added: Vec<&str> = new
+        .keys()
+        .filter(|address| !old.contains_key(*address))
+        .map(String::as_str)
+        .collect();
+
+    // Match renames, outermost modules first so that descendants follow their ancestors
+    removed.sort_by_key(|address| (address.matches(".module.").count(), *address));
+    let mut differences = Vec::new();
+    let mut index = 0;
+    while index < removed.len() {
+        let old_address = removed[index];
+        let old_call = &old[old_address];
+        let Some(position) = added.iter().position(|address| {
+            let new_call = &new[*address];
+            new_call.source == old_call.source && new_call.inputs == old_call.inputs
+        }) else {
+            index += 1;
+            continue;
+        };
+        let new_address = added.remove(position);
+        removed.remove(index);
+        differences.push(Difference::Renamed {
+            old: old_address,
+            new: new_address,
+        });
+        // Descendants moved along with the renamed module
+        removed.retain(|address| {
+            let Some(rest) = address.strip_prefix(old_address) else {
+                return true;
+            };
+            let moved = format!("{new_address}{rest}");
+            match added.iter().position(|address| **address == moved) {
+                Some(position) if rest.starts_with('.') => {
+                    added.remove(position);
+                    false
+                }
+                _ => true,
+            }
+        });
+    }
+
+    differences.extend(removed.into_iter().map(Difference::Removed));
+    differences.extend(added.into_iter().map(Difference::Added));
+    for (address, old_call) in old {
+        if let Some(new_call) = new.get(address) {
+            if new_call.source != old_call.source {
+                differences.push(Difference::Resourced {
+                    address,
+                    old: old_call.source,
+                    new: new_call.source,
+                });
+            }
+        }
+    }
+    differences.sort_by_key(|difference| match difference {
+        Difference::Added(address)
+        | Difference::Removed(address)
+        | Difference::Resourced { address, .. }
+        | Difference::Renamed { new: address, .. } => *address,
+    });
+    differences
+}
+
+/// A line of the side-by-side view, showing a module call in either configuration or both.
+struct Row {
+    old: Option<String>,
+    new: Option<String>,
+    /// `<` for removed modules, `>` for added ones, `|` for renamed or re-sourced ones, as with
+    /// `diff --side-by-side`.
+    gutter: char,
+    /// The line of the combined tree, showing both the old and new name and source where they
+    /// differ.
+    merged: String,
+}
+
+/// The address of the module calling the module at `address`, empty for the root module.
+fn parent(address: &str) -> &str {
+    address
+        .rfind(".module.")
+        .map_or("", |index| &address[..index])
+}
+
+/// The name of the module call at `address`.
+fn name(address: &str) -> &str {
+    address.rsplit("module.").next().unwrap_or(address)
+}
+
+/// Collect the rows of the module calls beneath `old` and `new`, a pair of matching modules of
+/// which either may be missing, aligning their children by name or rename.
+fn align(
+    (old_calls, new_calls): (&BTreeMap<String, Call>, &BTreeMap<String, Call>),
+    renames: &[(&str, &str)],
+    (old, new): (Option<&str>, Option<&str>),
+    prefix: &str,
+    glyphs: Glyphs,
+    out: &mut Vec<Row>,
+) {
+    let children =
+        |calls: &'_ BTreeMap<String, Call>, parent_address: Option<&str>| -> Vec<String> {
+            calls
+                .keys()
+                .filter(|address| Some(parent(address)) == parent_address)
+                .cloned()
+                .collect()
+        };
+    let mut new_children = children(new_calls, new);
+    let mut pairs: Vec<(Option<String>, Option<String>)> = Vec::new();
+    for old_child in children(old_calls, old) {
+        let renamed = renames
+            .iter()
+            .find(|(old, _)| *old == old_child)
+            .map(|(_, new)| *new);
+        let position = new_children.iter().position(|new_child| {
+            renamed.map_or(name(new_child) == name(&old_child), |renamed| {
+                new_child == renamed
+            })
+        });
+        let new_child = position.map(|position| new_children.remove(position));
+        pairs.push((Some(old_child), new_child));
+    }
+    pairs.extend(
+        new_children
+            .into_iter()
+            .map(|new_child| (None, Some(new_child))),
+    );
+    pairs.sort_by(|a, b| {
+        let key = |pair: &(Option<String>, Option<String>)| {
+            pair.1.clone().or(pair.0.clone()).unwrap_or_default()
+        };
+        name(&key(a)).cmp(name(&key(b)))
+    });
+
+    let count = pairs.len();
+    for (index, (old_child, new_child)) in pairs.into_iter().enumerate() {
+        let last = index + 1 == count;
+        let connector = if last { "└── " } else { "├── " };
+        let label = |address: &str, calls: &BTreeMap<String, Call>| {
+            format!(
+                "{prefix}{connector}{} ({})",
+                name(address),
+                calls[address].source
+            )
+        };
+        let gutter = match (&old_child, &new_child) {
+            (Some(_), None) => '<',
+            (None, Some(_)) => '>',
+            (Some(old), Some(new))
+                if name(old) != name(new) || old_calls[old].source != new_calls[new].source =>
+            {
+                '|'
+            }
+            _ => ' ',
+        };
+        let merged = match (&old_child, &new_child) {
+            (Some(old), Some(new)) => {
+                let names = match (name(old), name(new)) {
+                    (old, new) if old == new => new.to_owned(),
+                    (old, new) => format!("{old} → {new}"),
+                };
+                let sources = match (old_calls[old].source, new_calls[new].source) {
+                    (old, new) if old == new => new.to_owned(),
+                    (old, new) => format!("{old} → {new}"),
+                };
+                let glyph = match gutter {
+                    '|' => glyphs.prefix(Kind::Update),
+                    _ => "",
+                };
+                format!("{prefix}{connector}{glyph}{names} ({sources})")
+            }
+            (Some(old), None) => format!(
+                "{prefix}{connector}{}{} ({})",
+                glyphs.prefix(Kind::Delete),
+                name(old),
+                old_calls[old].source
+            ),
+            (None, Some(new)) => format!(
+                "{prefix}{connector}{}{} ({})",
+                glyphs.prefix(Kind::Create),
+                name(new),
+                new_calls[new].source
+            ),
+            (None, None) => unreachable!("rows pair at least one module"),
+        };
+        out.push(Row {
+            old: old_child.as_deref().map(|old| label(old, old_calls)),
+            new: new_child.as_deref().map(|new| label(new, new_calls)),
+            gutter,
+            merged,
+        });
+        let child_prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+        align(
+            (old_calls, new_calls),
+            renames,
+            (old_child.as_deref(), new_child.as_deref()),
+            &child_prefix,
+            glyphs,
+            out,
+        );
+    }
+}
+
+/// The rows of the module trees of both configurations, aligned by matching modules.
+fn rows(
+    old_calls: &BTreeMap<String, Call>,
+    new_calls: &BTreeMap<String, Call>,
+    differences: &[Difference],
+    glyphs: Glyphs,
+) -> Vec<Row> {
+    let renames: Vec<(&str, &str)> = differences
+        .iter()
+        .filter_map(|difference| match difference {
+            Difference::Renamed { old, new } => Some((*old, *new)),
+            _ => None,
+        })
+        .collect();
+    let mut all = vec![Row {
+        old: Some("*".to_owned()),
+        new: Some("*".to_owned()),
+        gutter: ' ',
+        merged: "*".to_owned(),
+    }];
+    align(
+        (old_calls, new_calls),
+        &renames,
+        (Some(""), Some("")),
+        "",
+        glyphs,
+        &mut all,
+    );
+    all
+}
+
+/// Wrap `text` in the ANSI escape sequence selecting the SGR parameter `code` when `color` is set.
+fn paint(text: &str, code: &str, color: bool) -> String {
+    if color && !text.is_empty() && !code.is_empty() {
+        format!("\x1b[{code}m{text}\x1b[0m")
+    } else {
+        text.to_owned()
+    }
+}
+
+/// Render the module trees of both configurations side by side.
+fn side_by_side(rows: &[Row], color: bool) -> String {
+    let width = rows
+        .iter()
+        .filter_map(|row| row.old.as_ref())
+        .map(|old| old.chars().count())
+        .max()
+        .unwrap_or_default();
+    let mut out = String::new();
+    for row in rows {
+        let old = row.old.as_deref().unwrap_or_default();
+        let new = row.new.as_deref().unwrap_or_default();
+        let (old_code, new_code) = match row.gutter {
+            '<' => ("31", ""),
+            '>' => ("", "32"),
+            '|' => ("33", "33"),
+            _ => ("", ""),
+        };
+        let padding = " ".repeat(width - old.chars().count());
+        let line = format!(
+            "{}{padding} {} {}",
+            paint(old, old_code, color),
+            row.gutter,
+            paint(new, new_code, color)
+        );
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Render a single module tree of both configurations, with added modules in green, removed ones
+/// in red, and renamed or re-sourced ones in yellow.
+fn combined(rows: &[Row], color: bool) -> String {
+    let mut out = String::new();
+    for row in rows {
+        let code = match row.gutter {
+            '<' => "31",
+            '>' => "32",
+            '|' => "33",
+            _ => "",
+        };
+        out.push_str(&paint(&row.merged, code, color));
+        out.push('\n');
+    }
+    out
+}
+
+/// Load the configuration at `path`: a plan as output by `terraform show -json`, or a project
+/// directory whose `.tf` files are read, as with `--no-plan`.
+fn load(path: &Path) -> anyhow::Result<Show<'static>> {
+    if path.is_dir() {
+        return configuration::show(path, false)
+            .with_context(|| format!("failed to read the configuration in {}", path.display()));
+    }
+    let json =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let show = Show::parse(&json).with_context(|| format!("failed to load {}", path.display()))?;
+    Ok(show.into_owned())
+}
+
+/// Print the structural differences between two configurations, each a `terraform show -json`
+/// plan file or a project directory, as a combined module tree, a list, or with both module trees
+/// side by side, the trees colored when `color` is set.
+///
+/// The inputs of modules read from directories are only their constants and what their other
+/// expressions refer to, so renames between them are matched by those and their source.
+pub fn run(old: &Path, new: &Path, glyphs: Glyphs, view: View, color: bool) -> anyhow::Result<()> {
+    let (old_show, new_show) = (load(old)?, load(new)?);
+    let (mut old_calls, mut new_calls) = (BTreeMap::new(), BTreeMap::new());
+    flatten(&old_show.configuration.root_module, "", &mut old_calls);
+    flatten(&new_show.configuration.root_module, "", &mut new_calls);
+
+    let differences = differences(&old_calls, &new_calls);
+    match view {
+        View::List => {}
+        View::SideBySide => {
+            let rows = rows(&old_calls, &new_calls, &differences, Glyphs::None);
+            print!("{}", self::side_by_side(&rows, color));
+            return Ok(());
+        }
+        View::Tree => {
+            print!(
+                "{}",
+                combined(&rows(&old_calls, &new_calls, &differences, glyphs), color)
+            );
+            return Ok(());
+        }
+    }
+    if differences.is_empty() {
+        println!("No module changes.");
+    }
+    print!("{}", list(&differences, &old_calls, &new_calls, glyphs));
+    Ok(())
+}
+
+/// `differences` between the calls `old` and `new`, one per line, prefixed with `glyphs`.
+pub fn list(
+    differences: &[Difference],
+    old: &BTreeMap<String, Call>,
+    new: &BTreeMap<String, Call>,
+    glyphs: Glyphs,
+) -> String {
+    let (added, removed, changed) = (
+        glyphs.prefix(Kind::Create),
+        glyphs.prefix(Kind::Delete),
+        glyphs.prefix(Kind::Update),
+    );
+    let mut out = String::new();
+    for difference in differences {
+        let _ = match difference {
+            Difference::Added(address) => {
+                writeln!(out, "{added}{address} ({})", new[*address].source)
+            }
+            Difference::Removed(address) => {
+                writeln!(out, "{removed}{address} ({})", old[*address].source)
+            }
+            Difference::Resourced { address, old, new } => {
+                writeln!(out, "{changed}{address} source changed from {old} to {new}")
+            }
+            Difference::Renamed { old, new } => writeln!(out, "{changed}{old} renamed to {new}"),
+        };
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn calls<'a>(calls: &[(&str, &'a str, &'a Value)]) -> BTreeMap<String, Call<'a>> {
+        calls
+            .iter()
+            .map(|(address, source, inputs)| (address.to_string(), Call { source, inputs }))
+            .collect()
+    }
+
+    fn list(old: &BTreeMap<String, Call>, new: &BTreeMap<String, Call>) -> String {
+        super::list(&differences(old, new), old, new, Glyphs::None)
+    }
+
+    #[test]
+    fn added_removed_and_resourced() {
+        let inputs = json!({});
+        let old = calls(&[
+            ("module.app", "./app", &inputs),
+            ("module.dns", "./dns", &inputs),
+        ]);
+        let new = calls(&[
+            ("module.app", "./app-v2", &inputs),
+            ("module.vpc", "./vpc", &inputs),
+        ]);
+        assert_eq!(
+            list(&old, &new),
+            "module.app source changed from ./app to ./app-v2\n\
+             module.dns (./dns)\n\
+             module.vpc (./vpc)\n"
+        );
+    }
+
+    #[test]
+    fn renames_carry_descendants() {
+        let inputs = json!({ "cidr": { "constant_value": "10.0.0.0/16" } });
+        let none = json!({});
+        let old = calls(&[
+            ("module.net", "./net", &inputs),
+            ("module.net.module.subnets", "./subnets", &none),
+        ]);
+        let new = calls(&[
+            ("module.network", "./net", &inputs),
+            ("module.network.module.subnets", "./subnets", &none),
+        ]);
+        assert_eq!(list(&old, &new), "module.net renamed to module.network\n");
+    }
+
+    #[test]
+    fn renames_match_inputs() {
+        let (eu, us) = (
+            json!({ "region": { "constant_value": "eu" } }),
+            json!({ "region": { "constant_value": "us" } }),
+        );
+        let old = calls(&[("module.eu", "./region", &eu)]);
+        let new = calls(&[("module.europe", "./region", &us)]);
+        assert_eq!(
+            list(&old, &new),
+            "module.eu (./region)\nmodule.europe (./region)\n"
+        );
+    }
+}
@@ -0,0 +1,87 @@
+//! Finding the root modules of a monorepo, to report on all of them at once.
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+
+use crate::{
+    hcl::{self, Body},
+    pool, scan,
+    source::{self, Source},
+};
+
+/// The root modules beneath `dir`, sorted: the directories with a `provider` block, or a
+/// `backend` or `cloud` block within their `terraform` block, which aren't called as a local
+/// module by any other directory. Hidden directories, e.g. `.terraform`, are skipped, and
+/// directories which fail to parse are skipped with a warning.
+pub fn roots(dir: &Path, parallelism: usize) -> anyhow::Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+    walk(dir, &mut dirs)?;
+    let bodies = pool::map(&dirs, parallelism, |dir| scan::parse_dir(dir));
+    let mut modules = Vec::new();
+    for (dir, body) in dirs.into_iter().zip(bodies) {
+        match body {
+            Ok(body) => modules.push((dir, body)),
+            Err(error) => eprintln!("Warning: skipping {}: {error:#}", dir.display()),
+        }
+    }
+
+    let mut called = HashSet::new();
+    for (dir, body) in &modules {
+        for call in body.blocks("module") {
+            let Some(source) = call
+                .body
+                .attribute("source")
+                .and_then(|source| hcl::string_literal(&source.expression))
+            else {
+                continue;
+            };
+            if let Source::Local(path) = source::parse(&source) {
+                if let Ok(callee) = dir.join(path).canonicalize() {
+                    called.insert(callee);
+                }
+            }
+        }
+    }
+    let mut roots: Vec<PathBuf> = modules
+        .into_iter()
+        .filter(|(dir, body)| {
+            is_root(body)
+                && dir
+                    .canonicalize()
+                    .is_ok_and(|canonical| !called.contains(&canonical))
+        })
+        .map(|(dir, _)| dir)
+        .collect();
+    roots.sort_unstable();
+    Ok(roots)
+}
+
+/// Whether the module configures providers or a backend, as only root modules should.
+fn is_root(body: &Body) -> bool {
+    body.blocks("provider").next().is_some()
+        || body.blocks("terraform").any(|terraform| {
+            terraform.body.blocks("backend").next().is_some()
+                || terraform.body.blocks("cloud").next().is_some()
+        })
+}
+
+/// Collect `dir` and its descendants which contain `.tf` files into `dirs`.
+fn walk(dir: &Path, dirs: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    if !scan::tf_files(dir)?.is_empty() {
+        dirs.push(dir.to_owned());
+    }
+    let entries = fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry?;
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if !hidden && entry.file_type()?.is_dir() {
+            walk(&entry.path(), dirs)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,35 @@
+//! Rendering the tree as a Graphviz digraph, e.g. for `dot -Tsvg`.
+
+use std::fmt::Write as _;
+
+use termtree::Tree;
+
+use crate::{
+    graph::escape,
+    render::{self, Render},
+    TreeNode,
+};
+
+/// A digraph with an edge from each module to the modules it calls, labelled with their names and
+/// with their locations as tooltips.
+pub struct Dot;
+
+impl Render for Dot {
+    fn render(&self, tree: &Tree<TreeNode>, out: &mut String) -> anyhow::Result<()> {
+        out.push_str("digraph modules {\n  node [shape = box, fontname = \"sans-serif\"];\n");
+        render::graph(
+            out,
+            tree,
+            &|out, id, label, node| {
+                write!(out, "  n{id} [label = {}", escape(label))?;
+                if let Some(location) = node.location() {
+                    write!(out, ", tooltip = {}", escape(&location))?;
+                }
+                writeln!(out, "];")
+            },
+            &|out, id, child| writeln!(out, "  n{id} -> n{child};"),
+        )?;
+        out.push_str("}\n");
+        Ok(())
+    }
+}
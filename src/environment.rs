@@ -0,0 +1,223 @@
+//! The environment variables which change how terraform commands behave.
+
+use std::{env, fs, path::Path, process};
+
+use anyhow::Context as _;
+
+use crate::glob;
+
+/// The environment variables kept by `--isolate-env`, as glob patterns: those programs need to
+/// run, and those locating terraform's own files.
+const ALLOWLIST: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "TERM",
+    "LANG",
+    "LC_*",
+    "TZ",
+    "TMPDIR",
+    "TMP",
+    "TEMP",
+    "SYSTEMROOT",
+    "TF_CLI_CONFIG_FILE",
+    "TF_DATA_DIR",
+    "TF_PLUGIN_CACHE_DIR",
+    "TF_IN_AUTOMATION",
+];
+
+/// Whether the environment variable `name` affects the plan.
+pub fn affects_plan(name: &str) -> bool {
+    matches!(name, "TF_WORKSPACE" | "TF_CLI_ARGS" | "TF_CLI_ARGS_plan")
+        || name.starts_with("TF_VAR_")
+}
+
+/// Whether `--isolate-env` keeps the environment variable `name`, being in the allowlist or
+/// matching one of the glob patterns `allowed`.
+pub fn allowed(name: &str, allowed: &[String]) -> bool {
+    ALLOWLIST
+        .iter()
+        .copied()
+        .chain(allowed.iter().map(String::as_str))
+        .any(|pattern| glob::matches(pattern, name))
+}
+
+/// Remove the variables of the environment which aren't [`allowed`] from that of `command`,
+/// leaving those it sets itself.
+pub fn isolate(command: &mut process::Command, allowed: &[String]) {
+    let set: Vec<_> = command
+        .get_envs()
+        .map(|(name, _)| name.to_owned())
+        .collect();
+    for (name, _) in env::vars_os() {
+        if !set.contains(&name) && !self::allowed(&name.to_string_lossy(), allowed) {
+            command.env_remove(name);
+        }
+    }
+}
+
+/// The variables of the env file at `path`, from its `NAME=value` lines, which may start with
+/// `export`. Values may be quoted as in a shell, e.g. `TF_VAR_tags='{ team = "platform" }'`, and
+/// blank lines and `#` comments, also after a value, are skipped.
+pub fn load(path: &Path) -> anyhow::Result<Vec<(String, String)>> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let mut variables = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        // Values drop their own trailing whitespace, which may be escaped
+        let line = line.trim_start();
+        if line.trim_end().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (name, value) = line
+            .split_once('=')
+            .map(|(name, value)| (name.trim(), value.trim_start()))
+            .filter(|(name, _)| {
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            })
+            .with_context(|| format!("{}:{}: expected `NAME=value`", path.display(), index + 1))?;
+        variables.push((name.to_owned(), self::value(value)));
+    }
+    Ok(variables)
+}
+
+/// The value of an env file line, without quotes or a trailing comment. Quoted parts are kept
+/// verbatim, as is the space between unquoted words, and an unquoted `#` after a space starts a
+/// comment.
+fn value(raw: &str) -> String {
+    let mut value = String::new();
+    // The length of the value without trailing unquoted whitespace
+    let mut len = 0;
+    let (mut quote, mut previous) = (None, '=');
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (None, '#') if previous.is_whitespace() => break,
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), c) if c == open => quote = None,
+            (None | Some('"'), '\\') => value.extend(chars.next()),
+            (_, c) => value.push(c),
+        }
+        if quote.is_some() || !c.is_whitespace() {
+            len = value.len();
+        }
+        previous = c;
+    }
+    value.truncate(len);
+    value
+}
+
+/// The arguments terraform inserts after `subcommand` of `command` from `TF_CLI_ARGS` and
+/// `TF_CLI_ARGS_<subcommand>`.
+fn cli_args(command: &process::Command, subcommand: &str) -> Vec<String> {
+    [
+        "TF_CLI_ARGS".to_owned(),
+        format!("TF_CLI_ARGS_{subcommand}"),
+    ]
+    .iter()
+    .filter_map(|name| var(command, name))
+    .flat_map(|value| split(&value))
+    .collect()
+}
+
+/// The value of the environment variable `name` for `command`: the value it sets, or else the
+/// inherited one, unless it removes it.
+fn var(command: &process::Command, name: &str) -> Option<String> {
+    match command.get_envs().find(|(set, _)| *set == name) {
+        Some((_, value)) => value.map(|value| value.to_string_lossy().into_owned()),
+        None => env::var(name).ok(),
+    }
+}
+
+/// Split `value` into words as a shell would, honouring quotes and backslashes.
+pub fn split(value: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (Some(open), c) if c == open => quote = None,
+            (None | Some('"'), '\\') => word.get_or_insert_with(String::new).extend(chars.next()),
+            (_, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+/// Render `command` as the shell command line terraform effectively runs, including the
+/// workspace, the arguments inserted from the environment, and the directory it runs in, if any.
+pub fn describe(command: &process::Command) -> String {
+    let mut words = Vec::new();
+    if let Some(dir) = command.get_current_dir() {
+        words.extend([
+            "cd".to_owned(),
+            quote(&dir.to_string_lossy()),
+            "&&".to_owned(),
+        ]);
+    }
+    if let Some(workspace) = var(command, "TF_WORKSPACE") {
+        words.push(quote(&format!("TF_WORKSPACE={workspace}")));
+    }
+    words.push(quote(&command.get_program().to_string_lossy()));
+    let mut inserted = false;
+    for arg in command.get_args() {
+        let arg = arg.to_string_lossy();
+        words.push(quote(&arg));
+        if !inserted && !arg.starts_with('-') {
+            words.extend(cli_args(command, &arg).iter().map(|arg| quote(arg)));
+            inserted = true;
+        }
+    }
+    words.join(" ")
+}
+
+/// Quote `word` for a POSIX shell, if required.
+fn quote(word: &str) -> String {
+    let is_plain = |c: char| c.is_alphanumeric() || "-_=./:@,+%".contains(c);
+    if !word.is_empty() && word.chars().all(is_plain) {
+        word.to_owned()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_as_a_shell() {
+        assert_eq!(
+            split(r#"-lock=false  -var 'tags={ team = "platform" }' -var="a b" c\ d"#),
+            [
+                "-lock=false",
+                "-var",
+                r#"tags={ team = "platform" }"#,
+                "-var=a b",
+                "c d"
+            ]
+        );
+        assert_eq!(split(r#""" '' \"quoted\""#), ["", "", "\"quoted\""]);
+        assert!(split("  ").is_empty());
+    }
+
+    #[test]
+    fn env_file_values() {
+        assert_eq!(value("plain"), "plain");
+        assert_eq!(value("two words  # a comment"), "two words");
+        assert_eq!(value("'kept # verbatim '"), "kept # verbatim ");
+        assert_eq!(value(r#""a \"b\" c""#), r#"a "b" c"#);
+        assert_eq!(value("url#fragment"), "url#fragment");
+        assert_eq!(value(r"trailing\ "), "trailing ");
+    }
+}
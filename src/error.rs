@@ -0,0 +1,131 @@
+//! The failures which callers can tell apart, each with its own exit code.
+
+use std::{fmt, io, path::PathBuf};
+
+/// The kinds of failure which callers may want to distinguish, e.g. to choose an exit code.
+///
+/// Other failures are reported as plain [`anyhow::Error`]s, which may wrap one of these.
+#[derive(Debug)]
+pub enum TreaformError {
+    /// A file couldn't be read.
+    Io { path: PathBuf, error: io::Error },
+    /// JSON, e.g. a plan or the module manifest, didn't have the expected structure.
+    Deserialize {
+        what: String,
+        error: serde_json::Error,
+    },
+    /// A command couldn't be started, e.g. because it isn't installed.
+    Spawn { command: String, error: io::Error },
+    /// A command other than `terraform plan` or `terraform show` exited unsuccessfully.
+    CommandFailed {
+        command: String,
+        diagnostics: String,
+    },
+    /// `terraform plan`, or that of `program`, e.g. `tofu`, exited unsuccessfully.
+    PlanFailed {
+        program: String,
+        diagnostics: String,
+    },
+    /// `terraform show`, or that of `program`, exited unsuccessfully.
+    ShowFailed {
+        program: String,
+        diagnostics: String,
+    },
+    /// The plan JSON uses a major format version which isn't understood.
+    UnsupportedFormatVersion { version: String },
+    /// The source directory of a module call couldn't be resolved.
+    SourceResolution { path: PathBuf, error: io::Error },
+    /// Plan warnings matched `--warning-as-error`.
+    WarningsEscalated { summaries: Vec<String> },
+    /// Modules exceed their `error` level resource budget.
+    BudgetExceeded { modules: usize },
+    /// Vendored modules don't match their declared sources, or were modified.
+    VerificationFailed { modules: usize },
+    /// Module calls broke lint rules.
+    LintFailed { violations: usize },
+    /// Local module sources call each other in a cycle, given as the directories of the modules
+    /// along it, ending with the first one again.
+    ModuleCycle { cycle: Vec<String> },
+    /// The module calls differ from those of a baseline.
+    BaselineChanged { differences: usize },
+}
+
+impl TreaformError {
+    /// The process exit code reporting this failure. Other failures exit with 1.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            TreaformError::Io { .. }
+            | TreaformError::Deserialize { .. }
+            | TreaformError::Spawn { .. }
+            | TreaformError::CommandFailed { .. } => 1,
+            TreaformError::PlanFailed { .. } => 3,
+            TreaformError::ShowFailed { .. } => 4,
+            TreaformError::UnsupportedFormatVersion { .. } => 5,
+            TreaformError::SourceResolution { .. } => 6,
+            TreaformError::WarningsEscalated { .. } => 7,
+            TreaformError::BudgetExceeded { .. } => 8,
+            TreaformError::VerificationFailed { .. } => 9,
+            TreaformError::LintFailed { .. } => 10,
+            TreaformError::ModuleCycle { .. } => 11,
+            TreaformError::BaselineChanged { .. } => 12,
+        }
+    }
+}
+
+impl fmt::Display for TreaformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TreaformError::Io { path, .. } => write!(f, "failed to read {}", path.display()),
+            TreaformError::Deserialize { what, .. } => write!(f, "failed to deserialize {what}"),
+            TreaformError::Spawn { command, .. } => write!(f, "failed to spawn `{command}`"),
+            TreaformError::CommandFailed {
+                command,
+                diagnostics,
+            } => write!(f, "`{command}` failed\n\n{}", diagnostics.trim()),
+            TreaformError::PlanFailed {
+                program,
+                diagnostics,
+            } => write!(f, "`{program} plan` failed\n\n{}", diagnostics.trim()),
+            TreaformError::ShowFailed {
+                program,
+                diagnostics,
+            } => write!(f, "`{program} show` failed\n\n{}", diagnostics.trim()),
+            TreaformError::UnsupportedFormatVersion { version } => {
+                write!(f, "unsupported plan format version {version}, expected 1.x")
+            }
+            TreaformError::SourceResolution { path, .. } => {
+                write!(f, "failed to resolve module source {}", path.display())
+            }
+            TreaformError::WarningsEscalated { summaries } => {
+                write!(f, "warnings escalated to errors: {}", summaries.join(", "))
+            }
+            TreaformError::BudgetExceeded { modules } => {
+                write!(f, "{modules} module(s) exceed their resource budget")
+            }
+            TreaformError::VerificationFailed { modules } => {
+                write!(f, "{modules} vendored module(s) failed verification")
+            }
+            TreaformError::LintFailed { violations } => {
+                write!(f, "{violations} lint violation(s) found")
+            }
+            TreaformError::ModuleCycle { cycle } => {
+                write!(f, "module calls form a cycle: {}", cycle.join(" → "))
+            }
+            TreaformError::BaselineChanged { differences } => {
+                write!(f, "{differences} module change(s) from the baseline")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TreaformError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TreaformError::Io { error, .. }
+            | TreaformError::Spawn { error, .. }
+            | TreaformError::SourceResolution { error, .. } => Some(error),
+            TreaformError::Deserialize { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! Reading plan JSON from files, stdin or HTTP(S) URLs.
+
+use std::{
+    fs,
+    io::{self, Read as _, Write as _},
+    process::{self, Stdio},
+};
+
+use anyhow::Context as _;
+
+/// Read the `terraform show -json` output at `location`, a path, `-` for stdin or an HTTP(S) URL,
+/// authenticating with `token` as a bearer token if one is given.
+pub fn plan_json(location: &str, token: Option<&str>) -> anyhow::Result<String> {
+    if location == "-" {
+        let mut contents = String::new();
+        io::stdin()
+            .read_to_string(&mut contents)
+            .context("failed to read stdin")?;
+        return Ok(contents);
+    }
+    if !(location.starts_with("http://") || location.starts_with("https://")) {
+        return fs::read_to_string(location).with_context(|| format!("failed to read {location}"));
+    }
+    get(location, token)
+}
+
+/// Download `url`, authenticating with `token` as a bearer token if one is given.
+pub fn get(location: &str, token: Option<&str>) -> anyhow::Result<String> {
+    // The header is passed on stdin to keep the token out of the process list
+    let mut child = process::Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location"])
+        .args(["--header", "@-", "--", location])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to spawn `curl`")?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    if let Some(token) = token {
+        writeln!(stdin, "Authorization: Bearer {token}")?;
+    }
+    drop(stdin);
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("failed to download {location}: {}", stderr.trim())
+    }
+    String::from_utf8(output.stdout).context("output not utf-8")
+}
@@ -0,0 +1,40 @@
+//! Pruning the tree to the modules matching, or not matching, a predicate.
+
+use std::fmt::Display;
+
+use termtree::Tree;
+
+use crate::{glob, TreeNode};
+
+/// Remove every branch of `tree` that contains no node satisfying `predicate`.
+///
+/// Ancestors of matching nodes are kept so that matches are still shown in context. Returns whether
+/// anything in `tree` matched.
+pub fn prune<D: Display>(tree: &mut Tree<D>, predicate: &impl Fn(&D) -> bool) -> bool {
+    tree.leaves.retain_mut(|leaf| prune(leaf, predicate));
+    predicate(&tree.root) || !tree.leaves.is_empty()
+}
+
+/// Remove every node beneath the root of `tree` which satisfies `predicate`, with its subtree.
+pub fn exclude<D: Display>(tree: &mut Tree<D>, predicate: &impl Fn(&D) -> bool) {
+    tree.leaves.retain(|leaf| !predicate(&leaf.root));
+    for leaf in &mut tree.leaves {
+        exclude(leaf, predicate);
+    }
+}
+
+/// Whether the name of the module call, its source directory relative to the root module or its
+/// remote source address matches the glob `pattern`.
+pub fn matches(node: &TreeNode, pattern: &str) -> bool {
+    let path: Vec<_> = node
+        .source
+        .iter()
+        .map(|component| component.to_string_lossy())
+        .collect();
+    glob::matches(pattern, &node.name)
+        || glob::matches_path(pattern, &path.join("/"))
+        || node
+            .remote
+            .as_ref()
+            .is_some_and(|remote| glob::matches(pattern, remote))
+}
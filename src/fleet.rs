@@ -0,0 +1,244 @@
+//! A combined report across the many projects listed in a manifest, for reviews of a whole
+//! organization's terraform.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+    process,
+};
+
+use anyhow::Context as _;
+
+use crate::{
+    hcl, output, pool, report,
+    scan::Scanner,
+    source::{self, Source},
+    Args, Format, TreaformError,
+};
+
+/// The outcome of reporting on one project.
+enum Outcome {
+    Reported {
+        modules: usize,
+        /// The number of modules exceeding an `error` level budget.
+        violations: usize,
+    },
+    Failed(String),
+}
+
+/// Report on every project listed in `manifest`: one path, or git URL to clone, per line.
+///
+/// Paths are relative to the manifest. URLs take the form of git module sources, e.g.
+/// `https://example.com/infra.git//network?ref=main`, and are cloned shallowly.
+pub fn run(args: &Args, manifest: &Path) -> anyhow::Result<()> {
+    let contents = fs::read_to_string(manifest)
+        .with_context(|| format!("failed to read {}", manifest.display()))?;
+    let base = manifest.parent().unwrap_or(Path::new(""));
+    let entries: Vec<&str> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    let parallelism = args.parallelism.unwrap_or(1) as usize;
+    let results = pool::map(&entries, parallelism, |entry| project(args, base, entry));
+    let mut outcomes = Vec::new();
+    // The projects calling each remote module
+    let mut usage: BTreeMap<String, BTreeSet<&str>> = BTreeMap::new();
+    for (entry, result) in entries.iter().zip(results) {
+        let (outcome, sources) = result?;
+        for source in sources {
+            usage.entry(source).or_default().insert(entry);
+        }
+        outcomes.push((entry, outcome));
+    }
+
+    let mut out = String::new();
+    out.push_str("Projects\n");
+    let (mut failed, mut violated) = (0, 0);
+    for (entry, outcome) in &outcomes {
+        match outcome {
+            Outcome::Reported {
+                modules,
+                violations,
+            } => {
+                let _ = write!(out, "  {entry}: {modules} modules");
+                if *violations > 0 {
+                    violated += violations;
+                    let _ = write!(out, ", {violations} over budget");
+                }
+                out.push('\n');
+            }
+            Outcome::Failed(error) => {
+                failed += 1;
+                let mut lines = error.lines();
+                let _ = write!(
+                    out,
+                    "  {entry}: failed, {}",
+                    lines.next().unwrap_or_default()
+                );
+                // Include the first of terraform's diagnostics
+                if let Some(diagnostic) = lines.find_map(|line| line.strip_prefix("Error: ")) {
+                    let _ = write!(out, " ({diagnostic})");
+                }
+                out.push('\n');
+            }
+        }
+    }
+    out.push_str("Shared modules\n");
+    for (source, projects) in usage.iter().filter(|(_, projects)| projects.len() > 1) {
+        let _ = writeln!(out, "  {source}: {} projects", projects.len());
+        for project in projects {
+            let _ = writeln!(out, "    {project}");
+        }
+    }
+    print!("{out}");
+
+    if failed > 0 {
+        anyhow::bail!("{failed} project(s) failed")
+    }
+    if violated > 0 {
+        return Err(TreaformError::BudgetExceeded { modules: violated }.into());
+    }
+    Ok(())
+}
+
+/// Report on the project at `entry`, also returning the remote sources of its module calls.
+fn project(args: &Args, base: &Path, entry: &str) -> anyhow::Result<(Outcome, BTreeSet<String>)> {
+    let dir = match checkout(base, entry) {
+        Ok(dir) => dir,
+        Err(error) => return Ok((Outcome::Failed(format!("{error:#}")), BTreeSet::new())),
+    };
+    let sources = match remote_sources(&dir) {
+        Ok(sources) => sources,
+        Err(error) => return Ok((Outcome::Failed(format!("{error:#}")), BTreeSet::new())),
+    };
+
+    let mut project_args = Args {
+        command: None,
+        path: vec![dir],
+        budgets: None,
+        format: Format::Json,
+        group_by: None,
+        no_progress: true,
+        ..args.clone()
+    };
+    let mut json = String::new();
+    let result = report(&mut project_args, &mut json);
+    let violations = match result {
+        Ok(()) => 0,
+        Err(error) => match error.downcast_ref::<TreaformError>() {
+            Some(TreaformError::BudgetExceeded { modules }) => *modules,
+            _ => return Ok((Outcome::Failed(format!("{error:#}")), sources)),
+        },
+    };
+    let tree: serde_json::Value =
+        serde_json::from_str(&json).context("failed to read back the JSON report")?;
+    let outcome = Outcome::Reported {
+        modules: count(&tree) - 1,
+        violations,
+    };
+    Ok((outcome, sources))
+}
+
+/// The directory of the project at `entry`, cloning it first if it's a git URL.
+fn checkout(base: &Path, entry: &str) -> anyhow::Result<PathBuf> {
+    let is_url = entry.contains("://") || entry.starts_with("git@");
+    if !is_url {
+        return Ok(base.join(entry));
+    }
+    let forced = format!("git::{}", entry.trim_start_matches("git::"));
+    let Source::Git {
+        url,
+        subdir,
+        reference,
+    } = source::parse(&forced)
+    else {
+        unreachable!("`git::` sources are git sources")
+    };
+
+    let mut hasher = DefaultHasher::new();
+    entry.hash(&mut hasher);
+    let dir = std::env::temp_dir()
+        .join("treaform-fleet")
+        .join(format!("{:016x}", hasher.finish()));
+    if dir.exists() {
+        fs::remove_dir_all(&dir).with_context(|| format!("failed to remove {}", dir.display()))?;
+    }
+    let mut command = process::Command::new("git");
+    command.args(["clone", "--quiet", "--depth", "1"]);
+    if let Some(reference) = reference {
+        command.args(["--branch", reference]);
+    }
+    command.arg("--").arg(url).arg(&dir);
+    output(command, "git clone")?;
+    Ok(match subdir {
+        Some(subdir) => dir.join(subdir),
+        None => dir,
+    })
+}
+
+/// The remote sources of the module calls within the project at `dir`, following local sources.
+fn remote_sources(dir: &Path) -> anyhow::Result<BTreeSet<String>> {
+    let mut scanner = Scanner::default();
+    let mut pending = vec![dir.to_owned()];
+    let mut visited = BTreeSet::new();
+    let mut sources = BTreeSet::new();
+    while let Some(dir) = pending.pop() {
+        if !visited.insert(dir.clone()) {
+            continue;
+        }
+        for block in scanner.module(&dir)?.blocks("module") {
+            let Some(literal) = block
+                .body
+                .attribute("source")
+                .and_then(|source| hcl::string_literal(&source.expression))
+            else {
+                continue;
+            };
+            match source::parse(&literal) {
+                Source::Local(path) => {
+                    let path = dir.join(path);
+                    pending.push(path.canonicalize().unwrap_or(path));
+                }
+                Source::Registry {
+                    host,
+                    namespace,
+                    name,
+                    provider,
+                    subdir,
+                } => {
+                    let mut key = if host == source::DEFAULT_REGISTRY {
+                        format!("{namespace}/{name}/{provider}")
+                    } else {
+                        format!("{host}/{namespace}/{name}/{provider}")
+                    };
+                    if let Some(subdir) = subdir {
+                        let _ = write!(key, "//{subdir}");
+                    }
+                    sources.insert(key);
+                }
+                Source::Git { url, subdir, .. } => {
+                    let mut key = source::repository_url(url).unwrap_or_else(|| url.to_owned());
+                    if let Some(subdir) = subdir {
+                        let _ = write!(key, "//{subdir}");
+                    }
+                    sources.insert(key);
+                }
+                Source::Other(other) => {
+                    sources.insert(other.to_owned());
+                }
+            }
+        }
+    }
+    Ok(sources)
+}
+
+/// The number of modules in a JSON report's tree.
+fn count(node: &serde_json::Value) -> usize {
+    1 + node["children"]
+        .as_array()
+        .map_or(0, |children| children.iter().map(count).sum())
+}
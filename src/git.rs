@@ -0,0 +1,84 @@
+//! Querying the git repository of a project: its root, and the files changed in it.
+
+use std::{
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::output;
+
+/// The absolute path of the root of the git repository containing `dir`.
+pub fn toplevel(dir: &Path) -> anyhow::Result<PathBuf> {
+    let mut command = process::Command::new("git");
+    command
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--show-toplevel"]);
+    let stdout = output(command, "git rev-parse")?;
+    Ok(PathBuf::from(stdout.trim_end()))
+}
+
+/// The absolute paths of the modified and untracked files in the git repository containing `dir`.
+pub fn dirty_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let toplevel = toplevel(dir)?;
+    let mut command = process::Command::new("git");
+    command
+        .arg("-C")
+        .arg(&toplevel)
+        .args(["status", "--porcelain", "-z", "--untracked-files=all"]);
+    let stdout = output(command, "git status")?;
+    let mut files = Vec::new();
+    let mut entries = stdout.split('\0').filter(|entry| !entry.is_empty());
+    while let Some(entry) = entries.next() {
+        let (status, path) = entry.split_at(3.min(entry.len()));
+        files.push(toplevel.join(path));
+        // Renames and copies are followed by their original path
+        if status.starts_with(['R', 'C']) {
+            if let Some(original) = entries.next() {
+                files.push(toplevel.join(original));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// The absolute paths of the files changed between the merge base of `base` and `HEAD`, in the git
+/// repository containing `dir`.
+pub fn changed_since(dir: &Path, base: &str) -> anyhow::Result<Vec<PathBuf>> {
+    let toplevel = toplevel(dir)?;
+    let mut command = process::Command::new("git");
+    command
+        .arg("-C")
+        .arg(&toplevel)
+        .args(["diff", "--name-only", "-z"])
+        .arg(format!("{base}...HEAD"));
+    let stdout = output(command, "git diff")?;
+    Ok(stdout
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(|path| toplevel.join(path))
+        .collect())
+}
+
+/// The commit which `revision` names in the git repository containing `dir`, if any.
+pub fn resolve(dir: &Path, revision: &str) -> Option<String> {
+    let mut command = process::Command::new("git");
+    command
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--verify", "--quiet"])
+        .arg(format!("{revision}^{{commit}}"));
+    let stdout = output(command, "git rev-parse").ok()?;
+    Some(stdout.trim_end().to_owned())
+}
+
+/// The number of modified and untracked files in the working tree of the git repository at `dir`.
+pub fn modified_count(dir: &Path) -> anyhow::Result<usize> {
+    let mut command = process::Command::new("git");
+    command
+        .arg("-C")
+        .arg(dir)
+        .args(["status", "--porcelain", "--untracked-files=all"]);
+    let stdout = output(command, "git status")?;
+    Ok(stdout.lines().count())
+}
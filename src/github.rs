@@ -0,0 +1,82 @@
+//! The workflow commands of GitHub Actions, which fold the output of a step into groups, annotate
+//! files, and add to the summary of the job.
+
+use std::{
+    env,
+    fmt::Write as _,
+    fs::OpenOptions,
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+
+/// The level of an annotation.
+#[derive(Clone, Copy, Debug)]
+pub enum Level {
+    Warning,
+    Error,
+}
+
+/// `body` within a collapsed group titled `title`.
+pub fn group(title: &str, body: &str) -> String {
+    let mut out = format!("::group::{}\n{body}", escape_data(title));
+    if !body.is_empty() && !body.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str("::endgroup::\n");
+    out
+}
+
+/// An annotation of `message` titled `title`, at `line` of `file` when known. The file is relative
+/// to the root of the repository.
+pub fn annotation(
+    level: Level,
+    location: Option<(&Path, usize)>,
+    title: &str,
+    message: &str,
+) -> String {
+    let command = match level {
+        Level::Warning => "warning",
+        Level::Error => "error",
+    };
+    let mut out = format!("::{command} ");
+    if let Some((file, line)) = location {
+        let file = escape_property(&file.display().to_string());
+        let _ = write!(out, "file={file},line={line},");
+    }
+    let _ = write!(
+        out,
+        "title={}::{}",
+        escape_property(title),
+        escape_data(message)
+    );
+    out
+}
+
+/// Append `markdown` to the summary of the job, if running in GitHub Actions.
+pub fn summarize(markdown: &str) -> anyhow::Result<()> {
+    let Some(path) = env::var_os("GITHUB_STEP_SUMMARY").map(PathBuf::from) else {
+        return Ok(());
+    };
+    // Several root modules may be summarized at once, so write each summary whole
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    file.write_all(format!("{markdown}\n").as_bytes())
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Escape the message of a workflow command, which ends at a newline.
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a property of a workflow command, which also ends at a `,` or `:`.
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
@@ -0,0 +1,115 @@
+//! Matching names, addresses and paths against glob patterns.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Match `text` against a glob `pattern`, where `*` matches any run of characters and `?` matches
+/// exactly one.
+pub fn matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    glob(&pattern, &text, false)
+}
+
+/// Match a `/` separated `path` against a glob `pattern`, where `*` and `?` don't match the
+/// separator and `**` matches any number of path segments.
+pub fn matches_path(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+    glob(&pattern, &path, true)
+}
+
+fn glob(pattern: &[char], text: &[char], is_path: bool) -> bool {
+    let is_separator = |c: &char| is_path && *c == '/';
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', '/', rest @ ..] if is_path => {
+            glob(rest, text, is_path)
+                || (0..text.len())
+                    .filter(|index| text[*index] == '/')
+                    .any(|index| glob(rest, &text[index + 1..], is_path))
+        }
+        ['*', '*', rest @ ..] if is_path => {
+            (0..=text.len()).any(|index| glob(rest, &text[index..], is_path))
+        }
+        ['*', rest @ ..] => (0..=text.len())
+            .take_while(|index| *index == 0 || !is_separator(&text[index - 1]))
+            .any(|index| glob(rest, &text[index..], is_path)),
+        ['?', rest @ ..] => match text {
+            [c, text @ ..] if !is_separator(c) => glob(rest, text, is_path),
+            _ => false,
+        },
+        [c, rest @ ..] => match text {
+            [t, text @ ..] if t == c => glob(rest, text, is_path),
+            _ => false,
+        },
+    }
+}
+
+/// The directories matching `pattern`, a path whose components may contain `*` and `?`, sorted.
+/// Hidden directories only match components which start with `.` themselves.
+pub fn directories(pattern: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::new()];
+    for component in pattern.components() {
+        let name = component.as_os_str().to_string_lossy();
+        if !name.contains(['*', '?']) {
+            for dir in &mut dirs {
+                dir.push(component);
+            }
+            continue;
+        }
+        dirs = dirs
+            .into_iter()
+            .flat_map(|dir| {
+                let entries = fs::read_dir(if dir.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    &dir
+                });
+                let mut children = Vec::new();
+                for entry in entries.into_iter().flatten().flatten() {
+                    let child = entry.file_name().to_string_lossy().into_owned();
+                    if (child.starts_with('.') && !name.starts_with('.'))
+                        || !matches(&name, &child)
+                        || !entry.path().is_dir()
+                    {
+                        continue;
+                    }
+                    children.push(dir.join(child));
+                }
+                children
+            })
+            .collect();
+    }
+    dirs.retain(|dir| dir.is_dir());
+    dirs.sort_unstable();
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_patterns() {
+        assert!(matches("module.app*", "module.app[0].module.db"));
+        assert!(matches("*.db", "module.app.module.db"));
+        assert!(matches("module.?pp", "module.app"));
+        assert!(!matches("module.?pp", "module.pp"));
+        assert!(!matches("module.app", "module.app[0]"));
+        assert!(matches("*", ""));
+    }
+
+    #[test]
+    fn path_patterns() {
+        assert!(matches_path("modules/*", "modules/vpc"));
+        assert!(!matches_path("modules/*", "modules/vpc/subnets"));
+        assert!(!matches_path("modules/?", "modules//"));
+        assert!(matches_path("**/vpc", "vpc"));
+        assert!(matches_path("**/vpc", "modules/network/vpc"));
+        assert!(matches_path("modules/**", "modules/network/vpc"));
+        assert!(!matches_path("**/vpc", "modules/vpcs"));
+    }
+}
@@ -0,0 +1,51 @@
+//! The glyphs marking created, updated and deleted resources and modules.
+
+use clap::ValueEnum;
+
+/// How change markers are rendered.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Glyphs {
+    /// Emoji, e.g. ✅ ❌ ♻️.
+    Emoji,
+    /// Plain ASCII, e.g. + ~ -.
+    Ascii,
+    /// No markers.
+    None,
+}
+
+/// A kind of change to a resource or module.
+#[derive(Clone, Copy)]
+pub enum Kind {
+    Create,
+    Read,
+    Update,
+    Delete,
+    /// Replaced by destroying before creating.
+    DeleteCreate,
+    /// Replaced by creating before destroying.
+    CreateDelete,
+}
+
+impl Glyphs {
+    /// The marker for `kind` followed by a space, or nothing when markers are omitted.
+    pub fn prefix(self, kind: Kind) -> &'static str {
+        match self {
+            Glyphs::Emoji => match kind {
+                Kind::Create => "✅ ",
+                Kind::Read => "📖 ",
+                Kind::Update => "✏️ ",
+                Kind::Delete => "❌ ",
+                Kind::DeleteCreate | Kind::CreateDelete => "♻️ ",
+            },
+            Glyphs::Ascii => match kind {
+                Kind::Create => "+ ",
+                Kind::Read => "<= ",
+                Kind::Update => "~ ",
+                Kind::Delete => "- ",
+                Kind::DeleteCreate => "-/+ ",
+                Kind::CreateDelete => "+/- ",
+            },
+            Glyphs::None => "",
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! A readable resource graph of one module subtree, simplified from `terraform graph`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Write as _,
+    process,
+};
+
+use clap::ValueEnum;
+
+use crate::{output, within};
+
+/// How the graph is obtained.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Via {
+    /// Simplify the plan graph of `terraform graph`.
+    Terraform,
+}
+
+/// The DOT graph of the dependencies between the resources within `scope`, or all resources,
+/// obtained with `command`, which runs terraform in the project.
+pub fn run(mut command: process::Command, scope: Option<&str>, via: Via) -> anyhow::Result<String> {
+    let dot = match via {
+        Via::Terraform => {
+            let name = format!("{} graph", command.get_program().to_string_lossy());
+            command.args(["graph", "-type=plan"]);
+            output(command, &name)?
+        }
+    };
+
+    // Nodes and the nodes they depend on, by address
+    let mut edges: HashMap<String, Vec<String>> = HashMap::new();
+    let mut resources = BTreeSet::new();
+    for line in dot.lines() {
+        let names = quoted(line);
+        let node = |index: usize| names.get(index).map(|name| normalize(name));
+        let (Some(from), to) = (node(0), node(1)) else {
+            continue;
+        };
+        let is_edge = line.contains("->");
+        for address in std::iter::once(&from).chain(to.iter()) {
+            if is_resource(address) && scope.is_none_or(|scope| within(address, scope)) {
+                resources.insert(address.clone());
+            }
+        }
+        if let (true, Some(to)) = (is_edge, to) {
+            edges.entry(from).or_default().push(to);
+        }
+    }
+
+    // Depend directly on the resources reached through module, variable, output and provider nodes
+    let mut graph: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for resource in &resources {
+        let dependencies = graph.entry(resource).or_default();
+        let mut pending: Vec<&str> = edges
+            .get(resource)
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        let mut seen = BTreeSet::new();
+        while let Some(node) = pending.pop() {
+            if !seen.insert(node) {
+                continue;
+            }
+            if is_resource(node) {
+                if resources.contains(node) && node != resource.as_str() {
+                    dependencies.insert(node);
+                }
+            } else if !node.starts_with("provider") && node != "root" {
+                // Providers and the root are closed after every resource using them
+                pending.extend(edges.get(node).into_iter().flatten().map(String::as_str));
+            }
+        }
+    }
+
+    let mut out = String::new();
+    out.push_str(
+        "digraph G {\n  rankdir = \"RL\";\n  node [shape = rect, fontname = \"sans-serif\"];\n",
+    );
+    let mut modules: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for resource in graph.keys() {
+        let (module, name) = split_module(resource);
+        modules.entry(module).or_default().push(name);
+    }
+    for (module, names) in &modules {
+        let indent = if module.is_empty() {
+            "  "
+        } else {
+            let _ = writeln!(
+                out,
+                "  subgraph \"cluster_{module}\" {{\n    label = \"{module}\""
+            );
+            "    "
+        };
+        for name in names {
+            let address = if module.is_empty() {
+                name.to_string()
+            } else {
+                format!("{module}.{name}")
+            };
+            let _ = writeln!(
+                out,
+                "{indent}{} [label={}];",
+                escape(&address),
+                escape(name)
+            );
+        }
+        if !module.is_empty() {
+            out.push_str("  }\n");
+        }
+    }
+    for (resource, dependencies) in &graph {
+        for dependency in dependencies {
+            let _ = writeln!(out, "  {} -> {};", escape(resource), escape(dependency));
+        }
+    }
+    out.push_str("}\n");
+    Ok(out)
+}
+
+/// The quoted strings on a line of DOT, unescaped, except for attribute values.
+fn quoted(line: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut chars = line.char_indices();
+    while let Some((start, c)) = chars.next() {
+        if c != '"' {
+            continue;
+        }
+        let mut string = String::new();
+        while let Some((_, c)) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => string.extend(chars.next().map(|(_, c)| c)),
+                c => string.push(c),
+            }
+        }
+        // Skip attribute values, e.g. `label = "..."`
+        if !line[..start].trim_end().ends_with('=') {
+            strings.push(string);
+        }
+    }
+    strings
+}
+
+/// The address of a plan graph node, without the `[root] ` prefix and `(expand)` suffix. Other
+/// suffixes, e.g. of `(close)` nodes, are kept to tell them apart.
+fn normalize(node: &str) -> String {
+    let node = node.strip_prefix("[root] ").unwrap_or(node);
+    node.strip_suffix(" (expand)").unwrap_or(node).to_owned()
+}
+
+/// Split an address into its module's address and the rest, e.g. `module.app` and
+/// `aws_instance.web`.
+fn split_module(address: &str) -> (&str, &str) {
+    let mut end = 0;
+    while let Some(rest) = address[end..].strip_prefix("module.") {
+        match rest.find('.') {
+            Some(dot) => end += "module.".len() + dot + 1,
+            None => break,
+        }
+    }
+    match end {
+        0 => ("", address),
+        end => (&address[..end - 1], &address[end..]),
+    }
+}
+
+/// Whether a node is a resource, rather than a module, variable, local, output or provider.
+fn is_resource(address: &str) -> bool {
+    let (_, name) = split_module(address);
+    let name = name
+        .strip_prefix("data.")
+        .or_else(|| name.strip_prefix("ephemeral."))
+        .unwrap_or(name);
+    let meta = [
+        "module.", "var.", "local.", "output.", "provider", "check.", "meta.",
+    ];
+    !meta.iter().any(|prefix| name.starts_with(prefix))
+        && name.split('.').count() == 2
+        && !name.contains(' ')
+}
+
+/// Quote `text` as a DOT string.
+pub fn escape(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', r"\\").replace('"', "\\\""))
+}
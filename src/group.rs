@@ -0,0 +1,50 @@
+//! Rendering the modules of the tree grouped by owner or label, rather than nested.
+
+use std::{collections::BTreeMap, fmt::Write as _, rc::Rc};
+
+use termtree::Tree;
+
+use crate::TreeNode;
+
+/// The heading of the group of modules without any key.
+const UNGROUPED: &str = "(none)";
+
+/// Render the modules of `tree` grouped by the keys `keys` returns for each of them.
+pub fn report(tree: &Tree<TreeNode>, keys: &dyn Fn(&TreeNode) -> Vec<Rc<str>>) -> String {
+    let mut groups: BTreeMap<Rc<str>, Vec<String>> = BTreeMap::new();
+    collect(tree, keys, &mut groups);
+    let mut report = String::new();
+    for (key, modules) in &groups {
+        let _ = writeln!(report, "{key}");
+        for module in modules {
+            let _ = writeln!(report, "  {module}");
+        }
+    }
+    report
+}
+
+fn collect(
+    tree: &Tree<TreeNode>,
+    keys: &dyn Fn(&TreeNode) -> Vec<Rc<str>>,
+    groups: &mut BTreeMap<Rc<str>, Vec<String>>,
+) {
+    let address: &str = if tree.root.address.is_empty() {
+        &tree.root.name
+    } else {
+        &tree.root.address
+    };
+    let entry = match tree.root.location() {
+        Some(location) => format!("{address} ({location})"),
+        None => address.to_owned(),
+    };
+    let mut node_keys = keys(&tree.root);
+    if node_keys.is_empty() {
+        node_keys.push(Rc::from(UNGROUPED));
+    }
+    for key in node_keys {
+        groups.entry(key).or_default().push(entry.clone());
+    }
+    for leaf in &tree.leaves {
+        collect(leaf, keys, groups);
+    }
+}
@@ -0,0 +1,573 @@
+//! A lightweight parser for the structure of HCL files.
+//!
+//! Only the nesting of blocks and attributes is parsed; attribute values are kept as their source
+//! text, which can be further inspected with [`string_literal`] and [`object_entries`].
+
+use std::{iter::Peekable, path::PathBuf, str::CharIndices};
+
+/// The attributes and blocks of a file or block.
+#[derive(Default, Debug, Clone)]
+pub struct Body {
+    pub attributes: Vec<Attribute>,
+    pub blocks: Vec<Block>,
+}
+
+/// An `name = expression` attribute.
+#[derive(Debug, Clone)]
+pub struct Attribute {
+    pub name: String,
+    /// The source text of the expression.
+    pub expression: String,
+}
+
+/// A `kind "label" { ... }` block.
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub kind: String,
+    pub labels: Vec<String>,
+    pub body: Body,
+    /// The override files which modified the block, with the attributes and nested blocks each
+    /// replaced.
+    pub overrides: Vec<(String, Vec<String>)>,
+    /// The file the block is in, only known for the top-level blocks of a module.
+    pub file: PathBuf,
+    /// The line the block starts on.
+    pub line: usize,
+}
+
+impl Body {
+    /// The attribute called `name`.
+    pub fn attribute(&self, name: &str) -> Option<&Attribute> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.name == name)
+    }
+
+    /// The blocks of the given kind.
+    pub fn blocks<'a>(&'a self, kind: &'a str) -> impl Iterator<Item = &'a Block> + 'a {
+        self.blocks.iter().filter(move |block| block.kind == kind)
+    }
+
+    /// The expressions of every attribute, including those of nested blocks.
+    pub fn expressions(&self) -> Vec<&str> {
+        let mut expressions: Vec<&str> = self
+            .attributes
+            .iter()
+            .map(|attribute| attribute.expression.as_str())
+            .collect();
+        for block in &self.blocks {
+            expressions.extend(block.body.expressions());
+        }
+        expressions
+    }
+}
+
+/// Parse the source of a HCL file.
+pub fn parse(source: &str) -> anyhow::Result<Body> {
+    let mut parser = Parser::new(source);
+    let body = parser.body()?;
+    if let Some((offset, c)) = parser.chars.next() {
+        anyhow::bail!("line {}: unexpected `{c}`", parser.line(offset))
+    }
+    Ok(body)
+}
+
+/// The value of a string literal expression without interpolations, e.g. `"./foo"`.
+pub fn string_literal(expression: &str) -> Option<String> {
+    let inner = expression.trim().strip_prefix('"')?.strip_suffix('"')?;
+    if inner.contains("${") || inner.contains("%{") {
+        return None;
+    }
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return None,
+            '\\' => match chars.next()? {
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                'r' => value.push('\r'),
+                c => value.push(c),
+            },
+            c => value.push(c),
+        }
+    }
+    Some(value)
+}
+
+/// The `key = value` entries of an object expression, e.g. `{ aws = aws.west }`, as source text.
+pub fn object_entries(expression: &str) -> Option<Vec<(String, String)>> {
+    let inner = expression.trim().strip_prefix('{')?.strip_suffix('}')?;
+    let mut entries = Vec::new();
+    for item in split_top_level(inner) {
+        let (key, value) = split_key_value(&item)?;
+        let key = string_literal(key).unwrap_or_else(|| key.trim().to_owned());
+        entries.push((key, value.trim().to_owned()));
+    }
+    Some(entries)
+}
+
+/// The items of a tuple expression, e.g. `[module.network, aws_iam_role.deploy]`, as source text.
+pub fn list_items(expression: &str) -> Option<Vec<String>> {
+    let inner = expression.trim().strip_prefix('[')?.strip_suffix(']')?;
+    Some(split_top_level(inner))
+}
+
+/// The variables, locals and module outputs an expression refers to, e.g. `var.replicas` for
+/// `var.replicas * 2`, in order of appearance.
+pub fn references(expression: &str) -> Vec<String> {
+    let is_word = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-');
+    let mut references = Vec::new();
+    let mut rest = expression;
+    while let Some(start) = rest.find(|c: char| is_word(c)) {
+        let word_end = rest[start..]
+            .find(|c: char| !is_word(c) && c != '.')
+            .map_or(rest.len(), |end| start + end);
+        let word = &rest[start..word_end];
+        let preceded = rest[..start].ends_with('.');
+        let mut parts = word.split('.');
+        if let (false, Some(root @ ("var" | "local" | "module")), Some(name)) =
+            (preceded, parts.next(), parts.next())
+        {
+            let reference = format!("{root}.{name}");
+            if !name.is_empty() && !references.contains(&reference) {
+                references.push(reference);
+            }
+        }
+        rest = &rest[word_end..];
+    }
+    references
+}
+
+/// The module outputs an expression refers to, as the names of the module call and the output,
+/// e.g. `vpc` and `id` for `module.vpc.id` or `module.vpc[0].id`. A reference to a whole module,
+/// e.g. `module.vpc`, has no output name, as it may use any of them.
+pub fn output_references(expression: &str) -> Vec<(String, Option<String>)> {
+    let is_word = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-');
+    let word = |text: &str| {
+        let end = text.find(|c: char| !is_word(c)).unwrap_or(text.len());
+        text[..end].to_owned()
+    };
+    let mut references = Vec::new();
+    for (start, prefix) in expression.match_indices("module.") {
+        if expression[..start].ends_with(|c: char| is_word(c) || c == '.') {
+            continue;
+        }
+        let rest = &expression[start + prefix.len()..];
+        let name = word(rest);
+        if name.is_empty() {
+            continue;
+        }
+        // Skip instance keys and splats, e.g. `[0]`, `["eu"]` or `[*]`
+        let mut rest = &rest[name.len()..];
+        while let Some(inner) = rest.strip_prefix('[') {
+            let Some(end) = inner.find(']') else {
+                break;
+            };
+            rest = &inner[end + 1..];
+        }
+        let output = rest
+            .strip_prefix('.')
+            .map(word)
+            .filter(|output| !output.is_empty());
+        let reference = (name, output);
+        if !references.contains(&reference) {
+            references.push(reference);
+        }
+    }
+    references
+}
+
+/// Split on the commas and newlines which aren't nested within brackets or strings.
+fn split_top_level(source: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut parser = Parser::new(source);
+    let mut start = 0;
+    loop {
+        parser.skip_expression(true);
+        let end = parser
+            .chars
+            .peek()
+            .map_or(source.len(), |(offset, _)| *offset);
+        let item = strip_comments(&source[start..end]);
+        if !item.trim().is_empty() {
+            items.push(item.trim().to_owned());
+        }
+        match parser.chars.next() {
+            Some((offset, _)) => start = offset + 1,
+            None => return items,
+        }
+    }
+}
+
+/// Split an object item on its first top-level `=` or `:`.
+fn split_key_value(item: &str) -> Option<(&str, &str)> {
+    let mut parser = Parser::new(item);
+    let mut depth = 0usize;
+    while let Some((offset, c)) = parser.chars.next() {
+        match c {
+            '"' => parser.skip_string(),
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            '=' | ':' if depth == 0 => {
+                if c == '=' && parser.chars.next_if(|(_, c)| *c == '=').is_some() {
+                    continue;
+                }
+                return Some((&item[..offset], &item[offset + 1..]));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Remove `#`, `//` and `/* */` comments which aren't within strings.
+fn strip_comments(source: &str) -> String {
+    let mut stripped = String::with_capacity(source.len());
+    let mut parser = Parser::new(source);
+    let mut start = 0;
+    while let Some(&(offset, c)) = parser.chars.peek() {
+        if c == '"' {
+            parser.chars.next();
+            parser.skip_string();
+        } else if parser.at_comment() {
+            stripped.push_str(&source[start..offset]);
+            parser.skip_comment();
+            start = parser
+                .chars
+                .peek()
+                .map_or(source.len(), |(offset, _)| *offset);
+        } else {
+            parser.chars.next();
+        }
+    }
+    stripped.push_str(&source[start..]);
+    stripped
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    /// The offset up to which lines have been counted, and the line it's on.
+    counted: (usize, usize),
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Self {
+        Parser {
+            source,
+            chars: source.char_indices().peekable(),
+            counted: (0, 1),
+        }
+    }
+
+    /// The line of `offset`, counting on from the last offset asked for, as parsing only advances.
+    fn line(&mut self, offset: usize) -> usize {
+        let (counted, line) = self.counted;
+        let line = if offset >= counted {
+            line + self.source[counted..offset].matches('\n').count()
+        } else {
+            self.source[..offset].matches('\n').count() + 1
+        };
+        self.counted = (offset, line);
+        line
+    }
+
+    fn offset(&mut self) -> usize {
+        self.chars
+            .peek()
+            .map_or(self.source.len(), |(offset, _)| *offset)
+    }
+
+    fn at_comment(&mut self) -> bool {
+        let rest = &self.source[self.offset()..];
+        rest.starts_with('#') || rest.starts_with("//") || rest.starts_with("/*")
+    }
+
+    /// Skip a comment, leaving the newline ending a line comment.
+    fn skip_comment(&mut self) {
+        let offset = self.offset();
+        if self.source[offset..].starts_with("/*") {
+            let end = self.source[offset + 2..]
+                .find("*/")
+                .map_or(self.source.len(), |end| offset + 2 + end + 2);
+            while self.chars.next_if(|(offset, _)| *offset < end).is_some() {}
+        } else {
+            while self.chars.next_if(|(_, c)| *c != '\n').is_some() {}
+        }
+    }
+
+    /// Skip whitespace, newlines and comments.
+    fn skip_blank(&mut self) {
+        loop {
+            while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+            if !self.at_comment() {
+                return;
+            }
+            self.skip_comment();
+        }
+    }
+
+    fn skip_inline_whitespace(&mut self) {
+        while self
+            .chars
+            .next_if(|(_, c)| c.is_whitespace() && *c != '\n')
+            .is_some()
+        {}
+    }
+
+    fn identifier(&mut self) -> String {
+        let mut identifier = String::new();
+        while let Some((_, c)) = self
+            .chars
+            .next_if(|(_, c)| c.is_alphanumeric() || matches!(c, '_' | '-'))
+        {
+            identifier.push(c);
+        }
+        identifier
+    }
+
+    /// Parse attributes and blocks until a closing brace or the end of the source.
+    fn body(&mut self) -> anyhow::Result<Body> {
+        let mut body = Body::default();
+        loop {
+            self.skip_blank();
+            let Some(&(offset, c)) = self.chars.peek() else {
+                return Ok(body);
+            };
+            if c == '}' {
+                return Ok(body);
+            }
+            let line = self.line(offset);
+            let name = self.identifier();
+            if name.is_empty() {
+                anyhow::bail!("line {line}: unexpected `{c}`")
+            }
+            self.skip_inline_whitespace();
+            match self.chars.peek() {
+                Some((_, '=')) => {
+                    self.chars.next();
+                    let start = self.offset();
+                    self.skip_expression(false);
+                    let expression = strip_comments(&self.source[start..self.offset()]);
+                    body.attributes.push(Attribute {
+                        name,
+                        expression: expression.trim().to_owned(),
+                    });
+                }
+                _ => {
+                    let mut labels = Vec::new();
+                    loop {
+                        self.skip_inline_whitespace();
+                        match self.chars.peek() {
+                            Some((_, '{')) => break,
+                            Some((offset, '"')) => {
+                                let start = *offset;
+                                self.chars.next();
+                                self.skip_string();
+                                let literal = &self.source[start..self.offset()];
+                                labels.push(string_literal(literal).unwrap_or_default());
+                            }
+                            Some(_) => {
+                                let label = self.identifier();
+                                if label.is_empty() {
+                                    anyhow::bail!("line {line}: expected a block label or `{{`")
+                                }
+                                labels.push(label);
+                            }
+                            None => anyhow::bail!("line {line}: unterminated block"),
+                        }
+                    }
+                    self.chars.next();
+                    let block_body = self.body()?;
+                    if self.chars.next().is_none() {
+                        anyhow::bail!("line {line}: unterminated block `{name}`")
+                    }
+                    body.blocks.push(Block {
+                        kind: name,
+                        labels,
+                        body: block_body,
+                        overrides: Vec::new(),
+                        file: PathBuf::new(),
+                        line,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Skip an expression, stopping before the newline or closing brace which ends it, or with
+    /// `in_list` also before a top-level comma.
+    fn skip_expression(&mut self, in_list: bool) {
+        let mut depth = 0usize;
+        while let Some(&(offset, c)) = self.chars.peek() {
+            match c {
+                '\n' | ',' if depth == 0 && (in_list || c == '\n') => return,
+                '}' | ']' | ')' if depth == 0 => return,
+                '"' => {
+                    self.chars.next();
+                    self.skip_string();
+                    continue;
+                }
+                '<' if self.source[offset..].starts_with("<<") => {
+                    self.skip_heredoc();
+                    continue;
+                }
+                '#' | '/' if self.at_comment() => {
+                    self.skip_comment();
+                    continue;
+                }
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                _ => {}
+            }
+            self.chars.next();
+        }
+    }
+
+    /// Skip the rest of a quoted template, after its opening quote, including interpolations.
+    fn skip_string(&mut self) {
+        while let Some((offset, c)) = self.chars.next() {
+            match c {
+                '"' => return,
+                '\\' => {
+                    self.chars.next();
+                }
+                '$' | '%' if self.source[offset + 1..].starts_with('{') => {
+                    self.chars.next();
+                    self.skip_expression(false);
+                    while self.chars.next_if(|(_, c)| *c == '\n').is_some() {
+                        self.skip_expression(false);
+                    }
+                    self.chars.next();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Skip a `<<EOF` or `<<-EOF` heredoc.
+    fn skip_heredoc(&mut self) {
+        let start = self.offset();
+        let header_end = self.source[start..]
+            .find('\n')
+            .map_or(self.source.len(), |end| start + end);
+        let delimiter = self.source[start..header_end]
+            .trim_start_matches('<')
+            .trim_start_matches('-')
+            .trim();
+        let mut end = self.source.len();
+        let mut line_start = header_end + 1;
+        while line_start < self.source.len() {
+            let line_end = self.source[line_start..]
+                .find('\n')
+                .map_or(self.source.len(), |end| line_start + end);
+            if self.source[line_start..line_end].trim() == delimiter {
+                end = line_end;
+                break;
+            }
+            line_start = line_end + 1;
+        }
+        while self.chars.next_if(|(offset, _)| *offset < end).is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"
+# The network
+module "vpc" {
+  source = "./modules/vpc" // a local module
+  cidr   = "10.0.0.0/16"
+  tags = {
+    Name = "main"
+    Team = "${var.team}-net"
+  }
+
+  /* a block comment
+     spanning lines */
+  providers = { aws = aws.west, "google" = google }
+}
+
+locals {
+  policy = <<-EOT
+    { "braces": "}" }
+  EOT
+  zones = [
+    "a",
+    "b",
+  ]
+}
+"#;
+
+    #[test]
+    fn blocks_and_attributes() {
+        let body = parse(SOURCE).unwrap();
+        assert_eq!(body.blocks.len(), 2);
+        let vpc = body.blocks("module").next().unwrap();
+        assert_eq!(vpc.labels, ["vpc"]);
+        assert_eq!(vpc.line, 3);
+        assert_eq!(
+            vpc.body.attribute("source").unwrap().expression,
+            r#""./modules/vpc""#
+        );
+        let tags = &vpc.body.attribute("tags").unwrap().expression;
+        assert!(tags.starts_with('{') && tags.ends_with('}'));
+        assert_eq!(
+            object_entries(&vpc.body.attribute("providers").unwrap().expression).unwrap(),
+            [
+                ("aws".to_owned(), "aws.west".to_owned()),
+                ("google".to_owned(), "google".to_owned()),
+            ]
+        );
+        let locals = body.blocks("locals").next().unwrap();
+        assert_eq!(locals.line, 16);
+        let policy = &locals.body.attribute("policy").unwrap().expression;
+        assert!(policy.starts_with("<<-EOT") && policy.ends_with("EOT"));
+        assert_eq!(
+            list_items(&locals.body.attribute("zones").unwrap().expression).unwrap(),
+            [r#""a""#, r#""b""#]
+        );
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let error = parse("module \"a\" {\n  source = \"./a\"\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 1: unterminated block `module`");
+        let error = parse("a = 1\n\n}").unwrap_err();
+        assert_eq!(error.to_string(), "line 3: unexpected `}`");
+        let error = parse("a = 1\n= 2").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: unexpected `=`");
+    }
+
+    #[test]
+    fn string_literals() {
+        assert_eq!(string_literal(r#" "./foo" "#).as_deref(), Some("./foo"));
+        assert_eq!(string_literal(r#""a\"b\n""#).as_deref(), Some("a\"b\n"));
+        assert_eq!(string_literal(r#""${var.name}""#), None);
+        assert_eq!(string_literal("var.name"), None);
+    }
+
+    #[test]
+    fn references_in_order() {
+        assert_eq!(
+            references("var.replicas * length(local.zones) + module.vpc.count + var.replicas"),
+            ["var.replicas", "local.zones", "module.vpc"]
+        );
+        assert!(references("data.aws_ami.var.id + each.value").is_empty());
+    }
+
+    #[test]
+    fn output_references_of_modules() {
+        assert_eq!(
+            output_references("module.vpc.id, module.dns[0].zone, module.app[*], my_module.x"),
+            [
+                ("vpc".to_owned(), Some("id".to_owned())),
+                ("dns".to_owned(), Some("zone".to_owned())),
+                ("app".to_owned(), None),
+            ]
+        );
+    }
+}
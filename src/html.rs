@@ -0,0 +1,171 @@
+//! Rendering the tree as a standalone HTML page, to publish as a CI artifact.
+
+use std::fmt::Write as _;
+
+use termtree::Tree;
+
+use crate::{render::Render, TreeNode};
+
+/// A page of the tree, with each module collapsible and followed by its metadata: its source,
+/// `count`, `for_each` keys, version constraint, number of resources per provider, and markers.
+/// The search box shows only the modules whose name, address or source contains the query, and
+/// their ancestors.
+///
+/// Everything, including styles and script, is inline, so the page has no dependencies.
+pub struct Html;
+
+impl Render for Html {
+    fn render(&self, tree: &Tree<TreeNode>, out: &mut String) -> anyhow::Result<()> {
+        let mut main = String::from("<ul>\n");
+        write_tree(&mut main, tree)?;
+        main.push_str("</ul>\n");
+        let title = match &*tree.root.name {
+            "*" => "Module tree",
+            name => name,
+        };
+        out.push_str(&page(title, &[main]));
+        Ok(())
+    }
+}
+
+const STYLE: &str = "\
+body { font: 14px/1.5 system-ui, sans-serif; margin: 2em; color: #1f2328; }
+input { font: inherit; padding: 0.3em 0.5em; width: 24em; margin-bottom: 1em; }
+ul { list-style: none; margin: 0; padding-left: 1.5em; }
+main > ul { padding-left: 0; }
+summary, .leaf { cursor: default; }
+.leaf { padding-left: 1.1em; }
+.name { font-weight: 600; }
+.meta { color: #59636e; margin-left: 0.5em; }
+.marker { background: #ddf4ff; border-radius: 0.8em; padding: 0 0.5em; margin-left: 0.5em; }
+.match > details > summary .name, .match > .leaf .name { background: #fff8c5; }
+.hidden { display: none; }
+";
+
+const SCRIPT: &str = "\
+const search = document.getElementById('search');
+search.addEventListener('input', () => {
+  const query = search.value.trim().toLowerCase();
+  const filter = (item) => {
+    const matches = query !== '' && item.dataset.search.includes(query);
+    let shown = query === '' || matches;
+    for (const child of item.querySelectorAll(':scope > details > ul > li')) {
+      shown = filter(child) || shown;
+    }
+    item.classList.toggle('match', matches);
+    item.classList.toggle('hidden', !shown);
+    const details = item.querySelector(':scope > details');
+    if (details && query !== '') details.open = shown;
+    return shown;
+  };
+  for (const item of document.querySelectorAll('main > ul > li')) filter(item);
+});
+";
+
+/// A page titled `title` with the search box above each of the rendered trees in `mains`.
+pub fn page(title: &str, mains: &[String]) -> String {
+    let title = escape(title);
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n<style>\n{STYLE}</style>\n</head>\n<body>\n\
+         <input id=\"search\" type=\"search\" placeholder=\"Search modules\" autofocus>\n"
+    );
+    for main in mains {
+        let _ = write!(out, "<main>\n{main}</main>\n");
+    }
+    let _ = write!(out, "<script>\n{SCRIPT}</script>\n</body>\n</html>\n");
+    out
+}
+
+/// The tree within each `<main>` element of `page`, to combine several pages into one.
+pub fn mains(page: &str) -> Vec<String> {
+    page.split("<main>\n")
+        .skip(1)
+        .filter_map(|main| main.split_once("</main>\n"))
+        .map(|(main, _)| main.to_owned())
+        .collect()
+}
+
+/// Write `tree` as a list item of nested lists, with a `<details>` element for each module with
+/// children.
+fn write_tree(out: &mut String, tree: &Tree<TreeNode>) -> anyhow::Result<()> {
+    let node = &tree.root;
+    let search = format!(
+        "{} {} {}",
+        node.name,
+        node.address,
+        node.location().unwrap_or_default()
+    );
+    write!(
+        out,
+        "<li data-search=\"{}\">",
+        escape(&search.to_lowercase())
+    )?;
+    if tree.leaves.is_empty() {
+        write!(out, "<div class=\"leaf\">")?;
+        write_node(out, node)?;
+        writeln!(out, "</div></li>")?;
+    } else {
+        // Collapsing the root would hide everything
+        let open = if node.address.is_empty() { " open" } else { "" };
+        write!(out, "<details{open}><summary>")?;
+        write_node(out, node)?;
+        writeln!(out, "</summary>\n<ul>")?;
+        for leaf in &tree.leaves {
+            write_tree(out, leaf)?;
+        }
+        writeln!(out, "</ul></details></li>")?;
+    }
+    Ok(())
+}
+
+/// Write the label of `node` and its metadata.
+fn write_node(out: &mut String, node: &TreeNode) -> anyhow::Result<()> {
+    let mut label = String::new();
+    for chained in &node.chain {
+        write!(label, "{chained} ▸ ")?;
+    }
+    node.write_label(&mut label)?;
+    write!(out, "<span class=\"name\">{}</span>", escape(&label))?;
+    let mut meta = Vec::new();
+    if let Some(location) = node.location() {
+        meta.push(location);
+    }
+    if let Some(version) = &node.version {
+        meta.push(format!("version {version}"));
+    }
+    if !node.providers.is_empty() {
+        let total: usize = node.providers.values().sum();
+        let providers: Vec<String> = node
+            .providers
+            .iter()
+            .map(|(provider, count)| format!("{provider}: {count}"))
+            .collect();
+        let plural = if total == 1 { "" } else { "s" };
+        meta.push(format!(
+            "{total} resource{plural} ({})",
+            providers.join(", ")
+        ));
+    }
+    if !meta.is_empty() {
+        write!(
+            out,
+            "<span class=\"meta\">{}</span>",
+            escape(&meta.join(" · "))
+        )?;
+    }
+    for marker in &node.markers {
+        write!(out, "<span class=\"marker\">{}</span>", escape(marker))?;
+    }
+    Ok(())
+}
+
+/// Replace the characters which are markup in text and attribute values with their entities.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
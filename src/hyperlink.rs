@@ -0,0 +1,22 @@
+//! Terminal hyperlinks, as OSC 8 escape sequences.
+
+use std::{fmt, path::Path};
+
+/// The `file://` URL of the absolute `path`, percent-encoding the bytes which aren't allowed in
+/// URL paths.
+pub fn file_url(path: &Path) -> String {
+    let mut url = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            url.push(char::from(byte));
+        } else {
+            url.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    url
+}
+
+/// Write `text` to `f` as a hyperlink to `url`, which terminals without support show as plain text.
+pub fn write(f: &mut impl fmt::Write, url: &str, text: &str) -> fmt::Result {
+    write!(f, "\x1b]8;;{url}\x1b\\{text}\x1b]8;;\x1b\\")
+}
@@ -0,0 +1,82 @@
+//! Which modules a set of changed files affects, to review or plan only those.
+
+use std::{
+    env, fs,
+    io::{self, Read as _},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+use termtree::Tree;
+
+use crate::{intern::Interner, TreeNode};
+
+/// The extensions of files which can affect a module.
+pub const EXTENSIONS: [&str; 4] = [".tf", ".tf.json", ".tfvars", ".tfvars.json"];
+
+/// Read the list of changed files, one per line, from `path` or from stdin when it's `None` or
+/// `-`.
+///
+/// Only terraform files are returned, as absolute paths.
+pub fn changed_files(path: Option<&Path>) -> anyhow::Result<Vec<PathBuf>> {
+    let contents = match path {
+        Some(path) if path != Path::new("-") => fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?,
+        _ => {
+            let mut contents = String::new();
+            io::stdin()
+                .read_to_string(&mut contents)
+                .context("failed to read stdin")?;
+            contents
+        }
+    };
+    let current_dir = env::current_dir().context("could not detect current directory")?;
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| EXTENSIONS.iter().any(|extension| line.ends_with(extension)))
+        .map(|line| current_dir.join(line))
+        .collect())
+}
+
+/// The deduplicated directories containing `files`.
+pub fn parent_dirs(files: &[PathBuf]) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    for dir in files.iter().filter_map(|file| file.parent()) {
+        // Deleted files still affect their module, so fall back to the unresolved directory
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_owned());
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+/// Mark every module whose source directory is in `dirs` with `marker`, returning whether any
+/// module was marked.
+pub fn mark(
+    tree: &mut Tree<TreeNode>,
+    base: &Path,
+    dirs: &[PathBuf],
+    marker: &str,
+    interner: &mut Interner,
+) -> bool {
+    let mut marked = false;
+    if dirs.iter().any(|dir| *dir == base.join(&*tree.root.source)) {
+        tree.root.markers.push(interner.str(marker));
+        marked = true;
+    }
+    for leaf in &mut tree.leaves {
+        marked |= mark(leaf, base, dirs, marker, interner);
+    }
+    marked
+}
+
+/// Remove every branch without a marked module, keeping the whole subtree beneath marked modules.
+pub fn prune(tree: &mut Tree<TreeNode>) -> bool {
+    if !tree.root.markers.is_empty() {
+        return true;
+    }
+    tree.leaves.retain_mut(prune);
+    !tree.leaves.is_empty()
+}
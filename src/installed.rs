@@ -0,0 +1,55 @@
+//! The modules installed by `terraform init`, as recorded in its module manifest.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::TreaformError;
+
+#[derive(Deserialize)]
+struct Manifest {
+    #[serde(rename = "Modules", default)]
+    modules: Vec<Module>,
+}
+
+/// An installed module call.
+#[derive(Deserialize, Debug)]
+pub struct Module {
+    /// The names of the module call and its ancestors joined by `.`, e.g. `platform.dns`, empty
+    /// for the root module.
+    #[serde(rename = "Key")]
+    pub key: String,
+    /// The source address, normalized by terraform, e.g. with the registry host.
+    #[serde(rename = "Source")]
+    pub source: String,
+    /// The selected version of a registry module.
+    #[serde(rename = "Version")]
+    pub version: Option<String>,
+    /// The directory holding the module, relative to the root module.
+    #[serde(rename = "Dir")]
+    pub dir: PathBuf,
+}
+
+/// The terraform data directory of the project at `dir`, as overridden by `TF_DATA_DIR`.
+pub fn data_dir(dir: &Path) -> PathBuf {
+    dir.join(env::var_os("TF_DATA_DIR").unwrap_or_else(|| ".terraform".into()))
+}
+
+/// The modules installed for the project at `dir`, or none if it hasn't been initialized.
+pub fn load(dir: &Path) -> Result<Vec<Module>, TreaformError> {
+    let path = data_dir(dir).join("modules").join("modules.json");
+    let json = match fs::read_to_string(&path) {
+        Ok(json) => json,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(TreaformError::Io { path, error }),
+    };
+    let manifest: Manifest =
+        serde_json::from_str(&json).map_err(|error| TreaformError::Deserialize {
+            what: path.display().to_string(),
+            error,
+        })?;
+    Ok(manifest.modules)
+}
@@ -0,0 +1,33 @@
+//! Sharing the strings and paths repeated across the nodes of large trees.
+
+use std::{collections::HashSet, path::PathBuf, rc::Rc};
+
+/// Deduplicates the strings and paths repeated across the nodes of large trees, so that each
+/// distinct value is only allocated once.
+#[derive(Default)]
+pub struct Interner {
+    strings: HashSet<Rc<str>>,
+    paths: HashSet<Rc<PathBuf>>,
+}
+
+impl Interner {
+    /// The shared copy of `string`.
+    pub fn str(&mut self, string: &str) -> Rc<str> {
+        if let Some(interned) = self.strings.get(string) {
+            return interned.clone();
+        }
+        let interned: Rc<str> = Rc::from(string);
+        self.strings.insert(interned.clone());
+        interned
+    }
+
+    /// The shared copy of `path`.
+    pub fn path(&mut self, path: PathBuf) -> Rc<PathBuf> {
+        if let Some(interned) = self.paths.get(&path) {
+            return interned.clone();
+        }
+        let interned = Rc::new(path);
+        self.paths.insert(interned.clone());
+        interned
+    }
+}
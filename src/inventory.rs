@@ -0,0 +1,206 @@
+//! A flat bill of materials of the modules a project depends on, for supply chain reviews.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::json;
+use termtree::Tree;
+
+use crate::{
+    installed,
+    source::{self, Source},
+    visit, TreeNode,
+};
+
+/// The format of the inventory.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Format {
+    /// A JSON array of modules, each with its 'kind', 'source', 'version', 'constraint', 'ref',
+    /// 'path' and 'calls'.
+    Json,
+    /// A header and one row per module, with the calls separated by ';'.
+    Csv,
+    /// A CycloneDX 1.5 JSON bill of materials, with a library component per module.
+    Cyclonedx,
+}
+
+/// A module the project depends on, shared by all of its calls.
+#[derive(Serialize)]
+struct Dependency {
+    /// `local`, `registry`, `git` or `other`.
+    kind: &'static str,
+    /// The source address of a remote module, or the directory of a local module relative to the
+    /// root module, e.g. `./modules/vpc`.
+    source: String,
+    /// The version of a registry module selected by `terraform init`.
+    version: Option<String>,
+    /// The `version` constraint of the calls of a registry module.
+    constraint: Option<String>,
+    /// The `ref` of a git source.
+    #[serde(rename = "ref")]
+    reference: Option<String>,
+    /// The module's directory, relative to the root module unless `--paths absolute`, unless it
+    /// isn't installed.
+    path: Option<PathBuf>,
+    /// The addresses of the module's calls.
+    calls: Vec<String>,
+}
+
+/// The inventory of the modules of `tree`, the project at `dir` named `name`, in `format`.
+pub fn report(
+    tree: &mut Tree<TreeNode>,
+    dir: &Path,
+    name: &str,
+    format: Format,
+) -> anyhow::Result<String> {
+    let installed: HashMap<String, String> = installed::load(dir)?
+        .into_iter()
+        .filter_map(|module| Some((module.key, module.version?)))
+        .collect();
+    // By the module's directory, which every call of the module shares
+    let mut dependencies: BTreeMap<PathBuf, Dependency> = BTreeMap::new();
+    visit(tree, &mut |node| {
+        // The root module is no dependency
+        if node.address.is_empty() {
+            return;
+        }
+        let dependency = dependencies
+            .entry(node.source.to_path_buf())
+            .or_insert_with(|| dependency(node));
+        if dependency.kind == "registry" && dependency.version.is_none() {
+            let key = node
+                .address
+                .strip_prefix("module.")
+                .unwrap_or_default()
+                .replace(".module.", ".");
+            dependency.version = installed.get(&key).cloned();
+        }
+        if dependency.constraint.is_none() {
+            dependency.constraint = node.version.clone();
+        }
+        dependency.calls.push(node.address.to_string());
+    });
+    let dependencies: Vec<Dependency> = dependencies.into_values().collect();
+    Ok(match format {
+        Format::Json => serde_json::to_string_pretty(&dependencies)? + "\n",
+        Format::Csv => csv(&dependencies),
+        Format::Cyclonedx => cyclonedx(&dependencies, name)?,
+    })
+}
+
+/// The dependency of the module call `node`, without its calls.
+fn dependency(node: &TreeNode) -> Dependency {
+    let (kind, reference) = match node.remote.as_deref().map(source::parse) {
+        None => ("local", None),
+        Some(Source::Registry { .. }) => ("registry", None),
+        Some(Source::Git { reference, .. }) => ("git", reference.map(str::to_owned)),
+        Some(_) => ("other", None),
+    };
+    let source = match &node.remote {
+        Some(remote) => remote.to_string(),
+        None if node.source.is_absolute() || node.source.starts_with("..") => {
+            node.source.display().to_string()
+        }
+        None => format!("./{}", node.source.display()),
+    };
+    Dependency {
+        kind,
+        source,
+        version: None,
+        constraint: None,
+        reference,
+        path: (!node.unresolved).then(|| node.source.to_path_buf()),
+        calls: Vec::new(),
+    }
+}
+
+/// `dependencies` as CSV, quoting fields as RFC 4180 does.
+fn csv(dependencies: &[Dependency]) -> String {
+    fn field(value: &str) -> String {
+        if value.contains([',', '"', '\n']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_owned()
+        }
+    }
+    let mut out = String::from("kind,source,version,constraint,ref,path,calls\n");
+    for dependency in dependencies {
+        let path = dependency
+            .path
+            .as_ref()
+            .map(|path| path.display().to_string());
+        let fields = [
+            dependency.kind,
+            &dependency.source,
+            dependency.version.as_deref().unwrap_or_default(),
+            dependency.constraint.as_deref().unwrap_or_default(),
+            dependency.reference.as_deref().unwrap_or_default(),
+            path.as_deref().unwrap_or_default(),
+            &dependency.calls.join(";"),
+        ];
+        let fields: Vec<String> = fields.into_iter().map(field).collect();
+        let _ = writeln!(out, "{}", fields.join(","));
+    }
+    out
+}
+
+/// `dependencies` as a CycloneDX bill of materials of the application `name`.
+///
+/// Components have no `purl`, as there's no package URL type for terraform modules. The paths,
+/// constraints and calls are `treaform:` properties.
+fn cyclonedx(dependencies: &[Dependency], name: &str) -> anyhow::Result<String> {
+    let components: Vec<serde_json::Value> = dependencies
+        .iter()
+        .map(|dependency| {
+            let mut properties = vec![json!({"name": "treaform:kind", "value": dependency.kind})];
+            if let Some(constraint) = &dependency.constraint {
+                properties.push(json!({"name": "treaform:constraint", "value": constraint}));
+            }
+            if let Some(path) = &dependency.path {
+                properties.push(json!({"name": "treaform:path", "value": path}));
+            }
+            for call in &dependency.calls {
+                properties.push(json!({"name": "treaform:call", "value": call}));
+            }
+            let mut component = json!({
+                "type": "library",
+                "bom-ref": dependency.source,
+                "name": dependency.source,
+                "properties": properties,
+            });
+            if let Some(version) = dependency
+                .version
+                .as_ref()
+                .or(dependency.reference.as_ref())
+            {
+                component["version"] = json!(version);
+            }
+            if let Source::Git { url, .. } = source::parse(&dependency.source) {
+                component["externalReferences"] = json!([{"type": "vcs", "url": url}]);
+            }
+            component
+        })
+        .collect();
+    let bom = json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "tools": {
+                "components": [{
+                    "type": "application",
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                }],
+            },
+            "component": {"type": "application", "name": name},
+        },
+        "components": components,
+    });
+    Ok(serde_json::to_string_pretty(&bom)? + "\n")
+}
@@ -0,0 +1,147 @@
+//! Rendering the tree as a JSON document, for other tools to consume.
+
+use std::{collections::BTreeMap, path::Path};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use termtree::Tree;
+
+use crate::{plan::Input, render::Render, TreeNode};
+
+/// A JSON document of nested modules, with the value of each `for_each` instance if
+/// `full_values` is set.
+pub struct Json {
+    pub full_values: bool,
+}
+
+/// The version of the JSON output's schema, incremented by incompatible changes to it.
+pub const FORMAT_VERSION: &str = "1";
+
+impl Render for Json {
+    fn render(&self, tree: &Tree<TreeNode>, out: &mut String) -> anyhow::Result<()> {
+        let mut json = JsonNode::new(tree, self.full_values);
+        json.format_version = Some(FORMAT_VERSION);
+        out.push_str(&serde_json::to_string_pretty(&json)?);
+        out.push('\n');
+        Ok(())
+    }
+}
+
+/// A module in the JSON output. Optional fields are omitted rather than null, and children are
+/// sorted by name.
+#[derive(Serialize)]
+struct JsonNode<'a> {
+    /// The version of the schema, e.g. `"1"`, on the root module only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format_version: Option<&'static str>,
+    /// The name of the module call, or `*` for the root module.
+    name: &'a str,
+    /// The address, e.g. `module.app.module.db`, empty for the root module. Only the module
+    /// instances of --expand-instances, and the modules within them, have instance keys.
+    address: &'a str,
+    /// The instance key, e.g. `[0]` or `["eu"]`, of a module instance with --expand-instances.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instance: Option<&'a str>,
+    /// The module's directory relative to the root module, empty for the root module.
+    source: &'a Path,
+    /// The source address of a module call which isn't a local path, e.g.
+    /// `terraform-aws-modules/vpc/aws`. Its `source` is where `terraform init` installed it, or
+    /// the same address if it isn't installed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remote_source: Option<&'a str>,
+    /// The constant `count` of the module call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    count: Option<usize>,
+    /// The instance keys of a constant `for_each`, or with --full-values an object of each
+    /// instance's value by key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    for_each: Option<ForEach<'a>>,
+    /// What a `count` which isn't constant refers to, e.g. `["var.replicas"]`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    count_references: Option<&'a [String]>,
+    /// What a `for_each` which isn't constant refers to, e.g. `["local.environments"]`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    for_each_references: Option<&'a [String]>,
+    /// The `version` constraint of the module call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<&'a str>,
+    /// The `depends_on` meta-argument of the module call.
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    depends_on: Vec<&'a str>,
+    /// The input variables passed to the module call with --vars, by name, each as
+    /// `{"constant_value": ...}`, `{"references": [...]}`, `{"sensitive": true}`, or `{}` when
+    /// computed.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    inputs: BTreeMap<&'a str, Value>,
+    /// The addresses of the planned resources within the module, e.g. `aws_s3_bucket.logs`, with
+    /// --resources.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    resources: Vec<&'a str>,
+    /// The modules called by this one.
+    children: Vec<JsonNode<'a>>,
+}
+
+/// The `for_each` instance keys, or with `--full-values` the value of each instance.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum ForEach<'a> {
+    Keys(Vec<&'a str>),
+    Values(BTreeMap<&'a str, &'a Value>),
+}
+
+impl<'a> JsonNode<'a> {
+    fn new(tree: &'a Tree<TreeNode>, full_values: bool) -> Self {
+        let node = &tree.root;
+        let for_each = node.for_each.as_ref().map(|for_each| {
+            if full_values {
+                ForEach::Values(
+                    for_each
+                        .iter()
+                        .map(|(key, value)| (key.as_str(), value))
+                        .collect(),
+                )
+            } else {
+                ForEach::Keys(for_each.keys().map(String::as_str).collect())
+            }
+        });
+        JsonNode {
+            format_version: None,
+            name: &node.name,
+            address: &node.address,
+            instance: node.instance.as_deref(),
+            source: &node.source,
+            remote_source: node.remote.as_deref(),
+            count: node.count,
+            for_each,
+            count_references: node.count_references.as_deref(),
+            for_each_references: node.for_each_references.as_deref(),
+            version: node.version.as_deref(),
+            depends_on: node.depends_on.iter().map(|address| &**address).collect(),
+            inputs: node
+                .inputs
+                .iter()
+                .map(|(name, input)| {
+                    let value = match input {
+                        Input::Constant(value) => json!({ "constant_value": value }),
+                        Input::References(references) => json!({ "references": references }),
+                        Input::Computed => json!({}),
+                        Input::Sensitive => json!({ "sensitive": true }),
+                    };
+                    (name.as_str(), value)
+                })
+                .collect(),
+            resources: tree
+                .leaves
+                .iter()
+                .filter(|leaf| leaf.root.resource)
+                .map(|leaf| &*leaf.root.name)
+                .collect(),
+            children: tree
+                .leaves
+                .iter()
+                .filter(|leaf| !leaf.root.resource)
+                .map(|leaf| JsonNode::new(leaf, full_values))
+                .collect(),
+        }
+    }
+}
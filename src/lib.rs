@@ -0,0 +1,2933 @@
+//! Print the module structure of a Terraform project.
+//!
+//! The module tree can also be built from plans directly, with [`plan::Show`] and [`module_tree`].
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BTreeMap, HashMap, HashSet},
+    convert::Infallible,
+    env,
+    fmt::{self, Write as _},
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{self, IsTerminal as _, Write as _},
+    mem,
+    path::{Path, PathBuf},
+    process::{self, Output, Stdio},
+    rc::Rc,
+    str::FromStr,
+};
+
+use anonymize::Anonymizer;
+use anyhow::Context as _;
+use clap::{
+    parser::ValueSource, ArgAction, CommandFactory, FromArgMatches as _, Parser, Subcommand,
+    ValueEnum,
+};
+use clap_complete::ArgValueCompleter;
+use codeowners::CodeOwners;
+use color::{Color, Coloring};
+use completions::Shell;
+use config::{Budget, Config, Level};
+use glyph::Glyphs;
+use intern::Interner;
+use plan::{Action, Input, Module, ResourceChange, Show};
+use render::Render;
+use runner::Runner;
+use scan::Scanner;
+use source::Source;
+use style::Style;
+use termtree::Tree;
+use warnings::Warning;
+
+mod anonymize;
+mod budget;
+mod cache;
+mod callers;
+mod check;
+mod codeowners;
+mod color;
+mod completions;
+mod config;
+mod configuration;
+mod constraints;
+mod coverage;
+mod d2;
+mod diagnostics;
+mod diff;
+mod discover;
+mod dot;
+mod environment;
+mod error;
+mod fetch;
+mod filter;
+mod fleet;
+mod git;
+mod github;
+mod glob;
+mod glyph;
+mod graph;
+mod group;
+mod hcl;
+mod html;
+mod hyperlink;
+mod impact;
+mod installed;
+mod intern;
+mod inventory;
+mod json;
+mod lint;
+mod markdown;
+mod mermaid;
+mod metadata;
+mod metrics;
+mod open;
+mod outputs;
+pub mod plan;
+mod plantuml;
+mod pool;
+mod progress;
+mod prompt;
+mod providers;
+mod registry;
+mod render;
+mod runner;
+mod scan;
+mod serve;
+mod source;
+mod state;
+mod stats;
+mod style;
+mod terragrunt;
+mod timings;
+mod toml;
+mod verify;
+mod warnings;
+mod watch;
+mod why;
+mod workspace;
+
+pub use error::TreaformError;
+
+/// Print the module structure of a Terraform project
+#[derive(Parser, Clone, Debug)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Load variable values from the given file, in addition to the default files terraform.tfvars
+    /// and *.auto.tfvars. Use this option more than once to include more than one variables file.
+    #[arg(long, global = true)]
+    var_file: Vec<String>,
+    /// 'foo=bar'. Set a value for one of the input variables in the root module of the configuration. Use
+    /// this option more than once to set more than one variable.
+    #[arg(long, global = true)]
+    var: Vec<String>,
+    /// Set the environment variables of the given file's 'NAME=value' lines, e.g.
+    /// 'TF_VAR_region=eu-west-1', for terraform. Use this option more than once to load more than
+    /// one file, with later files taking precedence.
+    #[arg(long, global = true, value_name = "PATH")]
+    env_file: Vec<PathBuf>,
+    /// Run terraform with only the environment variables it needs to run, e.g. PATH and HOME,
+    /// those of --allow-env and those of --env-file, rather than the whole environment, so that
+    /// plans don't depend on it.
+    #[arg(long, global = true)]
+    isolate_env: bool,
+    /// Keep the environment variables matching the given glob pattern with --isolate-env, e.g.
+    /// 'AWS_*'. Use this option more than once to keep several kinds of variable.
+    #[arg(long, global = true, requires = "isolate_env", value_name = "PATTERN")]
+    allow_env: Vec<String>,
+    /// Force replacement of a particular resource instance using its resource address. Use this
+    /// option more than once to replace more than one object.
+    #[arg(long, global = true)]
+    replace: Vec<String>,
+    /// Limit planning to only the given resource instance and its dependencies. Use this option
+    /// more than once to target more than one object.
+    #[arg(long, global = true)]
+    target: Vec<String>,
+    /// Whether to update the state from the remote objects before planning. Skipping this makes
+    /// planning faster, at the cost of missing changes made outside terraform.
+    #[arg(long, global = true, default_value_t = true, action = ArgAction::Set, value_name = "BOOL")]
+    refresh: bool,
+    /// Whether to hold the state lock while planning.
+    #[arg(long, global = true, default_value_t = true, action = ArgAction::Set, value_name = "BOOL")]
+    lock: bool,
+    /// Whether to prompt for the values of required variables which are unset, and plan again,
+    /// when running in a terminal. Values of sensitive variables aren't echoed.
+    #[arg(long, global = true, default_value_t = true, action = ArgAction::Set, value_name = "BOOL")]
+    input: bool,
+    /// How long to retry acquiring the state lock for, e.g. `30s`.
+    #[arg(long, global = true, value_name = "DURATION")]
+    lock_timeout: Option<String>,
+    /// Plan to destroy all the remote objects, rather than to converge them with the
+    /// configuration.
+    #[arg(long, global = true)]
+    destroy: bool,
+    /// Pass an extra argument to `terraform plan`, e.g. `--tf-arg=-refresh-only`. Use this option
+    /// more than once to pass more than one argument.
+    #[arg(long, global = true, allow_hyphen_values = true, value_name = "ARG")]
+    tf_arg: Vec<String>,
+    /// Show warnings in a more compact form that includes only the summary messages.
+    #[arg(long, global = true)]
+    compact_warnings: bool,
+    /// Hide plan warnings whose summary matches the given glob pattern. Use this option more than
+    /// once to suppress more than one kind of warning.
+    #[arg(long, global = true)]
+    suppress_warning: Vec<String>,
+    /// Fail when a plan warning's summary matches the given glob pattern. Use this option more
+    /// than once to escalate more than one kind of warning.
+    #[arg(long, global = true)]
+    warning_as_error: Vec<String>,
+    /// Only show modules containing resources from providers matching the given glob pattern, e.g.
+    /// 'aws' or 'google*', planned resource instances when the plan has them. Use this option more
+    /// than once to match any of several providers.
+    #[arg(long)]
+    provider: Vec<String>,
+    /// Only show modules, and their ancestors, whose name, source path or remote source matches
+    /// the given glob pattern, e.g. '*vpc*'. Use this option more than once to match any of
+    /// several patterns.
+    #[arg(long)]
+    filter: Vec<String>,
+    /// Hide modules, and everything beneath them, whose name, source path or remote source matches
+    /// the given glob pattern, e.g. 'modules/legacy/**'. Use this option more than once to hide
+    /// several kinds of module.
+    #[arg(long)]
+    exclude: Vec<String>,
+    /// Show the number of resources in each module's subtree, broken down by provider: the
+    /// resource instances of the plan, or the resource blocks without one.
+    #[arg(long)]
+    wide: bool,
+    /// Mark modules whose source directories contain modified or untracked files.
+    #[arg(long)]
+    git_dirty: bool,
+    /// Mark modules whose source directories contain files changed between the given git ref and
+    /// HEAD, e.g. 'origin/main'.
+    #[arg(long)]
+    since: Option<String>,
+    /// Annotate modules with their owners from the repository's CODEOWNERS file.
+    #[arg(long)]
+    owners: bool,
+    /// Annotate modules with the number of `run` blocks exercising them in the .tftest.hcl files
+    /// of the project's modules, and list the untested modules.
+    #[arg(long)]
+    tests: bool,
+    /// Only show modules whose metadata labels match 'key=pattern', where the pattern is a glob.
+    /// Use this option more than once to require several labels.
+    #[arg(long)]
+    label: Vec<String>,
+    /// Print the modules grouped by 'owner' (from CODEOWNERS) or by the value of the given metadata
+    /// label, instead of as a tree.
+    #[arg(long)]
+    group_by: Option<GroupBy>,
+    /// The output format.
+    #[arg(long, value_enum, default_value_t = Format::Tree)]
+    format: Format,
+    /// Write the output to this file instead of stdout.
+    #[arg(long)]
+    output: Option<PathBuf>,
+    /// Integrate with the given CI system: fold the output into groups, annotate the module calls
+    /// breaking lint rules or resource budgets, and add the tree to the job summary.
+    #[arg(long, value_enum, global = true)]
+    ci: Option<Ci>,
+    /// Include the value of each `for_each` instance, rather than only its key, in JSON output.
+    #[arg(long)]
+    full_values: bool,
+    /// How to show the directory of each module.
+    #[arg(long, value_enum, default_value_t = Paths::Relative)]
+    paths: Paths,
+    /// Show one child per instance beneath modules using `count` or `for_each`, with the instance
+    /// keys evaluated in the plan.
+    #[arg(long)]
+    expand_instances: bool,
+    /// Annotate modules with the number of their resources the plan adds, changes and destroys,
+    /// e.g. '+3 ~1 -0'.
+    #[arg(long)]
+    changes: bool,
+    /// Only show modules with resources the plan adds, changes or destroys, and their ancestors.
+    /// Implies --changes.
+    #[arg(long)]
+    changes_only: bool,
+    /// List the planned resources of each module beneath it, e.g. 'aws_s3_bucket.logs'.
+    #[arg(long)]
+    resources: bool,
+    /// List the input variables passed to each module call beneath it, with their constant values
+    /// or what they refer to, e.g. 'region = var.region'. Sensitive values are masked.
+    #[arg(long)]
+    vars: bool,
+    /// Show sensitive values, i.e. the constant values passed to sensitive variables and the
+    /// instances of a sensitive 'for_each', rather than '(sensitive)', e.g. to debug locally.
+    #[arg(long)]
+    reveal_sensitive: bool,
+    /// Show the subtree of a module directory called from several places only once, marking the
+    /// other calls with a reference to the first, and list the directories called more than once.
+    #[arg(long)]
+    dedupe: bool,
+    /// Mark each module call with the outputs its caller refers to, and with those nothing refers
+    /// to, which may be removed.
+    #[arg(long)]
+    outputs: bool,
+    /// Make the output the same on every run of the same configuration and plan, e.g. to compare
+    /// it with a golden file: without colors, and with paths relative to the root module.
+    #[arg(long, conflicts_with_all = ["timings_by_module", "hyperlinks"])]
+    stable: bool,
+    /// How to order the children of each module.
+    #[arg(long, value_enum, default_value_t = Sort::Name)]
+    sort: Sort,
+    /// Mark each module with its size: the number of its .tf files, their non-blank lines, and the
+    /// resources, variables and outputs they declare, e.g. '12 files, 1.4k LOC, 37 res, 5 vars,
+    /// 3 outputs'.
+    #[arg(long)]
+    metrics: bool,
+    /// Only show the subtree of the module at the given address, e.g. 'module.platform.module.dns',
+    /// or 'module.regions["eu"]' with --expand-instances.
+    #[arg(long, add = ArgValueCompleter::new(completions::addresses))]
+    root: Option<String>,
+    /// Only show modules nested at most this many levels beneath the root, marking those with
+    /// deeper modules hidden.
+    #[arg(long)]
+    depth: Option<usize>,
+    /// Read the plan from the given `terraform show -json` output, a path, '-' for stdin or an
+    /// HTTP(S) URL, instead of running terraform.
+    #[arg(long, visible_alias = "from-json", global = true)]
+    plan_json: Option<String>,
+    /// Print the terraform commands which would be run, as affected by the TF_WORKSPACE and
+    /// TF_CLI_ARGS environment variables, without running them.
+    #[arg(long, global = true)]
+    dry_run: bool,
+    /// Reuse the plan from the previous run with this option, unless the project's terraform files,
+    /// variable files or variable values have changed since. With '--reuse-plan=PATH', show the
+    /// saved plan at PATH instead of planning, e.g. one written by --plan-out.
+    #[arg(
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        require_equals = true,
+        global = true
+    )]
+    reuse_plan: Option<Option<PathBuf>>,
+    /// Write the plan to the given path, e.g. to `terraform apply` it afterwards, instead of to a
+    /// temporary file.
+    #[arg(long, global = true, conflicts_with_all = ["plan_json", "no_plan", "static_only"])]
+    plan_out: Option<PathBuf>,
+    /// Cache plans in the given directory, keyed by the contents of the project's terraform and
+    /// variable files, its variable values and the binary planning it, and reuse them while all of
+    /// those are unchanged. Implies --reuse-plan, whose plans are otherwise cached in a temporary
+    /// directory.
+    #[arg(long, env = "TREAFORM_CACHE_DIR", global = true)]
+    cache_dir: Option<PathBuf>,
+    /// Plan again rather than reusing a cached plan, caching the new plan for later runs.
+    #[arg(long, global = true)]
+    no_cache: bool,
+    /// Don't query module registries, relying on the responses cached by earlier runs.
+    #[arg(long, global = true)]
+    offline: bool,
+    /// Read the module structure from the project's .tf files and the modules installed by
+    /// `terraform init`, instead of planning. This is much faster and needs no credentials, but
+    /// only constant 'count' numbers are known, and no 'for_each' keys or planned resources.
+    #[arg(long, global = true, conflicts_with_all = ["plan_json", "reuse_plan", "export_plan_json"])]
+    no_plan: bool,
+    /// Like --no-plan, but only follow local module sources, ignoring any modules installed by
+    /// `terraform init`, so that the tree is the same before and after it's run.
+    #[arg(long = "static", global = true, conflicts_with_all = ["plan_json", "reuse_plan", "export_plan_json", "no_plan"])]
+    static_only: bool,
+    /// Save the plan's `terraform show -json` output to the given path, for reuse by other tools.
+    #[arg(long, global = true)]
+    export_plan_json: Option<PathBuf>,
+    /// A bearer token to authenticate the download of --plan-json.
+    #[arg(
+        long,
+        env = "TREAFORM_PLAN_JSON_TOKEN",
+        hide_env_values = true,
+        global = true
+    )]
+    plan_json_token: Option<String>,
+    /// Print the deployed module instances, with their evaluated instance keys, and resources
+    /// instead of planning: those of the current state, with `terraform show -json`, or those
+    /// recorded in the given state file, e.g. a local terraform.tfstate or a backup of one. The
+    /// state is printed as a plain tree, so the options filtering and rendering the plan conflict.
+    #[arg(
+        long,
+        visible_alias = "state-file",
+        value_name = "STATE_FILE",
+        num_args = 0..=1,
+        conflicts_with_all = [
+            "provider", "filter", "exclude", "wide", "git_dirty", "since", "owners", "tests",
+            "label", "group_by", "format", "full_values", "paths", "expand_instances", "changes",
+            "changes_only", "resources", "vars", "reveal_sensitive", "dedupe", "outputs", "stable",
+            "sort", "metrics", "root", "depth", "collapse_chains", "style", "depth_colors",
+            "hyperlinks", "palette", "timings_by_module", "anonymize",
+        ]
+    )]
+    state: Option<Option<PathBuf>>,
+    /// How to render change markers.
+    #[arg(long, value_enum, default_value_t = Glyphs::Ascii, global = true)]
+    glyphs: Glyphs,
+    /// Fold chains of modules with exactly one child onto a single line, e.g. 'platform ▸ base ▸
+    /// network', showing the details of the last module only.
+    #[arg(long)]
+    collapse_chains: bool,
+    /// How to draw the hierarchy of the tree.
+    #[arg(long, value_enum, default_value_t = Style::Tree)]
+    style: Style,
+    /// Color the tree's guide lines and module names by depth, unless --color is 'never', or
+    /// 'auto' and the NO_COLOR environment variable is set.
+    #[arg(long)]
+    depth_colors: bool,
+    /// When to highlight module names in bold, their instances in cyan, and their sources by kind:
+    /// registry sources in blue, git sources in magenta, and directories outside the project in
+    /// yellow.
+    #[arg(long, value_enum, default_value_t = Coloring::Auto, global = true)]
+    color: Coloring,
+    /// Link each module's directory to its `file://` URL, for terminals which open OSC 8
+    /// hyperlinks, e.g. by ctrl-clicking them. Ignored unless writing to a terminal.
+    #[arg(long)]
+    hyperlinks: bool,
+    /// The colors of successive depths with --depth-colors, as names, e.g. 'bright-red', or
+    /// 256-color numbers.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_value = color::DEFAULT_PALETTE,
+        value_parser = color::parse
+    )]
+    palette: Vec<Color>,
+    /// Annotate modules with the time terraform spent refreshing resources and reading data
+    /// sources in their subtrees, from the machine-readable log of the plan.
+    #[arg(long, conflicts_with_all = ["plan_json", "reuse_plan", "no_plan", "static_only"])]
+    timings_by_module: bool,
+    /// Don't show the progress of `terraform plan` on stderr, which is otherwise shown when
+    /// stderr is a terminal.
+    #[arg(long)]
+    no_progress: bool,
+    /// Report the output of a failed `terraform plan` as it is, rather than a concise report of
+    /// the errors from its machine-readable log.
+    #[arg(long)]
+    show_raw_errors: bool,
+    /// Replace module names, paths, instance keys, owners and label values with hashes which are
+    /// consistent within a run, so that the output can be shared without leaking internal naming.
+    #[arg(long)]
+    anonymize: bool,
+    /// Limit the number of concurrent operations: within terraform, when reading module sources,
+    /// and of the projects planned by `fleet`, `scan` or several --path options.
+    #[arg(long, default_value = "10", global = true)]
+    parallelism: Option<u32>,
+
+    /// The program to run terraform's commands with.
+    #[arg(long, value_enum, default_value_t = Runner::Terraform, global = true)]
+    runner: Runner,
+    /// The terraform binary, e.g. 'tofu' or a path to one. Defaults to `tofu` if it's installed, or
+    /// else `terraform`.
+    #[arg(long, env = "TREAFORM_TF_BIN", global = true)]
+    binary: Option<PathBuf>,
+    /// Report which terraform binary is used and its version on stderr.
+    #[arg(long, global = true)]
+    verbose: bool,
+    /// The path to terraform project. Use this option more than once, or a pattern like
+    /// 'envs/*', to show a forest of several root modules.
+    #[arg(long, default_value = ".", global = true)]
+    path: Vec<PathBuf>,
+    /// Plan in the given workspace, rather than the selected one.
+    #[arg(long, global = true, conflicts_with = "all_workspaces")]
+    workspace: Option<String>,
+    /// Create the --workspace if it doesn't exist yet.
+    #[arg(long, global = true, requires = "workspace")]
+    create_workspace: bool,
+    /// Plan every workspace, showing a tree for each, as their count and for_each instances may
+    /// differ.
+    #[arg(long, global = true)]
+    all_workspaces: bool,
+    /// The name of the root module, when shown among several.
+    #[arg(skip)]
+    root_name: Option<String>,
+    /// The environment variables loaded from the --env-file files, in order, followed by those of
+    /// the variables prompted for.
+    #[arg(skip)]
+    environment: Vec<(String, String)>,
+    /// The resource budgets of the project's configuration file, if loaded with its defaults
+    /// rather than when reporting on it.
+    #[arg(skip)]
+    budgets: Option<Vec<Budget>>,
+}
+
+impl Args {
+    /// Parse the command line, exiting on errors as [`Parser::parse`] does, and take the options
+    /// which aren't given, on the command line or by environment variables, from the
+    /// configuration files of the project and of the user.
+    pub fn parse_with_config() -> anyhow::Result<Self> {
+        let matches = Args::command().get_matches();
+        let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|error| error.exit());
+        let config = Config::defaults(args.project())?;
+        args.budgets = Some(config.budgets);
+        let unset = |id: &str| {
+            matches!(
+                matches.value_source(id),
+                None | Some(ValueSource::DefaultValue)
+            )
+        };
+        let value = |name: &str, value: &str| {
+            anyhow::anyhow!("invalid `{name}` in the configuration file: `{value}`")
+        };
+        if let Some(var_files) = config.var_files.filter(|_| unset("var_file")) {
+            args.var_file = var_files;
+        }
+        if let Some(binary) = config.binary.filter(|_| unset("binary")) {
+            args.binary = Some(binary);
+        }
+        if let Some(format) = config.format.filter(|_| unset("format")) {
+            args.format = Format::from_str(&format, true).map_err(|_| value("format", &format))?;
+        }
+        if let Some(color) = config.color.filter(|_| unset("color")) {
+            args.color = Coloring::from_str(&color, true).map_err(|_| value("color", &color))?;
+        }
+        if let Some(paths) = config.paths.filter(|_| unset("paths")) {
+            args.paths = Paths::from_str(&paths, true).map_err(|_| value("paths", &paths))?;
+        }
+        if let Some(filter) = config.filter.filter(|_| unset("filter")) {
+            args.filter = filter;
+        }
+        if let Some(exclude) = config.exclude.filter(|_| unset("exclude")) {
+            args.exclude = exclude;
+        }
+        if let Some(cache_dir) = config.cache_dir.filter(|_| unset("cache_dir")) {
+            args.cache_dir = Some(cache_dir);
+        }
+        if let Some(no_cache) = config.no_cache.filter(|_| unset("no_cache")) {
+            args.no_cache = no_cache;
+        }
+        Ok(args)
+    }
+
+    /// The terraform binary, detected when not given.
+    fn binary(&self) -> &Path {
+        self.binary.as_deref().unwrap_or(Path::new("terraform"))
+    }
+
+    /// A command of the runner in the project at `dir`, in the environment of --env-file and
+    /// --isolate-env.
+    fn terraform(&self, dir: &Path) -> process::Command {
+        let mut command = self.runner.command(self.binary(), dir);
+        if self.isolate_env {
+            environment::isolate(&mut command, &self.allow_env);
+        }
+        command.envs(self.environment.iter().map(|(name, value)| (name, value)));
+        if let Some(workspace) = &self.workspace {
+            command.env("TF_WORKSPACE", workspace);
+        }
+        command
+    }
+
+    /// The name of the runner's program, e.g. `tofu`, for messages.
+    fn program(&self) -> String {
+        self.runner.program(self.binary())
+    }
+
+    /// The project's directory, the only --path once several have been split into one run each.
+    fn project(&self) -> &Path {
+        self.path.first().map_or(Path::new("."), PathBuf::as_path)
+    }
+}
+
+#[derive(Subcommand, Clone, Debug)]
+enum Command {
+    /// Apply the planned changes to every resource within a module's subtree
+    Apply {
+        /// The address of the module, e.g. 'module.platform.module.dns'.
+        #[arg(long, add = ArgValueCompleter::new(completions::addresses))]
+        module: String,
+        /// Skip interactive approval of the scoped plan before applying.
+        #[arg(long)]
+        auto_approve: bool,
+    },
+    /// Print the subtrees affected by a list of changed files
+    Impact {
+        /// A file listing the changed files, one per line. Reads from stdin when omitted or '-'.
+        #[arg(long)]
+        files: Option<PathBuf>,
+    },
+    /// Print the address of every module instance, one per line, reusing the previous plan while
+    /// the project is unchanged
+    Addresses {
+        /// Also print resource addresses, shell-escaped for use by completion functions.
+        #[arg(long)]
+        complete: bool,
+        /// Only print addresses starting with this prefix.
+        prefix: Option<String>,
+    },
+    /// Print the module calls added, removed, renamed or re-sourced between two plans or projects
+    Diff {
+        /// The old plan, as output by `terraform show -json`, or project directory.
+        old: PathBuf,
+        /// The new plan, as output by `terraform show -json`, or project directory.
+        new: PathBuf,
+        /// Show the old module tree on the left and the new one on the right, aligned by matching
+        /// modules, instead of a single module tree of both, with added modules in green, removed
+        /// ones in red, and renamed or re-sourced ones in yellow.
+        #[arg(long, conflicts_with = "list")]
+        side_by_side: bool,
+        /// List the differences, one per line, instead of showing a single module tree of both.
+        #[arg(long)]
+        list: bool,
+    },
+    /// Print the script registering shell completions, e.g. for `source <(treaform completions
+    /// bash)`
+    Completions {
+        /// The shell to complete commands for.
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print the man page
+    Man,
+    /// Open the source of a module: local directories in $VISUAL or $EDITOR, and git or registry
+    /// sources in the browser
+    Open {
+        /// The address of the module, e.g. 'module.platform.module.dns'.
+        #[arg(add = ArgValueCompleter::new(completions::addresses))]
+        module: String,
+    },
+    /// Check that the modules vendored by `terraform init` match their declared sources and refs,
+    /// and haven't been modified locally
+    Verify,
+    /// Serve the tree over HTTP, at 'GET /tree?path=<dir>&format=<format>', reusing plans while the
+    /// project is unchanged
+    Serve {
+        /// The address to listen on.
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        listen: String,
+    },
+    /// Explain the version constraints each module places on every provider, which of them is
+    /// binding, and whether the version locked in .terraform.lock.hcl satisfies them all
+    Constraints,
+    /// Show the tree with the providers each module requires and their version constraints, the
+    /// provider configurations it defines, and those it inherits from its caller
+    Providers,
+    /// Print aggregate statistics: module calls, unique sources, the most reused modules, the
+    /// deepest nesting, the widest fan-out, the number of module instances, and directories which
+    /// look like copies of one another
+    Stats,
+    /// Show the tree with the newer versions available of its registry modules, querying the
+    /// registries at most daily unless --offline
+    Outdated,
+    /// Show the tree, and repaint it whenever a .tf or .tfvars file beneath the project changes,
+    /// marking the lines which changed
+    Watch,
+    /// Print every chain of module calls from the root to the modules whose name or source matches
+    /// a glob pattern, e.g. '* → platform → networking → vpc'
+    Why {
+        /// The glob pattern, e.g. 'vpc' or '*/modules/vpc'.
+        module: String,
+    },
+    /// Compare the module calls with those of a tree exported with '--format json', listing the
+    /// modules added, removed, renamed or re-sourced since. Exits with 12 when there are any
+    Check {
+        /// The exported tree, e.g. 'tree.json'.
+        #[arg(long)]
+        baseline: PathBuf,
+    },
+    /// List every call of a module directory within the root modules beneath a directory, by the
+    /// root module and the call's address
+    Callers {
+        /// The module's directory.
+        path: PathBuf,
+        /// The directory to search for root modules, or Terragrunt units with --runner terragrunt.
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Check module sources: git sources without a '?ref=' pin, registry modules without a version
+    /// constraint, local sources outside the repository, and local sources which are symlinks.
+    /// Exits with 10 when any are found
+    Lint,
+    /// Print a flat bill of materials of every module the project depends on, with its source,
+    /// version or ref, directory, and the addresses of its calls
+    Inventory {
+        /// The format of the inventory.
+        #[arg(long, value_enum, default_value_t = inventory::Format::Json)]
+        format: inventory::Format,
+    },
+    /// Print the dependencies between the resources within a module's subtree, as a DOT graph
+    Graph {
+        /// The address of the module, e.g. 'module.platform.module.dns'. Defaults to every module.
+        #[arg(long = "scope", add = ArgValueCompleter::new(completions::addresses))]
+        module: Option<String>,
+        /// How to obtain the graph.
+        #[arg(long, value_enum, default_value_t = graph::Via::Terraform)]
+        via: graph::Via,
+    },
+    /// Find every root module beneath a directory, or every Terragrunt unit with --runner
+    /// terragrunt, and show their trees together
+    Scan {
+        /// The directory to search, skipping hidden directories.
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Print the Terragrunt units beneath a directory, each above the units its `dependency` and
+    /// `dependencies` blocks depend on
+    Stack {
+        /// The directory to search, skipping hidden directories.
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Report module counts, budget violations and shared remote modules across the projects
+    /// listed in a manifest
+    Fleet {
+        /// A file listing one project per line, as a path relative to it or as a git URL, e.g.
+        /// 'https://example.com/infra.git//network?ref=main', to clone shallowly.
+        manifest: PathBuf,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    /// An indented tree.
+    Tree,
+    /// A JSON document of nested modules, each with its 'name', 'address', 'source' and
+    /// 'children', and when set its 'count', 'for_each' keys, 'version' and 'depends_on'. The root
+    /// has a 'format_version' of its schema.
+    Json,
+    /// A Graphviz digraph of the modules, with their source paths as tooltips.
+    Dot,
+    /// A Mermaid flowchart of the modules, in a fenced code block for markdown.
+    Mermaid,
+    /// The tree in a collapsed '<details>' block for a pull request comment, cut short to fit
+    /// GitHub's comment limit. Use --changes to show each module's changes.
+    Markdown,
+    /// A standalone HTML page of the tree, with collapsible modules, a search box, and each
+    /// module's source, instances, version constraint and resource counts. Best used with
+    /// --output.
+    Html,
+    /// A D2 diagram of the modules, with their source paths as tooltips.
+    D2,
+    /// A PlantUML diagram of the modules, with their source paths as tooltips.
+    Plantuml,
+}
+
+/// How the children of each module are ordered.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Sort {
+    /// By name.
+    Name,
+    /// By source directory, then by name.
+    Source,
+    /// By the number of resources in their subtree, most first, then by name. Resource instances
+    /// are counted when the plan has them, as with --wide.
+    Resources,
+    /// By the number of resources the plan changes in their subtree, most first, then by name.
+    Changes,
+}
+
+/// A CI system whose logs and summaries the output is integrated with.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Ci {
+    /// GitHub Actions, with workflow commands, and the tree written to $GITHUB_STEP_SUMMARY when
+    /// set.
+    Github,
+}
+
+/// How module directories are shown.
+#[derive(ValueEnum, Clone, Copy, Default, Debug)]
+pub enum Paths {
+    /// Relative to the root module, e.g. './modules/vpc'.
+    #[default]
+    Relative,
+    /// As absolute paths.
+    Absolute,
+    /// As the `source` argument of the module call, e.g. '../modules/vpc'.
+    Source,
+    /// Not at all.
+    None,
+}
+
+/// The renderer of the tree in the format selected by `args`.
+fn renderer(args: &Args) -> Box<dyn Render + '_> {
+    match args.format {
+        Format::Tree if matches!(args.style, Style::Indent) => Box::new(style::Indent {
+            highlight: args.color.enabled(),
+        }),
+        Format::Tree if args.depth_colors && args.color.allowed() => Box::new(color::DepthColors {
+            palette: &args.palette,
+        }),
+        Format::Tree => Box::new(render::Guides {
+            highlight: args.color.enabled(),
+        }),
+        Format::Json => Box::new(json::Json {
+            full_values: args.full_values,
+        }),
+        Format::Dot => Box::new(dot::Dot),
+        Format::Mermaid => Box::new(mermaid::Mermaid),
+        Format::Markdown => Box::new(markdown::Markdown),
+        Format::Html => Box::new(html::Html),
+        Format::D2 => Box::new(d2::D2),
+        Format::Plantuml => Box::new(plantuml::PlantUml),
+    }
+}
+
+#[derive(Clone, Debug)]
+enum GroupBy {
+    /// Group modules by their CODEOWNERS owners.
+    Owner,
+    /// Group modules by the value of a metadata label.
+    Label(String),
+}
+
+impl FromStr for GroupBy {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "owner" => GroupBy::Owner,
+            label => GroupBy::Label(label.to_owned()),
+        })
+    }
+}
+
+/// Where the module of a module call is.
+enum Location {
+    /// The path of its directory, before resolving symlinks and `..`.
+    Dir(PathBuf),
+    /// A remote module which isn't installed, described by its source address.
+    Remote(String),
+}
+
+impl Location {
+    /// The location of the module called with `source` from the module here, using the directory
+    /// installed by `terraform init` under key `key` for remote sources, relative to `base`.
+    fn call(
+        &self,
+        source: &str,
+        key: &str,
+        base: &Path,
+        installed: &HashMap<String, PathBuf>,
+    ) -> Location {
+        match (self, source::parse(source)) {
+            (Location::Dir(dir), Source::Local(path)) => Location::Dir(dir.join(path)),
+            (Location::Remote(remote), Source::Local(path)) => {
+                Location::Remote(format!("{path} in {remote}"))
+            }
+            _ => match installed.get(key) {
+                Some(dir) => Location::Dir(base.join(dir)),
+                None => Location::Remote(source.to_owned()),
+            },
+        }
+    }
+}
+
+/// The key of the module call `name` in terraform's module manifest, beneath the call `parent`.
+fn manifest_key(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{parent}.{name}")
+    }
+}
+
+/// The directories by key of the modules installed by `terraform init`.
+type Installed = HashMap<String, PathBuf>;
+
+impl Module<'_> {
+    /// Collect the unresolved directories of the module calls beneath this module at `location`,
+    /// whose key is `key`.
+    fn sources(
+        &self,
+        location: &Location,
+        key: &str,
+        base: &Path,
+        installed: &Installed,
+        sources: &mut Vec<PathBuf>,
+    ) {
+        for (name, value) in self.module_calls.iter().flatten() {
+            let key = manifest_key(key, name);
+            let location = location.call(&value.source, &key, base, installed);
+            value
+                .module
+                .sources(&location, &key, base, installed, sources);
+            if let Location::Dir(dir) = location {
+                sources.push(dir);
+            }
+        }
+    }
+
+    fn trees(
+        &self,
+        base: &Path,
+        location: &Location,
+        parent_address: &str,
+        installed: &Installed,
+        resolved: &HashMap<PathBuf, io::Result<PathBuf>>,
+        interner: &mut Interner,
+    ) -> Result<Vec<Tree<TreeNode>>, TreaformError> {
+        let parent_key = parent_address
+            .strip_prefix("module.")
+            .unwrap_or_default()
+            .replace(".module.", ".");
+        let mut calls: Vec<_> = self.module_calls.iter().flatten().collect();
+        calls.sort_unstable_by_key(|(name, _)| *name);
+        calls
+            .into_iter()
+            .map(|(name, value)| {
+                let address = if parent_address.is_empty() {
+                    format!("module.{name}")
+                } else {
+                    format!("{parent_address}.module.{name}")
+                };
+                let key = manifest_key(&parent_key, name);
+                let call_location = location.call(&value.source, &key, base, installed);
+                let is_local = matches!(source::parse(&value.source), Source::Local(_));
+                let (source, remote) = match &call_location {
+                    Location::Dir(dir) => {
+                        let source = match &resolved[dir] {
+                            Ok(source) => source.clone(),
+                            Err(error) => {
+                                return Err(TreaformError::SourceResolution {
+                                    path: dir.clone(),
+                                    error: io::Error::new(error.kind(), error.to_string()),
+                                })
+                            }
+                        };
+                        let source = if let Ok(source) = source.strip_prefix(base) {
+                            source.to_owned()
+                        } else {
+                            source
+                        };
+                        (source, (!is_local).then(|| interner.str(&value.source)))
+                    }
+                    Location::Remote(remote) => (PathBuf::from(remote), Some(interner.str(remote))),
+                };
+                let providers = interned_providers(&value.module, interner);
+                let leaves = value.module.trees(
+                    base,
+                    &call_location,
+                    &address,
+                    installed,
+                    resolved,
+                    interner,
+                )?;
+                Ok(Tree::new(TreeNode {
+                    name: interner.str(name),
+                    address: interner.str(&address),
+                    count: value
+                        .count_expression
+                        .as_ref()
+                        .and_then(|x| x.constant_value),
+                    for_each: value
+                        .for_each_expression
+                        .as_ref()
+                        .and_then(|x| x.constant_value.clone()),
+                    for_each_sensitive: value.sensitive_for_each(self),
+                    count_references: value
+                        .count_expression
+                        .as_ref()
+                        .filter(|x| x.constant_value.is_none())
+                        .map(|x| plan::outermost(&x.references)),
+                    for_each_references: value
+                        .for_each_expression
+                        .as_ref()
+                        .filter(|x| x.constant_value.is_none())
+                        .map(|x| plan::outermost(&x.references)),
+                    source: interner.path(source),
+                    call_source: Some(interner.str(&value.source)),
+                    remote,
+                    unresolved: matches!(call_location, Location::Remote(_)),
+                    providers,
+                    depends_on: value
+                        .depends_on
+                        .iter()
+                        .map(|address| interner.str(address))
+                        .collect(),
+                    version: value.version_constraint.clone(),
+                    ..Default::default()
+                })
+                .with_leaves(leaves))
+            })
+            .collect()
+    }
+}
+
+/// The number of `module`'s own resources per provider local name.
+fn interned_providers(module: &Module, interner: &mut Interner) -> BTreeMap<Rc<str>, usize> {
+    module
+        .providers()
+        .into_iter()
+        .map(|(provider, count)| (interner.str(provider), count))
+        .collect()
+}
+
+/// A module call in the tree, or the root module.
+#[derive(Clone, Default, Debug)]
+pub struct TreeNode {
+    /// The name of the module call, or `*` for the root module.
+    pub name: Rc<str>,
+    /// The module's address, empty for the root module. Only the instances expanded by
+    /// `--expand-instances`, and the modules within them, have instance keys.
+    pub address: Rc<str>,
+    /// The constant `count` of the module call.
+    pub count: Option<usize>,
+    /// The value of each instance of a constant `for_each`, by instance key.
+    pub for_each: Option<BTreeMap<String, serde_json::Value>>,
+    /// Whether the `for_each` of the module call, or of the call this is an instance of, is
+    /// sensitive.
+    pub for_each_sensitive: bool,
+    /// What a `count` which isn't constant refers to, e.g. `var.replicas`.
+    pub count_references: Option<Vec<String>>,
+    /// What a `for_each` which isn't constant refers to, e.g. `local.environments`.
+    pub for_each_references: Option<Vec<String>>,
+    /// The module's directory relative to the root module, shared by every call of the module.
+    pub source: Rc<PathBuf>,
+    /// The `source` argument of the module call as written, e.g. `../modules/vpc`.
+    pub call_source: Option<Rc<str>>,
+    /// How `source` is shown.
+    pub paths: Paths,
+    /// The `file://` URL of the module's directory, which its location links to, with
+    /// `--hyperlinks`.
+    pub hyperlink: Option<String>,
+    /// The source address of a module call which isn't a local path, e.g.
+    /// `terraform-aws-modules/vpc/aws`.
+    pub remote: Option<Rc<str>>,
+    /// Whether the module is remote and not installed, so that its directory is unknown and
+    /// `source` is only its source address.
+    pub unresolved: bool,
+    /// The number of the module's own resources per provider local name.
+    pub providers: BTreeMap<Rc<str>, usize>,
+    /// The number of the module's own resource instances per provider local name, in the planned
+    /// values of a plan which has them.
+    pub planned: Option<BTreeMap<Rc<str>, usize>>,
+    /// The number of resources in the module's subtree per provider, with `--wide`.
+    pub totals: Option<BTreeMap<Rc<str>, usize>>,
+    /// Annotations shown in brackets after the module, e.g. `dirty`.
+    pub markers: Vec<Rc<str>>,
+    /// The number of test runs exercising the module, with `--tests`.
+    pub tests: Option<usize>,
+    /// The module's owners from CODEOWNERS.
+    pub owners: Vec<Rc<str>>,
+    /// The module's metadata labels.
+    pub labels: BTreeMap<Rc<str>, Rc<str>>,
+    /// The `depends_on` meta-argument of the module call.
+    pub depends_on: Vec<Rc<str>>,
+    /// The `version` constraint of the module call.
+    pub version: Option<String>,
+    /// The `providers` meta-argument of the module call, as pairs of child and parent provider.
+    pub provider_mappings: Vec<(String, String)>,
+    /// The instance key, e.g. `[0]` or `["eu"]`, of a node expanded from a module call.
+    pub instance: Option<String>,
+    /// The labels of the single-child ancestors folded into this node by `--collapse-chains`.
+    pub chain: Vec<String>,
+    /// Whether this is a resource listed by `--resources`, named by its address within the module.
+    pub resource: bool,
+    /// The input variables passed to the module call, by name, with `--vars`.
+    pub inputs: Vec<(String, Input)>,
+    /// Whether this is an input variable listed by `--vars`, named by its assignment.
+    pub input: bool,
+}
+
+impl TreeNode {
+    /// The number of the module's own resources per provider: its planned resource instances, or
+    /// its resource blocks when there are no planned values.
+    fn resources(&self) -> &BTreeMap<Rc<str>, usize> {
+        self.planned.as_ref().unwrap_or(&self.providers)
+    }
+
+    /// Populate `planned` with the resource instances of every instance of each module call in
+    /// the planned values of `show`, by the provider of their resource blocks.
+    fn count_planned(tree: &mut Tree<TreeNode>, show: &Show) {
+        let Some(planned_values) = &show.planned_values else {
+            return;
+        };
+        let mut resources = HashMap::new();
+        planned_values.root_module.resources(&mut resources);
+        let mut counts: HashMap<String, BTreeMap<Rc<str>, usize>> = HashMap::new();
+        for (module, addresses) in resources {
+            let call = strip_instance_keys(module);
+            let Some(config) = show.configuration.root_module.module(&call) else {
+                continue;
+            };
+            for address in addresses {
+                let address = address
+                    .strip_prefix(module)
+                    .and_then(|address| address.strip_prefix('.'))
+                    .unwrap_or(address);
+                let address = split_instance_key(address).map_or(address, |(address, _)| address);
+                let Some(resource) = config
+                    .resources
+                    .iter()
+                    .find(|resource| resource.address == address)
+                else {
+                    continue;
+                };
+                *counts
+                    .entry(call.clone())
+                    .or_default()
+                    .entry(Rc::from(resource.provider()))
+                    .or_default() += 1;
+            }
+        }
+        visit(tree, &mut |node| {
+            node.planned = Some(counts.get(&*node.address).cloned().unwrap_or_default());
+        });
+    }
+
+    /// Populate `totals` with the resource counts of each subtree, returning those of `tree`.
+    fn total(tree: &mut Tree<TreeNode>) -> &BTreeMap<Rc<str>, usize> {
+        let mut totals = tree.root.resources().clone();
+        for leaf in &mut tree.leaves {
+            for (provider, count) in Self::total(leaf) {
+                *totals.entry(provider.clone()).or_default() += count;
+            }
+        }
+        tree.root.totals.insert(totals)
+    }
+
+    /// Order the children of every module of `tree` by `sort`, counting the resources changed by
+    /// `resource_changes` to sort by changes. Ties are ordered by name.
+    fn sort(tree: &mut Tree<TreeNode>, sort: Sort, resource_changes: &[ResourceChange]) {
+        let mut changes: HashMap<String, usize> = HashMap::new();
+        for change in resource_changes {
+            let actions = &change.change.actions;
+            if actions
+                .iter()
+                .any(|action| matches!(action, Action::Create | Action::Update | Action::Delete))
+            {
+                let module =
+                    strip_instance_keys(change.module_address.as_deref().unwrap_or_default());
+                *changes.entry(module).or_default() += 1;
+            }
+        }
+        fn count(tree: &Tree<TreeNode>, own: &impl Fn(&TreeNode) -> usize) -> usize {
+            own(&tree.root)
+                + tree
+                    .leaves
+                    .iter()
+                    .map(|leaf| count(leaf, own))
+                    .sum::<usize>()
+        }
+        fn sort_leaves(tree: &mut Tree<TreeNode>, sort: Sort, changes: &HashMap<String, usize>) {
+            match sort {
+                Sort::Name => tree.leaves.sort_by(|a, b| a.root.name.cmp(&b.root.name)),
+                Sort::Source => tree.leaves.sort_by(|a, b| {
+                    (&a.root.source, &a.root.name).cmp(&(&b.root.source, &b.root.name))
+                }),
+                Sort::Resources => tree.leaves.sort_by_cached_key(|leaf| {
+                    let own = |node: &TreeNode| node.resources().values().sum();
+                    (Reverse(count(leaf, &own)), leaf.root.name.clone())
+                }),
+                Sort::Changes => tree.leaves.sort_by_cached_key(|leaf| {
+                    let own = |node: &TreeNode| changes.get(&*node.address).copied().unwrap_or(0);
+                    (Reverse(count(leaf, &own)), leaf.root.name.clone())
+                }),
+            }
+            for leaf in &mut tree.leaves {
+                sort_leaves(leaf, sort, changes);
+            }
+        }
+        sort_leaves(tree, sort, &changes);
+    }
+
+    /// Replace the children of every module call using `count` or `for_each` with one child per
+    /// instance, each containing the module's own children addressed within that instance.
+    ///
+    /// The instance keys evaluated in the plan, from `planned` by address including the keys of
+    /// enclosing instances, are preferred to the constant `count` and `for_each` expressions,
+    /// which miss instances of dynamic expressions. Instances of the same call may so expand
+    /// their own module calls differently.
+    fn expand_instances(
+        tree: &mut Tree<TreeNode>,
+        planned: &HashMap<String, Vec<String>>,
+        interner: &mut Interner,
+    ) {
+        let instances: Option<Vec<String>> = match (
+            planned.get(&*tree.root.address),
+            &tree.root.count,
+            &tree.root.for_each,
+        ) {
+            (Some(keys), _, _) => Some(keys.clone()),
+            (None, Some(count), _) => Some((0..*count).map(|index| format!("[{index}]")).collect()),
+            (None, _, Some(for_each)) => {
+                Some(for_each.keys().map(|key| format!("[{key:?}]")).collect())
+            }
+            (None, None, None) => None,
+        };
+        if let Some(instances) = instances {
+            let children = mem::take(&mut tree.leaves);
+            tree.leaves = instances
+                .into_iter()
+                .map(|instance| {
+                    let node = TreeNode {
+                        name: tree.root.name.clone(),
+                        address: interner.str(&format!("{}{instance}", tree.root.address)),
+                        source: tree.root.source.clone(),
+                        instance: Some(instance),
+                        for_each_sensitive: tree.root.for_each_sensitive,
+                        ..Default::default()
+                    };
+                    let mut leaves = children.clone();
+                    for leaf in &mut leaves {
+                        Self::readdress(leaf, &node.address, interner);
+                    }
+                    Tree::new(node).with_leaves(leaves)
+                })
+                .collect();
+        }
+        for leaf in &mut tree.leaves {
+            Self::expand_instances(leaf, planned, interner);
+        }
+    }
+
+    /// The subtree of the module at `address`, searching only beneath modules whose addresses
+    /// it extends.
+    fn subtree(tree: Tree<TreeNode>, address: &str) -> Option<Tree<TreeNode>> {
+        if *tree.root.address == *address {
+            return Some(tree);
+        }
+        tree.leaves
+            .into_iter()
+            .filter(|leaf| address.starts_with(&*leaf.root.address))
+            .find_map(|leaf| Self::subtree(leaf, address))
+    }
+
+    /// Remove the modules nested more than `depth` levels beneath the root of `tree`, marking the
+    /// modules at that depth with the number of modules hidden beneath them.
+    fn truncate(tree: &mut Tree<TreeNode>, depth: usize, interner: &mut Interner) {
+        if depth > 0 {
+            for leaf in &mut tree.leaves {
+                Self::truncate(leaf, depth - 1, interner);
+            }
+            return;
+        }
+        let hidden = Self::descendants(tree);
+        if hidden > 0 {
+            tree.leaves.clear();
+            tree.root
+                .markers
+                .push(interner.str(&format!("{hidden} nested hidden")));
+        }
+    }
+
+    /// Replace the subtree of every module call of a directory already shown, in the order of the
+    /// tree, with a marker referring to the first call of it. The location of each directory and
+    /// its number of calls are added to `calls`, indexed by directory in `first`.
+    fn dedupe(
+        tree: &mut Tree<TreeNode>,
+        first: &mut HashMap<Rc<PathBuf>, (usize, Rc<str>)>,
+        calls: &mut Vec<(String, usize)>,
+        interner: &mut Interner,
+    ) {
+        for leaf in &mut tree.leaves {
+            // The instances of a call are shown beneath it
+            if leaf.root.instance.is_none() {
+                if let Some((index, address)) = first.get(&leaf.root.source) {
+                    calls[*index].1 += 1;
+                    leaf.leaves.clear();
+                    leaf.root
+                        .markers
+                        .push(interner.str(&format!("→ see {address}")));
+                    continue;
+                }
+                let location = leaf
+                    .root
+                    .location()
+                    .unwrap_or_else(|| leaf.root.source.display().to_string());
+                first.insert(
+                    leaf.root.source.clone(),
+                    (calls.len(), leaf.root.address.clone()),
+                );
+                calls.push((location, 1));
+            }
+            Self::dedupe(leaf, first, calls, interner);
+        }
+    }
+
+    /// The number of modules beneath the root of `tree`.
+    fn descendants(tree: &Tree<TreeNode>) -> usize {
+        tree.leaves
+            .iter()
+            .map(|leaf| 1 + Self::descendants(leaf))
+            .sum()
+    }
+
+    /// Show `(sensitive)` in place of the keys and values of every sensitive `for_each` of `tree`,
+    /// and of the instance keys of their calls, including within the addresses beneath them.
+    fn mask_sensitive(tree: &mut Tree<TreeNode>, interner: &mut Interner) {
+        let node = &mut tree.root;
+        if node.for_each_sensitive {
+            if node.for_each.take().is_some() {
+                node.for_each_references = Some(vec!["(sensitive)".to_owned()]);
+            }
+            if let Some(instance) = &node.instance {
+                let call = node
+                    .address
+                    .strip_suffix(instance.as_str())
+                    .unwrap_or_default();
+                let masked = format!("{call}[(sensitive)]");
+                let address = mem::replace(&mut node.address, interner.str(&masked));
+                node.instance = Some("[(sensitive)]".to_owned());
+                for leaf in &mut tree.leaves {
+                    visit(leaf, &mut |node| {
+                        if let Some(rest) = node.address.strip_prefix(&*address) {
+                            node.address = interner.str(&format!("{masked}{rest}"));
+                        }
+                    });
+                }
+            }
+        }
+        for leaf in &mut tree.leaves {
+            Self::mask_sensitive(leaf, interner);
+        }
+    }
+
+    /// Address the modules of `tree` within the module instance at `parent`.
+    fn readdress(tree: &mut Tree<TreeNode>, parent: &str, interner: &mut Interner) {
+        tree.root.address = interner.str(&format!("{parent}.module.{}", tree.root.name));
+        for leaf in &mut tree.leaves {
+            Self::readdress(leaf, &tree.root.address, interner);
+        }
+    }
+
+    /// Show the directory of every module of `tree`, relative to `base`, as `paths` selects.
+    fn show_paths(tree: &mut Tree<TreeNode>, paths: Paths, base: &Path, interner: &mut Interner) {
+        visit(tree, &mut |node| {
+            node.paths = paths;
+            if node.unresolved {
+                return;
+            }
+            match paths {
+                Paths::Relative if node.source.is_absolute() => {
+                    node.source = interner.path(relative(&node.source, base));
+                }
+                Paths::Absolute if node.source.is_relative() => {
+                    node.source = interner.path(base.join(&*node.source));
+                }
+                _ => {}
+            }
+        });
+    }
+
+    /// Where the module is, e.g. `./network` or `terraform-aws-modules/vpc/aws at
+    /// ./.terraform/modules/vpc`, unless paths aren't shown.
+    fn location(&self) -> Option<String> {
+        let path: PathBuf = self.source.iter().collect();
+        let path = if path.is_relative() && !path.starts_with("..") {
+            format!("./{}", path.display())
+        } else {
+            path.display().to_string()
+        };
+        Some(match (&self.remote, self.paths) {
+            (_, Paths::None) => return None,
+            (Some(remote), _) if self.unresolved => format!("{remote}, not installed"),
+            (_, Paths::Source) => match &self.call_source {
+                Some(call_source) => call_source.to_string(),
+                None => path,
+            },
+            (Some(remote), _) => format!("{remote} at {path}"),
+            (None, _) => path,
+        })
+    }
+
+    /// Write the instance key, or the name with the `count` or `for_each` instances, of the module.
+    fn write_label(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        self.write_highlighted_label(f, false)
+    }
+
+    /// Write the label as [`TreeNode::write_label`] does, with the name in bold and the instances
+    /// in cyan when `highlight` is set.
+    fn write_highlighted_label(&self, f: &mut impl fmt::Write, highlight: bool) -> fmt::Result {
+        if let Some(instance) = &self.instance {
+            return color::styled(f, highlight, color::BOLD, instance);
+        }
+        color::styled(f, highlight, color::BOLD, &self.name)?;
+        let mut instances = String::new();
+        match self.count {
+            Some(0) => instances.push_str("[disabled]"),
+            Some(count) => write!(instances, "×{count}")?,
+            None => {}
+        }
+        if let Some(for_each) = &self.for_each {
+            instances.push('{');
+            for (index, each) in for_each.keys().enumerate() {
+                write!(instances, "{each}")?;
+                if index + 1 < for_each.len() {
+                    instances.push(' ');
+                }
+            }
+            instances.push('}');
+        }
+        // An expression which isn't constant but refers to nothing, e.g. `timestamp()`, shows `?`
+        let references = |references: &[String]| match references {
+            [] => "?".to_owned(),
+            references => references.join(", "),
+        };
+        if let Some(count_references) = &self.count_references {
+            write!(instances, "[count: {}]", references(count_references))?;
+        }
+        if let Some(for_each_references) = &self.for_each_references {
+            write!(
+                instances,
+                "{{for_each: {}}}",
+                references(for_each_references)
+            )?;
+        }
+        if !instances.is_empty() {
+            color::styled(f, highlight, color::CYAN, instances)?;
+        }
+        Ok(())
+    }
+
+    /// The color of the module's location: blue for registry sources, magenta for git sources,
+    /// yellow for directories outside the root module's, and none for the others.
+    fn location_color(&self, location: &str) -> &'static str {
+        match self.remote.as_deref().map(source::parse) {
+            Some(Source::Registry { .. }) => color::BLUE,
+            Some(Source::Git { .. }) => color::MAGENTA,
+            Some(_) => "",
+            None if location.starts_with("..") => color::YELLOW,
+            None => "",
+        }
+    }
+
+    /// The module's line in the tree, highlighted with ANSI colors when `highlight` is set.
+    pub fn line(&self, highlight: bool) -> String {
+        let mut line = String::new();
+        let _ = self.write(&mut line, highlight);
+        line
+    }
+
+    /// Mark each module with changes with the number of its resources which are added, changed and
+    /// destroyed, with replacements counted as both added and destroyed. Returns the addresses of
+    /// the modules with changes.
+    fn mark_changes(
+        tree: &mut Tree<TreeNode>,
+        resource_changes: &[ResourceChange],
+        glyphs: Glyphs,
+        interner: &mut Interner,
+    ) -> HashSet<String> {
+        let mut counts: HashMap<String, [usize; 3]> = HashMap::new();
+        for change in resource_changes {
+            let actions = &change.change.actions;
+            let module = strip_instance_keys(change.module_address.as_deref().unwrap_or_default());
+            let [add, update, destroy] = counts.entry(module).or_default();
+            *add += actions.contains(&Action::Create) as usize;
+            *update += actions.contains(&Action::Update) as usize;
+            *destroy += actions.contains(&Action::Delete) as usize;
+        }
+        counts.retain(|_, counts| counts.iter().any(|count| *count > 0));
+        visit(tree, &mut |node| {
+            let Some(&[add, update, destroy]) = counts.get(&*node.address) else {
+                return;
+            };
+            let marker = match glyphs {
+                Glyphs::None => format!("{add} to add, {update} to change, {destroy} to destroy"),
+                glyphs => {
+                    let count = |kind, count| format!("{}{count}", glyphs.prefix(kind).trim_end());
+                    format!(
+                        "{} {} {}",
+                        count(glyph::Kind::Create, add),
+                        count(glyph::Kind::Update, update),
+                        count(glyph::Kind::Delete, destroy)
+                    )
+                }
+            };
+            node.markers.push(interner.str(&marker));
+        });
+        counts.into_keys().collect()
+    }
+
+    /// Mark the modules containing resource instances which are tainted or deposed, which usually
+    /// means a previous apply failed.
+    fn mark_broken(
+        tree: &mut Tree<TreeNode>,
+        resource_changes: &[ResourceChange],
+        interner: &mut Interner,
+    ) {
+        let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+        for change in resource_changes {
+            let tainted = change.action_reason.as_deref() == Some("replace_because_tainted");
+            if !tainted && change.deposed.is_none() {
+                continue;
+            }
+            let module = strip_instance_keys(change.module_address.as_deref().unwrap_or_default());
+            let (tainted_count, deposed_count) = counts.entry(module).or_default();
+            if change.deposed.is_some() {
+                *deposed_count += 1;
+            } else {
+                *tainted_count += 1;
+            }
+        }
+        visit(tree, &mut |node| {
+            if let Some((tainted, deposed)) = counts.get(&*node.address) {
+                if *tainted > 0 {
+                    node.markers
+                        .push(interner.str(&format!("{tainted} tainted")));
+                }
+                if *deposed > 0 {
+                    node.markers
+                        .push(interner.str(&format!("{deposed} deposed")));
+                }
+            }
+        });
+    }
+
+    /// Populate `inputs` with the input variables passed to each module call beneath `tree`, whose
+    /// module is `module`.
+    fn inputs(tree: &mut Tree<TreeNode>, module: &Module, reveal: bool) {
+        for leaf in &mut tree.leaves {
+            let Some(call) = module
+                .module_calls
+                .as_ref()
+                .and_then(|calls| calls.get(&*leaf.root.name))
+            else {
+                continue;
+            };
+            leaf.root.inputs = call.inputs(reveal);
+            Self::inputs(leaf, &call.module, reveal);
+        }
+    }
+
+    /// Add the input variables of each module call as its first children.
+    fn add_inputs(tree: &mut Tree<TreeNode>) {
+        for leaf in &mut tree.leaves {
+            Self::add_inputs(leaf);
+        }
+        // The inputs of expanded modules are listed beneath the call
+        if tree.root.instance.is_some() {
+            return;
+        }
+        let inputs: Vec<Tree<TreeNode>> = tree
+            .root
+            .inputs
+            .iter()
+            .map(|(name, input)| {
+                Tree::new(TreeNode {
+                    name: Rc::from(format!("{name} = {input}")),
+                    input: true,
+                    ..Default::default()
+                })
+            })
+            .collect();
+        tree.leaves.splice(0..0, inputs);
+    }
+
+    /// Add the planned resources of each module as its first children, from the resource
+    /// addresses by module instance of `planned`.
+    ///
+    /// Modules which aren't expanded into instances list the resources of all their instances.
+    fn add_resources(tree: &mut Tree<TreeNode>, planned: &HashMap<&str, Vec<&str>>) {
+        let mut by_instance: HashMap<&str, Vec<&str>> = HashMap::new();
+        let mut by_module: HashMap<String, Vec<&str>> = HashMap::new();
+        for (module, addresses) in planned {
+            for address in addresses {
+                let relative = address
+                    .strip_prefix(*module)
+                    .and_then(|rest| rest.strip_prefix('.'))
+                    .unwrap_or(address);
+                by_instance.entry(module).or_default().push(relative);
+                let resources = by_module.entry(strip_instance_keys(module)).or_default();
+                if !resources.contains(&relative) {
+                    resources.push(relative);
+                }
+            }
+        }
+        fn add(
+            tree: &mut Tree<TreeNode>,
+            by_instance: &HashMap<&str, Vec<&str>>,
+            by_module: &HashMap<String, Vec<&str>>,
+        ) {
+            for leaf in &mut tree.leaves {
+                add(leaf, by_instance, by_module);
+            }
+            // The resources of expanded modules are listed beneath their instances
+            if tree.leaves.iter().any(|leaf| leaf.root.instance.is_some()) {
+                return;
+            }
+            // Modules within expanded instances are addressed with their instance keys
+            let resources = by_instance
+                .get(&*tree.root.address)
+                .or_else(|| by_module.get(&*tree.root.address));
+            let mut resources: Vec<Tree<TreeNode>> = resources
+                .into_iter()
+                .flatten()
+                .map(|address| {
+                    Tree::new(TreeNode {
+                        name: Rc::from(*address),
+                        resource: true,
+                        ..Default::default()
+                    })
+                })
+                .collect();
+            resources.sort_by(|a, b| a.root.name.cmp(&b.root.name));
+            tree.leaves.splice(0..0, resources);
+        }
+        add(tree, &by_instance, &by_module);
+    }
+
+    /// Fold every chain of modules beneath the root with exactly one child into its last module.
+    fn collapse_chains(tree: &mut Tree<TreeNode>) {
+        for leaf in &mut tree.leaves {
+            while leaf.leaves.len() == 1 {
+                let mut child = leaf.leaves.pop().expect("one child");
+                let mut chain = mem::take(&mut leaf.root.chain);
+                let mut label = String::new();
+                let _ = leaf.root.write_label(&mut label);
+                chain.push(label);
+                child.root.chain = chain;
+                *leaf = child;
+            }
+            Self::collapse_chains(leaf);
+        }
+    }
+}
+
+/// Build the tree of module calls in the configuration of `show`, a plan of the project at
+/// `terraform_dir`, resolving their sources on at most `parallelism` threads.
+///
+/// Remote modules are found where `terraform init` installed them, if it has been run.
+///
+/// Each node is labelled with its module's own resources, but none of the other annotations of
+/// the command line, e.g. markers or owners.
+pub fn module_tree(
+    show: &Show,
+    terraform_dir: &Path,
+    parallelism: usize,
+) -> Result<Tree<TreeNode>, TreaformError> {
+    let installed = installed_dirs(terraform_dir)?;
+    build_tree(
+        show,
+        terraform_dir,
+        parallelism,
+        &installed,
+        &mut Interner::default(),
+    )
+}
+
+/// The directories of the modules installed by `terraform init` in the project at
+/// `terraform_dir`.
+fn installed_dirs(terraform_dir: &Path) -> Result<Installed, TreaformError> {
+    Ok(installed::load(terraform_dir)?
+        .into_iter()
+        .map(|module| (module.key, module.dir))
+        .collect())
+}
+
+/// Build the tree as [`module_tree`] does, with the installed modules `installed`, sharing the
+/// strings and paths of its nodes through `interner`.
+fn build_tree(
+    show: &Show,
+    terraform_dir: &Path,
+    parallelism: usize,
+    installed: &Installed,
+    interner: &mut Interner,
+) -> Result<Tree<TreeNode>, TreaformError> {
+    let root_module = &show.configuration.root_module;
+    let root = Location::Dir(terraform_dir.to_owned());
+    let root_node = TreeNode {
+        name: interner.str("*"),
+        source: Rc::default(),
+        providers: interned_providers(root_module, interner),
+        ..Default::default()
+    };
+    let mut sources = Vec::new();
+    root_module.sources(&root, "", terraform_dir, installed, &mut sources);
+    sources.sort_unstable();
+    sources.dedup();
+    let canonical = pool::map(&sources, parallelism, |source| source.canonicalize());
+    let resolved = sources.into_iter().zip(canonical).collect();
+    let leaves = root_module.trees(terraform_dir, &root, "", installed, &resolved, interner)?;
+    Ok(Tree::new(root_node).with_leaves(leaves))
+}
+
+/// The path of `path` relative to `base`, stepping out of `base` with `..` as needed.
+fn relative(path: &Path, base: &Path) -> PathBuf {
+    let mut path_components = path.components().peekable();
+    let mut base_components = base.components().peekable();
+    while path_components.peek().is_some() && path_components.peek() == base_components.peek() {
+        path_components.next();
+        base_components.next();
+    }
+    base_components
+        .map(|_| Path::new(".."))
+        .chain(path_components.map(|component| Path::new(component.as_os_str())))
+        .collect()
+}
+
+/// Order the instance keys `a` and `b`, e.g. `[2]` before `[10]`, and `["a"]` before `["b"]`.
+fn compare_instance_keys(a: &str, b: &str) -> Ordering {
+    let index = |key: &str| {
+        key.strip_prefix('[')?
+            .strip_suffix(']')?
+            .parse::<u64>()
+            .ok()
+    };
+    match (index(a), index(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+/// Call `f` on every node of `tree`.
+fn visit(tree: &mut Tree<TreeNode>, f: &mut impl FnMut(&mut TreeNode)) {
+    f(&mut tree.root);
+    for leaf in &mut tree.leaves {
+        visit(leaf, f);
+    }
+}
+
+impl fmt::Display for TreeNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, false)
+    }
+}
+
+impl TreeNode {
+    /// Write the module's line in the tree, highlighted with ANSI colors when `highlight` is set.
+    fn write(&self, f: &mut impl fmt::Write, highlight: bool) -> fmt::Result {
+        if self.resource || self.input {
+            return f.write_str(&self.name);
+        }
+        for label in &self.chain {
+            color::styled(f, highlight, color::BOLD, label)?;
+            f.write_str(" ▸ ")?;
+        }
+        self.write_highlighted_label(f, highlight)?;
+        if self.instance.is_some() {
+            return Ok(());
+        }
+        if let Some(location) = self.location() {
+            f.write_str(" (")?;
+            let code = self.location_color(&location);
+            let mut styled = String::new();
+            color::styled(&mut styled, highlight && !code.is_empty(), code, location)?;
+            match &self.hyperlink {
+                Some(url) => hyperlink::write(f, url, &styled)?,
+                None => f.write_str(&styled)?,
+            }
+            f.write_char(')')?;
+        }
+        if let Some(version) = &self.version {
+            write!(f, " [version {version}]")?;
+        }
+        if !self.depends_on.is_empty() {
+            write!(f, " [depends on {}]", self.depends_on.join(", "))?;
+        }
+        if !self.provider_mappings.is_empty() {
+            f.write_str(" [providers: ")?;
+            for (index, (child, parent)) in self.provider_mappings.iter().enumerate() {
+                let separator = if index == 0 { "" } else { ", " };
+                write!(f, "{separator}{child} → {parent}")?;
+            }
+            f.write_char(']')?;
+        }
+        if let Some(totals) = self.totals.as_ref().filter(|totals| !totals.is_empty()) {
+            f.write_str(" |")?;
+            for (index, (provider, count)) in totals.iter().enumerate() {
+                let separator = if index == 0 { " " } else { ", " };
+                write!(f, "{separator}{provider}: {count}")?;
+            }
+        }
+        for marker in &self.markers {
+            write!(f, " [{marker}]")?;
+        }
+        if !self.owners.is_empty() {
+            write!(f, " [owned by {}]", self.owners.join(", "))?;
+        }
+        if !self.labels.is_empty() {
+            f.write_str(" [")?;
+            for (index, (key, value)) in self.labels.iter().enumerate() {
+                let separator = if index == 0 { "" } else { ", " };
+                write!(f, "{separator}{key}={value}")?;
+            }
+            f.write_char(']')?;
+        }
+        Ok(())
+    }
+}
+
+/// The `terraform plan` command of the project at `project_dir` writing the plan to `out`.
+fn plan_command(args: &Args, project_dir: &Path, out: &Path, targets: &[&str]) -> process::Command {
+    let mut command = args.terraform(project_dir);
+    command.arg("plan");
+    for var_file in &args.var_file {
+        command.arg("-var-file");
+        command.arg(var_file);
+    }
+    for var in &args.var {
+        command.arg("-var");
+        command.arg(var);
+    }
+    for replace in &args.replace {
+        command.arg(format!("-replace={replace}"));
+    }
+    for target in args
+        .target
+        .iter()
+        .map(String::as_str)
+        .chain(targets.iter().copied())
+    {
+        command.arg(format!("-target={target}"));
+    }
+    if !args.refresh {
+        command.arg("-refresh=false");
+    }
+    if !args.lock {
+        command.arg("-lock=false");
+    }
+    if let Some(lock_timeout) = &args.lock_timeout {
+        command.arg(format!("-lock-timeout={lock_timeout}"));
+    }
+    if args.destroy {
+        command.arg("-destroy");
+    }
+    command.args(&args.tf_arg);
+    if args.compact_warnings {
+        command.arg("-compact-warnings");
+    }
+    if let Some(parallelism) = args.parallelism {
+        command.arg(format!("-parallelism={parallelism}"));
+    }
+    if json_log(args) {
+        command.arg("-json");
+    }
+    // Missing variables are prompted for by `plan`, as terraform's prompts would be captured
+    command
+        .args(["-input=false", "-no-color", "-out"])
+        .arg(out.as_os_str());
+    command
+}
+
+/// Whether `terraform plan` writes its machine-readable log, from which its diagnostics, timings
+/// and progress are read.
+fn json_log(args: &Args) -> bool {
+    !args.show_raw_errors || args.timings_by_module || progress::enabled(args)
+}
+
+/// Run `terraform plan`, reporting its warnings, writing the plan to `out` and returning the output.
+///
+/// When run interactively, unless `--input=false`, values are prompted for any required variables
+/// which are unset, and added to `args.environment` before retrying.
+fn plan(
+    args: &mut Args,
+    terraform_dir: &Path,
+    project_dir: &Path,
+    out: &Path,
+    targets: &[&str],
+) -> anyhow::Result<String> {
+    let name = format!("{} plan", args.program());
+    let stdout = loop {
+        let command = plan_command(args, project_dir, out, targets);
+        let result = if progress::enabled(args) {
+            progress::output(command, &name)
+        } else {
+            output(command, &name)
+        };
+        let raw = match result {
+            Ok(stdout) => break stdout,
+            Err(TreaformError::CommandFailed { diagnostics, .. }) => diagnostics,
+            Err(error) => return Err(error.into()),
+        };
+        // Failures before planning starts, e.g. crashes, are written to stderr unstructured
+        let parsed = if json_log(args) {
+            diagnostics::parse(&raw)
+        } else {
+            Vec::new()
+        };
+        let text = if parsed.is_empty() {
+            raw.clone()
+        } else {
+            diagnostics::plain(&parsed)
+        };
+        let missing = prompt::missing_variables(&text);
+        if missing.is_empty() || !args.input || !prompt::interactive() {
+            let diagnostics = if args.show_raw_errors || parsed.is_empty() {
+                raw
+            } else {
+                diagnostics::report(&parsed, args.color.enabled_for_stderr())
+            };
+            return Err(TreaformError::PlanFailed {
+                program: args.program(),
+                diagnostics,
+            }
+            .into());
+        }
+        args.environment
+            .extend(prompt::variables(terraform_dir, &missing)?);
+    };
+
+    // Report warnings
+    let text = if json_log(args) {
+        diagnostics::plain(&diagnostics::parse(&stdout))
+    } else {
+        stdout.clone()
+    };
+    let mut escalated = Vec::new();
+    for warning in Warning::parse(&text) {
+        let is_match = |pattern: &String| glob::matches(pattern, &warning.summary);
+        if args.warning_as_error.iter().any(is_match) {
+            if !escalated.contains(&warning.summary) {
+                escalated.push(warning.summary);
+            }
+        } else if args.suppress_warning.iter().any(is_match) {
+            continue;
+        } else if args.compact_warnings {
+            // Terraform leaves the detail in its machine-readable log regardless
+            eprintln!("Warning: {}", warning.summary);
+        } else {
+            eprintln!("{warning}\n");
+        }
+    }
+    if !escalated.is_empty() {
+        return Err(TreaformError::WarningsEscalated {
+            summaries: escalated,
+        }
+        .into());
+    }
+    Ok(stdout)
+}
+
+/// Run `terraform show -json` on a saved plan of the project at `project_dir`.
+fn show_command(args: &Args, project_dir: &Path, plan: &Path) -> process::Command {
+    let mut command = args.terraform(project_dir);
+    command.args(["show", "-json"]);
+    command.arg(plan);
+    command
+}
+
+fn show_json(args: &Args, project_dir: &Path, plan: &Path) -> Result<String, TreaformError> {
+    let command = show_command(args, project_dir, plan);
+    let name = format!("{} show", args.program());
+    output(command, &name).map_err(|error| match error {
+        TreaformError::CommandFailed { diagnostics, .. } => TreaformError::ShowFailed {
+            program: args.program(),
+            diagnostics,
+        },
+        error => error,
+    })
+}
+
+/// Run a command to completion, returning its stdout or failing with its error output.
+///
+/// `name` names the command in errors, e.g. `git status`.
+fn output(mut command: process::Command, name: &str) -> Result<String, TreaformError> {
+    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let Output {
+        status,
+        stdout,
+        stderr,
+    } = command.output().map_err(|error| TreaformError::Spawn {
+        command: name.to_owned(),
+        error,
+    })?;
+    let stdout = String::from_utf8_lossy(&stdout).into_owned();
+    if !status.success() {
+        let diagnostics = if !stderr.is_empty() {
+            String::from_utf8_lossy(&stderr).into_owned()
+        } else {
+            stdout
+        };
+        return Err(TreaformError::CommandFailed {
+            command: name.to_owned(),
+            diagnostics,
+        });
+    }
+    Ok(stdout)
+}
+
+/// Whether `module_address` lies within the subtree of the module at `selector`.
+///
+/// Instance keys in `module_address` are ignored unless `selector` specifies them.
+fn within(module_address: &str, selector: &str) -> bool {
+    let is_prefix = |address: &str| {
+        address
+            .strip_prefix(selector)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.') || rest.starts_with('['))
+    };
+    is_prefix(module_address) || is_prefix(&strip_instance_keys(module_address))
+}
+
+/// Remove the `[...]` instance keys from an address.
+fn strip_instance_keys(address: &str) -> String {
+    let mut stripped = String::with_capacity(address.len());
+    let (mut in_key, mut in_string) = (false, false);
+    for c in address.chars() {
+        match c {
+            '"' if in_key => in_string = !in_string,
+            '[' if !in_string => in_key = true,
+            ']' if !in_string => in_key = false,
+            _ if !in_key => stripped.push(c),
+            _ => {}
+        }
+    }
+    stripped
+}
+
+/// Split the trailing `[...]` instance key off an address, e.g. `module.net["eu"]`.
+fn split_instance_key(address: &str) -> Option<(&str, &str)> {
+    let (mut start, mut in_string) = (None, false);
+    for (index, c) in address.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '[' if !in_string => start = Some(index),
+            _ => {}
+        }
+    }
+    let start = start.filter(|_| address.ends_with(']'))?;
+    Some(address.split_at(start))
+}
+
+/// Plan the resources within `module`, confirm the changes and apply them.
+fn apply(
+    args: &mut Args,
+    terraform_dir: &Path,
+    project_dir: &Path,
+    temp_plan: &Path,
+    show: &Show,
+    module: &str,
+    auto_approve: bool,
+) -> anyhow::Result<()> {
+    let targets: Vec<&str> = show
+        .resource_changes
+        .iter()
+        .filter(|change| change.mode == "managed")
+        .filter(|change| {
+            change
+                .module_address
+                .as_deref()
+                .is_some_and(|address| within(address, module))
+        })
+        .map(|change| change.address.as_str())
+        .collect();
+    if targets.is_empty() {
+        anyhow::bail!("no resources found within `{module}`")
+    }
+
+    // Plan only the targeted resources
+    let scoped_plan = temp_plan.with_extension("scoped.plan");
+    plan(args, terraform_dir, project_dir, &scoped_plan, &targets)?;
+    let stdout = show_json(args, project_dir, &scoped_plan)?;
+    let scoped = Show::parse(&stdout)?;
+    let (mut add, mut change, mut destroy) = (0, 0, 0);
+    for resource_change in &scoped.resource_changes {
+        let actions = &resource_change.change.actions;
+        let kind = match actions.as_slice() {
+            [Action::Create] => glyph::Kind::Create,
+            [Action::Update] => glyph::Kind::Update,
+            [Action::Delete] => glyph::Kind::Delete,
+            [Action::Read] => glyph::Kind::Read,
+            [Action::Delete, Action::Create] => glyph::Kind::DeleteCreate,
+            [Action::Create, Action::Delete] => glyph::Kind::CreateDelete,
+            _ => continue,
+        };
+        add += actions.contains(&Action::Create) as usize;
+        change += actions.contains(&Action::Update) as usize;
+        destroy += actions.contains(&Action::Delete) as usize;
+        println!("  {}{}", args.glyphs.prefix(kind), resource_change.address);
+    }
+    println!("Plan: {add} to add, {change} to change, {destroy} to destroy.");
+    if add + change + destroy == 0 {
+        return Ok(());
+    }
+
+    // Confirm and apply
+    if !auto_approve {
+        print!("\nDo you want to perform these actions? Only 'yes' will be accepted to approve: ");
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        if answer.trim() != "yes" {
+            anyhow::bail!("apply cancelled")
+        }
+    }
+    let program = args.program();
+    let status = args
+        .terraform(project_dir)
+        .arg("apply")
+        .arg(&scoped_plan)
+        .status()
+        .with_context(|| format!("failed to spawn `{program} apply`"))?;
+    if !status.success() {
+        anyhow::bail!("`{program} apply` failed")
+    }
+    Ok(())
+}
+
+/// Run the command described by `args`, printing its output.
+pub fn run(mut args: Args) -> anyhow::Result<()> {
+    if args.binary.is_none() {
+        args.binary = Some(runner::detect());
+    }
+    for env_file in &args.env_file {
+        let variables = environment::load(env_file)?;
+        if args.verbose {
+            let names: Vec<&str> = variables.iter().map(|(name, _)| name.as_str()).collect();
+            eprintln!("Loaded {} from {}", names.join(", "), env_file.display());
+        }
+        args.environment.extend(variables);
+    }
+    if args.isolate_env && args.verbose {
+        let mut kept: Vec<String> = env::vars_os()
+            .map(|(name, _)| name.to_string_lossy().into_owned())
+            .filter(|name| environment::allowed(name, &args.allow_env))
+            .collect();
+        kept.sort();
+        eprintln!("Keeping only {} from the environment", kept.join(", "));
+    }
+    if args.stable {
+        if matches!(args.paths, Paths::Absolute) {
+            anyhow::bail!("--stable shows paths relative to the root module, not --paths absolute")
+        }
+        args.color = Coloring::Never;
+        args.depth_colors = false;
+        args.no_progress = true;
+    }
+    // Completion functions list the addresses on every TAB, which can't wait for a plan each time
+    if matches!(args.command, Some(Command::Addresses { .. })) && args.reuse_plan.is_none() {
+        args.reuse_plan = Some(None);
+    }
+    match &args.command {
+        Some(Command::Completions { shell }) => {
+            completions::register(*shell, Args::command().get_name(), &mut io::stdout())?;
+            return Ok(());
+        }
+        Some(Command::Man) => {
+            let command = Args::command();
+            let source = format!("{} {}", command.get_name(), env!("CARGO_PKG_VERSION"));
+            clap_mangen::Man::new(command)
+                .source(source)
+                .render(&mut io::stdout())?;
+            return Ok(());
+        }
+        _ => {}
+    }
+    if let Some(Command::Diff {
+        old,
+        new,
+        side_by_side,
+        list,
+    }) = &args.command
+    {
+        let view = match (side_by_side, list) {
+            (true, _) => diff::View::SideBySide,
+            (_, true) => diff::View::List,
+            _ => diff::View::Tree,
+        };
+        return diff::run(old, new, args.glyphs, view, args.color.enabled());
+    }
+    if let Some(Some(state)) = &args.state {
+        return state::run(state);
+    }
+    if let Some(Command::Serve { listen }) = &args.command {
+        return serve::run(&args, listen);
+    }
+    if let Some(Command::Watch) = &args.command {
+        return watch::run(&args);
+    }
+    if let Some(Command::Fleet { manifest }) = &args.command {
+        return fleet::run(&args, manifest);
+    }
+    if let Some(Command::Stack { dir }) = &args.command {
+        print!("{}", terragrunt::stack(dir)?);
+        return Ok(());
+    }
+    if let Some(Command::Callers { path, dir }) = &args.command {
+        return callers::run(&args, path, dir);
+    }
+    if let Some(Command::Scan { dir }) = &args.command {
+        let parallelism = args.parallelism.unwrap_or(1) as usize;
+        let roots = match args.runner {
+            Runner::Terraform => discover::roots(dir, parallelism)?,
+            Runner::Terragrunt => terragrunt::units(dir)?,
+        };
+        if roots.is_empty() {
+            anyhow::bail!("no root modules found in {}", dir.display())
+        }
+        let args = Args {
+            command: None,
+            ..args.clone()
+        };
+        return forest(&args, &roots_of(&args, &roots), "root module");
+    }
+    let roots: Vec<PathBuf> = args
+        .path
+        .iter()
+        .flat_map(|path| match path.to_string_lossy().contains(['*', '?']) {
+            true => glob::directories(path),
+            false => vec![path.clone()],
+        })
+        .collect();
+    match roots.as_slice() {
+        [] => anyhow::bail!("no directories match --path"),
+        [_] => {
+            // The budgets were loaded for the pattern rather than the directory it matched
+            if roots != args.path {
+                args.budgets = None;
+            }
+            args.path = roots;
+        }
+        _ if args.command.is_some() || args.state.is_some() => {
+            anyhow::bail!("only the tree can be shown for more than one --path")
+        }
+        _ if args.plan_out.is_some() || matches!(args.reuse_plan, Some(Some(_))) => {
+            anyhow::bail!("--plan-out and --reuse-plan=PATH can only be used with a single --path")
+        }
+        _ if args.all_workspaces => {
+            anyhow::bail!("--all-workspaces can only be used with a single --path")
+        }
+        _ => return forest(&args, &roots_of(&args, &roots), "root module"),
+    }
+    if args.all_workspaces {
+        if args.command.is_some() || args.state.is_some() {
+            anyhow::bail!("only the tree can be shown for --all-workspaces")
+        }
+        let project_dir = env::current_dir()
+            .context("could not detect current directory")?
+            .join(args.project())
+            .canonicalize()
+            .context("failed to resolve path")?;
+        if args.dry_run {
+            let list = workspace::list_command(&args, &project_dir);
+            println!("{}", environment::describe(&list));
+            return Ok(());
+        }
+        let runs: Vec<Args> = workspace::list(&args, &project_dir)?
+            .into_iter()
+            .map(|workspace| Args {
+                root_name: Some(workspace.clone()),
+                workspace: Some(workspace),
+                all_workspaces: false,
+                ..args.clone()
+            })
+            .collect();
+        return forest(&args, &runs, "workspace");
+    }
+    let mut out = String::new();
+    let result = report(&mut args, &mut out);
+    if let (Some(Ci::Github), None) = (args.ci, &args.output) {
+        let title = match &args.root_name {
+            Some(root_name) => root_name.clone(),
+            None => args.project().display().to_string(),
+        };
+        out = github::group(&title, &out);
+    }
+    write_output(&args, &out)?;
+    result
+}
+
+/// The runs reporting on each of the projects at `roots`.
+fn roots_of(args: &Args, roots: &[PathBuf]) -> Vec<Args> {
+    roots
+        .iter()
+        .map(|root| Args {
+            path: vec![root.clone()],
+            root_name: Some(root.display().to_string()),
+            budgets: None,
+            ..args.clone()
+        })
+        .collect()
+}
+
+/// Print the trees of `runs`, e.g. of several projects or workspaces, beneath a single header
+/// counting them as `noun`s, or a JSON array of their trees. The runs are planned concurrently,
+/// on at most `--parallelism` threads, each writing to its own buffer. Every run is reported on,
+/// even after one fails, and the first failure is returned.
+fn forest(args: &Args, runs: &[Args], noun: &str) -> anyhow::Result<()> {
+    let parallelism = args.parallelism.unwrap_or(1) as usize;
+    let results = pool::map(runs, parallelism, |run| {
+        let mut run_args = Args {
+            // Concurrent plans would draw over each other's progress
+            no_progress: true,
+            ..run.clone()
+        };
+        let name = run.root_name.clone().unwrap_or_default();
+        let mut out = String::new();
+        let result =
+            report(&mut run_args, &mut out).with_context(|| format!("failed to report on {name}"));
+        (result, out)
+    });
+    let mut result = Ok(());
+    let mut reports = Vec::new();
+    let mut names = Vec::new();
+    for (run, (root_result, out)) in runs.iter().zip(results) {
+        match root_result {
+            Err(error) if result.is_ok() => result = Err(error),
+            Err(error) => eprintln!("Error: {error:?}"),
+            Ok(()) => {}
+        }
+        if !out.is_empty() {
+            reports.push(out);
+            names.push(run.root_name.clone().unwrap_or_default());
+        }
+    }
+    let mut out = String::new();
+    let plural = if runs.len() == 1 { "" } else { "s" };
+    let grouped = matches!(args.ci, Some(Ci::Github)) && args.output.is_none();
+    match args.format {
+        // Each tree is folded into a group of its own, rather than drawn beneath the header
+        Format::Tree if grouped => {
+            let _ = writeln!(out, "{} {noun}{plural}", runs.len());
+            for (name, report) in names.iter().zip(&reports) {
+                out.push_str(&github::group(name, report));
+            }
+        }
+        Format::Tree => {
+            let _ = writeln!(out, "{} {noun}{plural}", runs.len());
+            for (index, report) in reports.iter().enumerate() {
+                let (first, rest) = match (args.style, index + 1 == reports.len()) {
+                    (Style::Indent, _) => ("  ", "  "),
+                    (Style::Tree, false) => ("├── ", "│   "),
+                    (Style::Tree, true) => ("└── ", "    "),
+                };
+                for (line_index, line) in report.lines().enumerate() {
+                    let prefix = if line_index == 0 { first } else { rest };
+                    let _ = writeln!(out, "{prefix}{line}");
+                }
+            }
+        }
+        Format::Json => {
+            let _ = writeln!(out, "[{}]", reports.join(","));
+        }
+        Format::Dot | Format::Mermaid | Format::Markdown | Format::Plantuml => {
+            out = reports.concat()
+        }
+        // Containers keep the ids of each project's diagram apart
+        Format::D2 => {
+            for (index, report) in reports.iter().enumerate() {
+                let _ = writeln!(out, "p{index}: {{\n{report}}}");
+            }
+        }
+        Format::Html => {
+            let mains: Vec<String> = reports
+                .iter()
+                .flat_map(|report| html::mains(report))
+                .collect();
+            out = html::page(&format!("{} {noun}{plural}", runs.len()), &mains);
+        }
+    }
+    if grouped && !matches!(args.format, Format::Tree) {
+        out = github::group(&format!("{} {noun}{plural}", runs.len()), &out);
+    }
+    write_output(args, &out)?;
+    result
+}
+
+/// Print `out`, or write it to the file of --output.
+fn write_output(args: &Args, out: &str) -> anyhow::Result<()> {
+    match &args.output {
+        Some(path) => {
+            fs::write(path, out).with_context(|| format!("failed to write {}", path.display()))
+        }
+        None => {
+            print!("{out}");
+            Ok(())
+        }
+    }
+}
+
+/// Plan the project and write its tree to `out`, or run the other commands which need a plan.
+fn report(args: &mut Args, out: &mut String) -> anyhow::Result<()> {
+    // Calculate dirs
+    let mut terraform_dir = env::current_dir().context("could not detect current directory")?;
+    terraform_dir.push(args.project());
+    let project_dir = terraform_dir
+        .canonicalize()
+        .context("failed to resolve path")?;
+    // Terragrunt runs terraform on a copy of the unit's configuration within its cache
+    let terraform_dir = match args.runner {
+        Runner::Terragrunt if !args.dry_run => terragrunt::working_dir(args, &project_dir)?,
+        _ => project_dir.clone(),
+    };
+    if let Some(Command::Open { module }) = &args.command {
+        return open::run(&terraform_dir, module);
+    }
+    if let Some(Command::Verify) = &args.command {
+        return verify::run(&terraform_dir);
+    }
+    if let Some(Command::Constraints) = &args.command {
+        return constraints::run(&terraform_dir);
+    }
+    if let Some(Command::Graph { module, via }) = &args.command {
+        let command = args.terraform(&project_dir);
+        out.push_str(&graph::run(command, module.as_deref(), *via)?);
+        return Ok(());
+    }
+    if let Some(None) = &args.state {
+        let mut command = args.terraform(&project_dir);
+        command.args(["show", "-json"]);
+        let name = format!("{} show", args.program());
+        out.push_str(&state::deployed(&output(command, &name)?)?);
+        return Ok(());
+    }
+
+    // Create `.plan` path
+    // Workspaces of the same project have different plans
+    let mut hasher = DefaultHasher::new();
+    project_dir.hash(&mut hasher);
+    env::var_os("TF_WORKSPACE").hash(&mut hasher);
+    args.workspace.hash(&mut hasher);
+    let plan_name = hasher.finish();
+    let mut temp_plan = env::temp_dir();
+    temp_plan.push(plan_name.to_string());
+    temp_plan.set_extension(".plan");
+    // Terraform resolves paths within the project, so keep the plans given relative to here
+    let current_dir = env::current_dir().context("could not detect current directory")?;
+    if let Some(plan_out) = &args.plan_out {
+        temp_plan = current_dir.join(plan_out);
+    }
+
+    // Run `terraform plan` and `terraform show` commands, unless the plan is provided or skipped
+    let mut plan_timings = None;
+    let stdout;
+    let show = if args.no_plan || args.static_only {
+        if matches!(args.command, Some(Command::Apply { .. })) {
+            anyhow::bail!("`apply` needs a plan and cannot use --no-plan or --static")
+        }
+        configuration::show(&terraform_dir, args.static_only)?
+    } else {
+        stdout = match (&args.plan_json, &args.reuse_plan) {
+            (Some(location), _) => {
+                if matches!(args.command, Some(Command::Apply { .. })) {
+                    anyhow::bail!("`apply` plans the module itself and cannot use --plan-json")
+                }
+                if let Some(Command::Impact { files }) = &args.command {
+                    if location == "-"
+                        && files.as_deref().is_none_or(|files| files == Path::new("-"))
+                    {
+                        anyhow::bail!(
+                            "`impact` cannot read both the plan and its --files from stdin"
+                        )
+                    }
+                }
+                fetch::plan_json(location, args.plan_json_token.as_deref())?
+            }
+            (None, Some(Some(saved))) => {
+                let saved = current_dir.join(saved);
+                if args.dry_run {
+                    let show = show_command(args, &project_dir, &saved);
+                    println!("{}", environment::describe(&show));
+                    return Ok(());
+                }
+                show_json(args, &project_dir, &saved)?
+            }
+            (None, _) if args.dry_run => {
+                if let Some(workspace) = args.workspace.as_ref().filter(|_| args.create_workspace) {
+                    let create = workspace::create_command(args, &project_dir, workspace);
+                    println!("{}", environment::describe(&create));
+                }
+                let plan = plan_command(args, &project_dir, &temp_plan, &[]);
+                let show = show_command(args, &project_dir, &temp_plan);
+                println!("{}", environment::describe(&plan));
+                println!("{}", environment::describe(&show));
+                return Ok(());
+            }
+            (None, _) => {
+                if let Some(workspace) = args.workspace.as_ref().filter(|_| args.create_workspace) {
+                    workspace::create(args, &project_dir, workspace)?;
+                }
+                let cache = match (&args.cache_dir, &args.reuse_plan) {
+                    (Some(cache_dir), _) => Some(cache_dir.clone()),
+                    (None, Some(_)) => Some(env::temp_dir().join("treaform")),
+                    (None, None) => None,
+                };
+                let cache = match cache {
+                    Some(cache_dir) => {
+                        let fingerprint = cache::fingerprint(args, &project_dir, &terraform_dir)?;
+                        Some(cache::entry(&cache_dir, fingerprint))
+                    }
+                    None => None,
+                };
+                let cached = cache
+                    .as_deref()
+                    // A cached plan would leave no plan file at --plan-out
+                    .filter(|_| !args.no_cache && args.plan_out.is_none())
+                    .and_then(cache::load);
+                match cached {
+                    Some(json) => json,
+                    None => {
+                        if args.verbose {
+                            eprintln!("Planning with {}", runner::describe(args.binary()));
+                        }
+                        let log = plan(args, &terraform_dir, &project_dir, &temp_plan, &[])?;
+                        if args.timings_by_module {
+                            plan_timings = Some(timings::parse(&log));
+                        }
+                        let json = show_json(args, &project_dir, &temp_plan)?;
+                        if let Some(cache) = &cache {
+                            cache::store(cache, &json)?;
+                        }
+                        json
+                    }
+                }
+            }
+        };
+        if let Some(path) = &args.export_plan_json {
+            fs::write(path, &stdout)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+        let show = Show::parse(&stdout)?;
+        if args.verbose {
+            let unknown = || "unknown".to_owned();
+            eprintln!(
+                "Plan of format version {}, made by terraform {}",
+                show.format_version.clone().unwrap_or_else(unknown),
+                show.terraform_version.clone().unwrap_or_else(unknown)
+            );
+        }
+        show
+    };
+
+    if let Some(Command::Addresses { complete, prefix }) = &args.command {
+        let mut addresses = Vec::new();
+        if let Some(planned_values) = &show.planned_values {
+            planned_values
+                .root_module
+                .addresses(*complete, &mut addresses);
+        }
+        addresses.sort_unstable();
+        let prefix = prefix.as_deref().unwrap_or_default();
+        for address in addresses
+            .into_iter()
+            .filter(|address| address.starts_with(prefix))
+        {
+            if *complete {
+                let escaped: String = address
+                    .chars()
+                    .flat_map(|c| match c {
+                        '"' | '[' | ']' => vec!['\\', c],
+                        c => vec![c],
+                    })
+                    .collect();
+                println!("{escaped}");
+            } else {
+                println!("{address}");
+            }
+        }
+        return Ok(());
+    }
+    if let Some(Command::Apply {
+        module,
+        auto_approve,
+    }) = &args.command
+    {
+        let (module, auto_approve) = (module.clone(), *auto_approve);
+        return apply(
+            args,
+            &terraform_dir,
+            &project_dir,
+            &temp_plan,
+            &show,
+            &module,
+            auto_approve,
+        );
+    }
+
+    // Create tree
+    let parallelism = args.parallelism.unwrap_or(1) as usize;
+    let installed = if args.static_only {
+        Installed::new()
+    } else {
+        installed_dirs(&terraform_dir)?
+    };
+    let mut interner = Interner::default();
+    let mut tree = build_tree(
+        &show,
+        &terraform_dir,
+        parallelism,
+        &installed,
+        &mut interner,
+    )?;
+    let mut dirs = Vec::new();
+    visit(&mut tree, &mut |node| {
+        if !node.unresolved {
+            dirs.push(terraform_dir.join(&*node.source))
+        }
+    });
+    dirs.sort_unstable();
+    dirs.dedup();
+    let mut scanner = Scanner::default();
+    scanner.preload(&dirs, parallelism);
+    scan::annotate(&mut scanner, &mut tree, &terraform_dir, &mut interner)?;
+    if let Some(Command::Stats) = &args.command {
+        let mut planned = HashMap::new();
+        if let Some(planned_values) = &show.planned_values {
+            planned_values.root_module.instances(&mut planned);
+        }
+        TreeNode::show_paths(&mut tree, args.paths, &terraform_dir, &mut interner);
+        let mut expanded = tree.clone();
+        TreeNode::expand_instances(&mut expanded, &planned, &mut interner);
+        out.push_str(&stats::report(&tree, &expanded, &terraform_dir)?);
+        return Ok(());
+    }
+    if let Some(Command::Lint) = &args.command {
+        // Outside of a repository, the project is the boundary
+        let repository = git::toplevel(&terraform_dir).unwrap_or_else(|_| terraform_dir.clone());
+        let repository = fs::canonicalize(&repository).unwrap_or(repository);
+        let violations = lint::check(&tree, &terraform_dir, &repository, &mut scanner);
+        if let Some(Ci::Github) = args.ci {
+            for violation in &violations {
+                let location = violation.file.as_deref().zip(violation.line);
+                let message = format!("{}: {}", violation.address, violation.message);
+                eprintln!(
+                    "{}",
+                    github::annotation(github::Level::Error, location, violation.rule, &message)
+                );
+            }
+        }
+        out.push_str(&lint::report(
+            &violations,
+            matches!(args.format, Format::Json),
+        )?);
+        if !violations.is_empty() {
+            return Err(TreaformError::LintFailed {
+                violations: violations.len(),
+            }
+            .into());
+        }
+        return Ok(());
+    }
+    if let Some(Command::Check { baseline }) = &args.command {
+        TreeNode::show_paths(&mut tree, args.paths, &terraform_dir, &mut interner);
+        return check::run(&tree, baseline, args.glyphs, out);
+    }
+    if let Some(Command::Callers { path, .. }) = &args.command {
+        out.push_str(&callers::addresses(&mut tree, &terraform_dir, path));
+        return Ok(());
+    }
+    if let Some(Command::Why { module }) = &args.command {
+        TreeNode::show_paths(&mut tree, args.paths, &terraform_dir, &mut interner);
+        out.push_str(&why::report(&tree, module)?);
+        return Ok(());
+    }
+    if let Some(Command::Inventory { format }) = &args.command {
+        let name = match &args.root_name {
+            Some(name) => name.clone(),
+            None => project_dir
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+        };
+        TreeNode::show_paths(&mut tree, args.paths, &terraform_dir, &mut interner);
+        out.push_str(&inventory::report(
+            &mut tree,
+            &terraform_dir,
+            &name,
+            *format,
+        )?);
+        return Ok(());
+    }
+    if let Some(Command::Outdated) = &args.command {
+        let cache_dir = args
+            .cache_dir
+            .clone()
+            .unwrap_or_else(|| env::temp_dir().join("treaform"));
+        registry::annotate(
+            &mut tree,
+            &terraform_dir,
+            &cache_dir,
+            args.offline,
+            parallelism,
+            &mut interner,
+        )?;
+    }
+    if let Some(Command::Providers) = &args.command {
+        let provider_config = &show.configuration.provider_config;
+        providers::annotate(
+            &mut scanner,
+            &mut tree,
+            &terraform_dir,
+            provider_config,
+            &mut interner,
+        )?;
+    }
+    TreeNode::mark_broken(&mut tree, &show.resource_changes, &mut interner);
+    if args.vars {
+        TreeNode::inputs(
+            &mut tree,
+            &show.configuration.root_module,
+            args.reveal_sensitive,
+        );
+    }
+    if args.outputs {
+        outputs::annotate(&mut scanner, &mut tree, &terraform_dir, &mut interner)?;
+    }
+    if args.metrics {
+        metrics::annotate(&mut scanner, &mut tree, &terraform_dir, &mut interner)?;
+    }
+    if args.changes || args.changes_only {
+        let changed = TreeNode::mark_changes(
+            &mut tree,
+            &show.resource_changes,
+            args.glyphs,
+            &mut interner,
+        );
+        if args.changes_only {
+            filter::prune(&mut tree, &|node: &TreeNode| {
+                changed.contains(&*node.address)
+            });
+        }
+    }
+    TreeNode::count_planned(&mut tree, &show);
+    if args.wide {
+        TreeNode::total(&mut tree);
+    }
+    let budgets = match args.budgets.take() {
+        Some(budgets) => budgets,
+        None => Config::load(&project_dir)?.budgets,
+    };
+    let mut violations = Vec::new();
+    budget::check(&mut tree, &budgets, &mut violations, &mut interner);
+    if !args.provider.is_empty() {
+        filter::prune(&mut tree, &|node: &TreeNode| {
+            node.resources()
+                .keys()
+                .any(|provider| args.provider.iter().any(|arg| glob::matches(arg, provider)))
+        });
+    }
+    if !args.filter.is_empty() {
+        filter::prune(&mut tree, &|node: &TreeNode| {
+            args.filter
+                .iter()
+                .any(|pattern| filter::matches(node, pattern))
+        });
+    }
+    if !args.exclude.is_empty() {
+        filter::exclude(&mut tree, &|node: &TreeNode| {
+            args.exclude
+                .iter()
+                .any(|pattern| filter::matches(node, pattern))
+        });
+    }
+    if args.git_dirty {
+        let dirs = impact::parent_dirs(&git::dirty_files(&terraform_dir)?);
+        impact::mark(&mut tree, &terraform_dir, &dirs, "dirty", &mut interner);
+    }
+    if let Some(base) = &args.since {
+        let dirs = impact::parent_dirs(&git::changed_since(&terraform_dir, base)?);
+        impact::mark(
+            &mut tree,
+            &terraform_dir,
+            &dirs,
+            &format!("changed since {base}"),
+            &mut interner,
+        );
+    }
+    if let Some(plan_timings) = &plan_timings {
+        timings::annotate(&mut tree, plan_timings, &mut interner);
+    }
+    if args.tests {
+        let runs = coverage::runs(&dirs)?;
+        coverage::annotate(&mut tree, &terraform_dir, &runs, &mut interner);
+    }
+    // Modules called more than once share their labels and owners
+    let mut result = Ok(());
+    let mut labels: HashMap<Rc<PathBuf>, BTreeMap<Rc<str>, Rc<str>>> = HashMap::new();
+    visit(&mut tree, &mut |node| {
+        if !labels.contains_key(&node.source) {
+            match metadata::load(&terraform_dir.join(&*node.source)) {
+                Ok(loaded) => {
+                    let loaded = loaded
+                        .iter()
+                        .map(|(key, value)| (interner.str(key), interner.str(value)))
+                        .collect();
+                    labels.insert(node.source.clone(), loaded);
+                }
+                Err(error) => {
+                    result = Err(error);
+                    return;
+                }
+            }
+        }
+        node.labels = labels[&node.source].clone();
+    });
+    result?;
+    if !args.label.is_empty() {
+        let selectors = args
+            .label
+            .iter()
+            .map(|label| {
+                label
+                    .split_once('=')
+                    .with_context(|| format!("expected `key=pattern`, found `{label}`"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        filter::prune(&mut tree, &|node: &TreeNode| {
+            selectors.iter().all(|(key, pattern)| {
+                node.labels
+                    .get(*key)
+                    .is_some_and(|value| glob::matches(pattern, value))
+            })
+        });
+    }
+    if args.owners || matches!(args.group_by, Some(GroupBy::Owner)) {
+        let root = git::toplevel(&terraform_dir).unwrap_or_else(|_| terraform_dir.clone());
+        let root = fs::canonicalize(&root).unwrap_or(root);
+        let Some(codeowners) = CodeOwners::discover(&root)? else {
+            anyhow::bail!("no CODEOWNERS file found in {}", root.display())
+        };
+        let mut owners: HashMap<Rc<PathBuf>, Vec<Rc<str>>> = HashMap::new();
+        visit(&mut tree, &mut |node| {
+            node.owners = owners
+                .entry(node.source.clone())
+                .or_insert_with(|| {
+                    codeowners
+                        .module_owners(&terraform_dir.join(&*node.source))
+                        .iter()
+                        .map(|owner| interner.str(owner))
+                        .collect()
+                })
+                .clone();
+        });
+    }
+    if let Some(Command::Impact { files }) = &args.command {
+        let dirs = impact::parent_dirs(&impact::changed_files(files.as_deref())?);
+        if !impact::mark(&mut tree, &terraform_dir, &dirs, "changed", &mut interner) {
+            return Ok(());
+        }
+        impact::prune(&mut tree);
+    }
+    TreeNode::sort(&mut tree, args.sort, &show.resource_changes);
+    if args.expand_instances {
+        let mut planned = HashMap::new();
+        if let Some(planned_values) = &show.planned_values {
+            planned_values.root_module.instances(&mut planned);
+        }
+        // The plan may list instances out of key order, e.g. `[10]` before `[2]`
+        for keys in planned.values_mut() {
+            keys.sort_by(|a, b| compare_instance_keys(a, b));
+        }
+        TreeNode::expand_instances(&mut tree, &planned, &mut interner);
+    }
+    if let Some(root) = &args.root {
+        let Some(subtree) = TreeNode::subtree(tree, root) else {
+            anyhow::bail!("no module at address `{root}`")
+        };
+        tree = subtree;
+    }
+    if let Some(depth) = args.depth {
+        TreeNode::truncate(&mut tree, depth, &mut interner);
+    }
+    if !args.reveal_sensitive {
+        TreeNode::mask_sensitive(&mut tree, &mut interner);
+    }
+    if args.hyperlinks && io::stdout().is_terminal() {
+        visit(&mut tree, &mut |node| {
+            if !node.unresolved {
+                let dir = terraform_dir.join(&*node.source);
+                node.hyperlink = Some(hyperlink::file_url(&dir));
+            }
+        });
+    }
+    TreeNode::show_paths(&mut tree, args.paths, &terraform_dir, &mut interner);
+    if let Some(root_name) = args.root_name.as_ref().filter(|_| !args.anonymize) {
+        tree.root.name = interner.str(root_name);
+    }
+    if args.anonymize {
+        let anonymizer = Anonymizer::new();
+        anonymizer.tree(&mut tree, &mut interner);
+        for violation in &mut violations {
+            violation.address = anonymizer.text(&violation.address);
+        }
+    }
+    let mut untested = Vec::new();
+    coverage::untested(&tree, &mut untested);
+    let mut shared = Vec::new();
+    if args.dedupe {
+        TreeNode::dedupe(&mut tree, &mut HashMap::new(), &mut shared, &mut interner);
+        shared.retain(|(_, calls)| *calls > 1);
+    }
+    if args.collapse_chains && matches!(args.format, Format::Tree | Format::Markdown) {
+        TreeNode::collapse_chains(&mut tree);
+    }
+    match &args.group_by {
+        Some(GroupBy::Owner) => out.push_str(&group::report(&tree, &|node| node.owners.clone())),
+        Some(GroupBy::Label(key)) => out.push_str(&group::report(&tree, &|node| {
+            node.labels.get(key.as_str()).cloned().into_iter().collect()
+        })),
+        None => {
+            if args.resources {
+                let mut planned = HashMap::new();
+                if let Some(planned_values) = &show.planned_values {
+                    planned_values.root_module.resources(&mut planned);
+                }
+                TreeNode::add_resources(&mut tree, &planned);
+            }
+            // Other formats show inputs as data rather than as children
+            if args.vars && matches!(args.format, Format::Tree | Format::Markdown) {
+                TreeNode::add_inputs(&mut tree);
+            }
+            renderer(args).render(&tree, out)?;
+            if let Some(Ci::Github) = args.ci {
+                let mut summary = String::new();
+                markdown::Markdown.render(&tree, &mut summary)?;
+                github::summarize(&summary)?;
+            }
+        }
+    }
+    if !shared.is_empty() {
+        eprintln!("Shared modules:");
+        for (location, calls) in &shared {
+            eprintln!("  {location}: {calls} call sites");
+        }
+    }
+    if !untested.is_empty() {
+        eprintln!("Untested modules:");
+        for module in &untested {
+            eprintln!("  {module}");
+        }
+    }
+    let mut failed = 0;
+    for violation in &violations {
+        let message = format!(
+            "{} has {} resources, exceeding its budget of {}",
+            violation.address, violation.resources, violation.max_resources
+        );
+        match (args.ci, violation.level) {
+            (Some(Ci::Github), level) => {
+                let level = match level {
+                    Level::Error => github::Level::Error,
+                    Level::Warn => github::Level::Warning,
+                };
+                eprintln!(
+                    "{}",
+                    github::annotation(level, None, "resource budget", &message)
+                );
+            }
+            (None, Level::Error) => eprintln!("Error: {message}"),
+            (None, Level::Warn) => eprintln!("Warning: {message}"),
+        }
+        failed += (violation.level == Level::Error) as usize;
+    }
+    if failed > 0 {
+        return Err(TreaformError::BudgetExceeded { modules: failed }.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instance_keys_are_stripped() {
+        assert_eq!(
+            strip_instance_keys(r#"module.app[0].module.db["eu"]"#),
+            "module.app.module.db"
+        );
+        assert_eq!(
+            strip_instance_keys(r#"module.net["a]b"].module.vpc"#),
+            "module.net.module.vpc"
+        );
+        assert_eq!(strip_instance_keys("module.app"), "module.app");
+    }
+
+    #[test]
+    fn trailing_instance_key_is_split() {
+        assert_eq!(
+            split_instance_key(r#"module.app[0].module.net["eu[1]"]"#),
+            Some(("module.app[0].module.net", r#"["eu[1]"]"#))
+        );
+        assert_eq!(split_instance_key("module.app[0].module.db"), None);
+    }
+
+    #[test]
+    fn instance_keys_are_ordered() {
+        let mut indices = vec!["[10]", "[2]", "[1]"];
+        indices.sort_by(|a, b| compare_instance_keys(a, b));
+        assert_eq!(indices, ["[1]", "[2]", "[10]"]);
+        let mut keys = vec![r#"["eu"]"#, r#"["10"]"#, r#"["2"]"#];
+        keys.sort_by(|a, b| compare_instance_keys(a, b));
+        assert_eq!(keys, [r#"["10"]"#, r#"["2"]"#, r#"["eu"]"#]);
+    }
+}
@@ -0,0 +1,126 @@
+//! Linting module sources for calls which aren't reproducible or reach outside the repository.
+
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use termtree::Tree;
+
+use crate::{
+    relative,
+    scan::Scanner,
+    source::{self, Source},
+    TreeNode,
+};
+
+/// A module call breaking a rule.
+#[derive(Serialize)]
+pub struct Violation {
+    /// The name of the rule, e.g. `unpinned-git-source`.
+    pub rule: &'static str,
+    pub address: String,
+    pub message: String,
+    /// The file of the module call, relative to the root of the repository.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    /// The line the module call starts on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+}
+
+/// Check the module calls beneath `tree`, whose modules are at `base` joined with their sources,
+/// against the rules, with `repository` the canonical root of the repository holding the project.
+/// The calls are located in the modules read by `scanner`.
+pub fn check(
+    tree: &Tree<TreeNode>,
+    base: &Path,
+    repository: &Path,
+    scanner: &mut Scanner,
+) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    check_calls(tree, base, repository, scanner, &mut violations);
+    violations
+}
+
+fn check_calls(
+    tree: &Tree<TreeNode>,
+    base: &Path,
+    repository: &Path,
+    scanner: &mut Scanner,
+    violations: &mut Vec<Violation>,
+) {
+    let dir = base.join(&*tree.root.source);
+    for leaf in &tree.leaves {
+        let node = &leaf.root;
+        // Remote modules which aren't installed can't be read
+        let location = match scanner.module_call(&dir, &node.name) {
+            Ok(Some(block)) => {
+                let file = fs::canonicalize(&block.file).unwrap_or_else(|_| block.file.clone());
+                Some((relative(&file, repository), block.line))
+            }
+            _ => None,
+        };
+        let mut violation = |rule, message: String| {
+            violations.push(Violation {
+                rule,
+                address: node.address.to_string(),
+                message,
+                file: location.as_ref().map(|(file, _)| file.clone()),
+                line: location.as_ref().map(|(_, line)| *line),
+            })
+        };
+        let call_source = node.call_source.as_deref().unwrap_or_default();
+        match source::parse(call_source) {
+            Source::Git {
+                reference: None, ..
+            } => violation(
+                "unpinned-git-source",
+                format!("git source `{call_source}` has no `?ref=` pin"),
+            ),
+            Source::Registry { .. } if node.version.is_none() => violation(
+                "unversioned-registry-module",
+                format!("registry module `{call_source}` has no version constraint"),
+            ),
+            Source::Local(path) if !node.unresolved => {
+                // Sources are joined lexically, so `..` only goes away once canonicalized
+                let resolved = base.join(&*node.source);
+                let resolved = fs::canonicalize(&resolved).unwrap_or(resolved);
+                if !resolved.starts_with(repository) {
+                    violation(
+                        "source-outside-repository",
+                        format!("local source `{call_source}` is outside the repository"),
+                    );
+                }
+                if fs::symlink_metadata(dir.join(path)).is_ok_and(|metadata| metadata.is_symlink())
+                {
+                    violation(
+                        "symlinked-source",
+                        format!("local source `{call_source}` is a symlink"),
+                    );
+                }
+            }
+            _ => {}
+        }
+        check_calls(leaf, base, repository, scanner, violations);
+    }
+}
+
+/// `violations` as lines of `address: message [rule]`, or as a JSON array of objects with
+/// `rule`, `address`, `message`, `file` and `line` fields when `json` is set.
+pub fn report(violations: &[Violation], json: bool) -> anyhow::Result<String> {
+    if json {
+        return Ok(serde_json::to_string_pretty(violations)? + "\n");
+    }
+    let mut out = String::new();
+    for violation in violations {
+        let _ = writeln!(
+            out,
+            "{}: {} [{}]",
+            violation.address, violation.message, violation.rule
+        );
+    }
+    Ok(out)
+}
@@ -0,0 +1,63 @@
+//! Rendering the tree as markdown, to post as a pull request comment.
+
+use std::fmt::Write as _;
+
+use termtree::Tree;
+
+use crate::{render::Render, TreeNode};
+
+/// The maximum length of a GitHub comment, in characters.
+const MAX_LENGTH: usize = 65_536;
+
+/// A collapsed `<details>` block summarizing the number of modules, with the tree in a fenced code
+/// block. Modules show their markers, such as the change counts of `--changes`.
+///
+/// Lines beyond the length of a GitHub comment are left out, noting how many.
+pub struct Markdown;
+
+impl Render for Markdown {
+    fn render(&self, tree: &Tree<TreeNode>, out: &mut String) -> anyhow::Result<()> {
+        let modules = modules(tree) - 1;
+        let plural = if modules == 1 { "" } else { "s" };
+        // The root module is `*` unless named with --root-name
+        let title = match &*tree.root.name {
+            "*" => "Module tree".to_owned(),
+            name => escape(name),
+        };
+        let head = format!(
+            "<details>\n<summary>{title} ({modules} module{plural})</summary>\n\n```text\n"
+        );
+        let tail = "```\n\n</details>\n";
+        let text = tree.to_string();
+        // Room for the note on truncated lines
+        let budget = MAX_LENGTH - head.chars().count() - tail.chars().count() - 64;
+        let mut length = 0;
+        let mut body = String::new();
+        let lines: Vec<&str> = text.lines().collect();
+        for (index, line) in lines.iter().enumerate() {
+            length += line.chars().count() + 1;
+            if length > budget {
+                writeln!(body, "… {} more lines not shown", lines.len() - index)?;
+                break;
+            }
+            writeln!(body, "{line}")?;
+        }
+        out.push_str(&head);
+        out.push_str(&body);
+        out.push_str(tail);
+        Ok(())
+    }
+}
+
+/// The number of modules in `tree`, leaving out the resources and inputs listed beneath them.
+fn modules(tree: &Tree<TreeNode>) -> usize {
+    let node = &tree.root;
+    !(node.resource || node.input) as usize + tree.leaves.iter().map(modules).sum::<usize>()
+}
+
+/// Replace the characters which would start HTML tags or entities in the summary.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,35 @@
+//! Rendering the tree as a Mermaid flowchart, to paste into GitHub or GitLab markdown.
+
+use std::fmt::Write as _;
+
+use termtree::Tree;
+
+use crate::{
+    render::{self, Render},
+    TreeNode,
+};
+
+/// A `flowchart TD` in a fenced `mermaid` code block, with an edge from each module to the
+/// modules it calls, labelled with their names and `count` or `for_each` instances.
+pub struct Mermaid;
+
+impl Render for Mermaid {
+    fn render(&self, tree: &Tree<TreeNode>, out: &mut String) -> anyhow::Result<()> {
+        out.push_str("```mermaid\nflowchart TD\n");
+        render::graph(
+            out,
+            tree,
+            &|out, id, label, _| writeln!(out, "    n{id}[\"{}\"]", escape(label)),
+            &|out, id, child| writeln!(out, "    n{id} --> n{child}"),
+        )?;
+        out.push_str("```\n");
+        Ok(())
+    }
+}
+
+/// Replace the characters which end or break a quoted label with their entity codes.
+fn escape(text: &str) -> String {
+    text.replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
@@ -0,0 +1,73 @@
+//! The labels of a module, from the metadata file in its directory.
+
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use anyhow::Context as _;
+
+/// The names of the per-module metadata files, in order of precedence.
+const FILE_NAMES: [&str; 2] = ["treaform.yaml", "treaform.yml"];
+
+/// Load the labels from the metadata file in the module at `dir`, if there is one.
+///
+/// Only a flat mapping of scalars is supported, e.g. `tier: critical`.
+pub fn load(dir: &Path) -> anyhow::Result<BTreeMap<String, String>> {
+    for file_name in FILE_NAMES {
+        let path = dir.join(file_name);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+            Err(error) => {
+                return Err(error).with_context(|| format!("failed to read {}", path.display()))
+            }
+        };
+        return parse(&contents).with_context(|| format!("failed to parse {}", path.display()));
+    }
+    Ok(BTreeMap::new())
+}
+
+fn parse(contents: &str) -> anyhow::Result<BTreeMap<String, String>> {
+    let mut labels = BTreeMap::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = strip_comment(line).trim_end();
+        if line.trim().is_empty() || line == "---" {
+            continue;
+        }
+        if line.starts_with([' ', '\t', '-']) {
+            anyhow::bail!(
+                "line {}: only flat `key: value` pairs are supported",
+                index + 1
+            )
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            anyhow::bail!("line {}: expected `key: value`", index + 1)
+        };
+        labels.insert(
+            unquote(key.trim()).to_owned(),
+            unquote(value.trim()).to_owned(),
+        );
+    }
+    Ok(labels)
+}
+
+/// Remove a trailing `# comment` which isn't inside a quoted string.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, '#') if previous.is_whitespace() => return &line[..index],
+            _ => {}
+        }
+        previous = c;
+    }
+    line
+}
+
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .into_iter()
+        .find_map(|quote| value.strip_prefix(quote)?.strip_suffix(quote))
+        .unwrap_or(value)
+}
@@ -0,0 +1,78 @@
+//! The size of each module, to spot modules which have grown too large and should be split.
+
+use std::{collections::HashMap, fs, path::Path, rc::Rc};
+
+use anyhow::Context as _;
+use termtree::Tree;
+
+use crate::{
+    intern::Interner,
+    scan::{self, Scanner},
+    visit, TreeNode,
+};
+
+/// Mark each module of `tree`, read from `base` joined with its source, with the number of its
+/// `.tf` files, their non-blank lines, and the resources, variables and outputs they declare, e.g.
+/// `12 files, 1.4k LOC, 37 res, 5 vars, 3 outputs`. Modules which aren't installed aren't marked.
+pub fn annotate(
+    scanner: &mut Scanner,
+    tree: &mut Tree<TreeNode>,
+    base: &Path,
+    interner: &mut Interner,
+) -> anyhow::Result<()> {
+    let mut markers: HashMap<_, Rc<str>> = HashMap::new();
+    let mut result = Ok(());
+    visit(tree, &mut |node| {
+        if node.unresolved || result.is_err() {
+            return;
+        }
+        if !markers.contains_key(&node.source) {
+            match metrics(scanner, &base.join(&*node.source)) {
+                Ok(marker) => {
+                    markers.insert(node.source.clone(), interner.str(&marker));
+                }
+                Err(error) => {
+                    result = Err(error);
+                    return;
+                }
+            }
+        }
+        node.markers.push(markers[&node.source].clone());
+    });
+    result
+}
+
+/// The marker of the module at `dir`.
+fn metrics(scanner: &mut Scanner, dir: &Path) -> anyhow::Result<String> {
+    let files = scan::tf_files(dir)?;
+    let mut lines = 0;
+    for file in &files {
+        lines += fs::read_to_string(file)
+            .with_context(|| format!("failed to read {}", file.display()))?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .count();
+    }
+    let body = scanner.module(dir)?;
+    let plural = |count: usize, noun: &str| {
+        let s = if count == 1 { "" } else { "s" };
+        format!("{count} {noun}{s}")
+    };
+    Ok(format!(
+        "{}, {} LOC, {} res, {}, {}",
+        plural(files.len(), "file"),
+        compact(lines),
+        body.blocks("resource").count(),
+        plural(body.blocks("variable").count(), "var"),
+        plural(body.blocks("output").count(), "output"),
+    ))
+}
+
+/// `count` with thousands abbreviated, e.g. `1.4k`.
+fn compact(count: usize) -> String {
+    if count < 1000 {
+        count.to_string()
+    } else {
+        format!("{:.1}k", count as f64 / 1000.0)
+    }
+}
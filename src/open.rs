@@ -0,0 +1,173 @@
+//! Opening the source of a module call: local directories in an editor, and remote sources in a
+//! browser.
+
+use std::{env, path::Path, process};
+
+use anyhow::Context as _;
+
+use crate::{
+    environment, hcl, installed,
+    scan::Scanner,
+    source::{self, Source},
+    strip_instance_keys,
+};
+
+/// Open the source of the module call at `address` in the project at `dir`.
+pub fn run(dir: &Path, address: &str) -> anyhow::Result<()> {
+    let stripped = strip_instance_keys(address);
+    let segments: Vec<&str> = stripped.split('.').collect();
+    let names: Vec<&str> = segments
+        .chunks(2)
+        .map(|pair| match pair {
+            ["module", name] => Ok(*name),
+            _ => Err(anyhow::anyhow!("`{address}` isn't a module address")),
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    let installed = installed::load(dir)?;
+    let mut scanner = Scanner::default();
+    let mut module_dir = dir.to_owned();
+    for (depth, name) in names.iter().enumerate() {
+        let block = scanner
+            .module_call(&module_dir, name)?
+            .with_context(|| format!("no module `{name}` in {}", module_dir.display()))?;
+        let literal = |name| {
+            block
+                .body
+                .attribute(name)
+                .and_then(|attribute| hcl::string_literal(&attribute.expression))
+        };
+        let source = literal("source")
+            .with_context(|| format!("the source of module `{name}` isn't a string literal"))?;
+        let constraint = literal("version");
+        let key = names[..=depth].join(".");
+        let installed = installed.iter().find(|module| module.key == key);
+
+        if depth + 1 == names.len() {
+            let version = installed
+                .and_then(|module| module.version.clone())
+                .or(constraint.as_deref().and_then(exact_version));
+            return match source::parse(&source) {
+                Source::Local(path) => {
+                    let path = module_dir.join(path);
+                    edit(&path.canonicalize().unwrap_or(path))
+                }
+                source => browse(&url(source, version.as_deref())?),
+            };
+        }
+        module_dir = match (source::parse(&source), installed) {
+            (Source::Local(path), _) => module_dir.join(path),
+            (_, Some(module)) => dir.join(&module.dir),
+            (_, None) => {
+                anyhow::bail!("module `{key}` isn't installed, run `terraform init` first")
+            }
+        };
+    }
+    anyhow::bail!("`{address}` isn't a module address")
+}
+
+/// The version required by `constraint` if it allows only one, e.g. `1.2.0` or `= 1.2.0`.
+fn exact_version(constraint: &str) -> Option<String> {
+    let version = constraint.trim().trim_start_matches('=').trim();
+    let is_version = !version.is_empty()
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-');
+    is_version.then(|| version.to_owned())
+}
+
+/// The web page of a remote module source.
+fn url(source: Source, version: Option<&str>) -> anyhow::Result<String> {
+    Ok(match source {
+        Source::Local(path) => anyhow::bail!("`{path}` is a local path"),
+        Source::Registry {
+            host,
+            namespace,
+            name,
+            provider,
+            subdir,
+        } => {
+            let mut url = format!("https://{host}/modules/{namespace}/{name}/{provider}");
+            if let Some(version) = version {
+                url.push('/');
+                url.push_str(version);
+            }
+            if let Some(submodule) = subdir.and_then(|subdir| subdir.strip_prefix("modules/")) {
+                if version.is_none() {
+                    url.push_str("/latest");
+                }
+                url.push_str("/submodules/");
+                url.push_str(submodule);
+            }
+            url
+        }
+        Source::Git {
+            url,
+            subdir,
+            reference,
+        } => {
+            let repository = source::repository_url(url)
+                .with_context(|| format!("cannot open git repository `{url}` in a browser"))?;
+            if subdir.is_none() && reference.is_none() {
+                return Ok(repository);
+            }
+            let reference = reference.unwrap_or("HEAD");
+            let tree = if repository.contains("gitlab") {
+                "-/tree"
+            } else if repository.contains("bitbucket") {
+                "src"
+            } else {
+                "tree"
+            };
+            let mut url = format!("{repository}/{tree}/{reference}");
+            if let Some(subdir) = subdir {
+                url.push('/');
+                url.push_str(subdir.trim_matches('/'));
+            }
+            url
+        }
+        Source::Other(source)
+            if source.starts_with("https://") || source.starts_with("http://") =>
+        {
+            source.to_owned()
+        }
+        Source::Other(source) => anyhow::bail!("cannot open module source `{source}`"),
+    })
+}
+
+/// Open `dir` in `$VISUAL` or `$EDITOR`.
+fn edit(dir: &Path) -> anyhow::Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .context("set VISUAL or EDITOR to open local modules")?;
+    let mut words = environment::split(&editor).into_iter();
+    let program = words.next().context("VISUAL or EDITOR is empty")?;
+    let mut command = process::Command::new(&program);
+    command.args(words).arg(dir);
+    wait(command, &program)
+}
+
+/// Open `url` in `$BROWSER` or the platform's default browser.
+fn browse(url: &str) -> anyhow::Result<()> {
+    let mut words = match env::var("BROWSER") {
+        Ok(browser) => environment::split(&browser),
+        Err(_) if cfg!(target_os = "macos") => vec!["open".to_owned()],
+        Err(_) if cfg!(windows) => ["cmd", "/C", "start", ""].map(str::to_owned).to_vec(),
+        Err(_) => vec!["xdg-open".to_owned()],
+    }
+    .into_iter();
+    let program = words.next().context("BROWSER is empty")?;
+    let mut command = process::Command::new(&program);
+    command.args(words).arg(url);
+    wait(command, &program)
+}
+
+fn wait(mut command: process::Command, program: &str) -> anyhow::Result<()> {
+    let status = command
+        .status()
+        .with_context(|| format!("failed to spawn `{program}`"))?;
+    if !status.success() {
+        anyhow::bail!("`{program}` failed with {status}")
+    }
+    Ok(())
+}
@@ -0,0 +1,76 @@
+//! Which outputs of each module are consumed by its caller, read from the `.tf` files.
+
+use std::path::Path;
+
+use termtree::Tree;
+
+use crate::{hcl, intern::Interner, scan::Scanner, TreeNode};
+
+/// Mark the module calls beneath `tree` with the outputs their callers refer to, and with the
+/// outputs nothing refers to, reading the modules at `base` joined with their sources.
+///
+/// A caller referring to a whole module, e.g. passing `module.vpc` on, is taken to use every
+/// output. The outputs of the root module are consumed outside of the configuration, so they're
+/// never unused.
+pub fn annotate(
+    scanner: &mut Scanner,
+    tree: &mut Tree<TreeNode>,
+    base: &Path,
+    interner: &mut Interner,
+) -> anyhow::Result<()> {
+    if tree.root.unresolved {
+        return Ok(());
+    }
+    let references: Vec<(String, Option<String>)> = scanner
+        .module(&base.join(&*tree.root.source))?
+        .blocks
+        .iter()
+        .flat_map(|block| block.body.expressions())
+        .flat_map(hcl::output_references)
+        .collect();
+    for leaf in &mut tree.leaves {
+        let consumed: Vec<&Option<String>> = references
+            .iter()
+            .filter(|(call, _)| *call == *leaf.root.name)
+            .map(|(_, output)| output)
+            .collect();
+        let whole = consumed.iter().any(|output| output.is_none());
+        let mut used: Vec<&str> = consumed
+            .iter()
+            .filter_map(|output| output.as_deref())
+            .collect();
+        used.sort_unstable();
+        used.dedup();
+        let outputs: Vec<String> = if leaf.root.unresolved {
+            Vec::new()
+        } else {
+            scanner
+                .module(&base.join(&*leaf.root.source))?
+                .blocks("output")
+                .filter_map(|block| block.labels.first().cloned())
+                .collect()
+        };
+        if whole {
+            leaf.root.markers.push(interner.str("all outputs used"));
+        } else {
+            if !used.is_empty() {
+                leaf.root
+                    .markers
+                    .push(interner.str(&format!("outputs used: {}", used.join(", "))));
+            }
+            let mut unused: Vec<&str> = outputs
+                .iter()
+                .map(String::as_str)
+                .filter(|output| !used.contains(output))
+                .collect();
+            unused.sort_unstable();
+            if !unused.is_empty() {
+                leaf.root
+                    .markers
+                    .push(interner.str(&format!("unused outputs: {}", unused.join(", "))));
+            }
+        }
+        annotate(scanner, leaf, base, interner)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,462 @@
+//! The parts of `terraform show -json` output describing the module structure of a plan.
+//!
+//! Strings are borrowed from the JSON where possible. Use [`Show::into_owned`] for a plan which
+//! outlives its JSON.
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::{split_instance_key, TreaformError};
+
+/// A plan, as output by `terraform show -json`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Show<'a> {
+    pub format_version: Option<String>,
+    /// The version of terraform which made the plan, e.g. `1.8.2`.
+    pub terraform_version: Option<String>,
+    #[serde(borrow = "'a")]
+    pub configuration: Configuration<'a>,
+    #[serde(default)]
+    pub resource_changes: Vec<ResourceChange>,
+    pub planned_values: Option<PlannedValues>,
+}
+
+impl<'a> Show<'a> {
+    /// Deserialize `terraform show -json` output, which must use major format version 1.
+    pub fn parse(json: &'a str) -> Result<Self, TreaformError> {
+        #[derive(Deserialize)]
+        struct Version {
+            format_version: Option<String>,
+        }
+        let check = |version: Option<String>| match version {
+            Some(version) if !version.starts_with("1.") && version != "1" => {
+                Err(TreaformError::UnsupportedFormatVersion { version })
+            }
+            _ => Ok(()),
+        };
+        match serde_json::from_str::<Show>(json) {
+            Ok(show) => {
+                check(show.format_version.clone())?;
+                Ok(show)
+            }
+            Err(error) => {
+                // Report a newer format rather than the first difference in it
+                if let Ok(Version { format_version }) = serde_json::from_str(json) {
+                    check(format_version)?;
+                }
+                Err(TreaformError::Deserialize {
+                    what: "the plan".to_owned(),
+                    error,
+                })
+            }
+        }
+    }
+
+    /// Copy the strings borrowed from the JSON.
+    pub fn into_owned(self) -> Show<'static> {
+        Show {
+            format_version: self.format_version,
+            terraform_version: self.terraform_version,
+            configuration: Configuration {
+                provider_config: self.configuration.provider_config,
+                root_module: self.configuration.root_module.into_owned(),
+            },
+            resource_changes: self.resource_changes,
+            planned_values: self.planned_values,
+        }
+    }
+}
+
+/// The values of the resources after the plan is applied.
+#[derive(Deserialize, Clone, Debug)]
+pub struct PlannedValues {
+    pub root_module: PlannedModule,
+}
+
+/// A module instance of the planned values.
+#[derive(Deserialize, Clone, Debug)]
+pub struct PlannedModule {
+    /// The module instance's address, which is missing for the root module.
+    pub address: Option<String>,
+    #[serde(default)]
+    pub resources: Vec<PlannedResource>,
+    #[serde(default)]
+    pub child_modules: Vec<PlannedModule>,
+}
+
+impl PlannedModule {
+    /// Collect the addresses of the module instances beneath this one, and of their resources if
+    /// `resources` is set.
+    pub fn addresses<'a>(&'a self, resources: bool, addresses: &mut Vec<&'a str>) {
+        addresses.extend(self.address.as_deref());
+        if resources {
+            addresses.extend(
+                self.resources
+                    .iter()
+                    .map(|resource| resource.address.as_str()),
+            );
+        }
+        for child in &self.child_modules {
+            child.addresses(resources, addresses);
+        }
+    }
+
+    /// Collect the addresses of the resources of this module instance and those beneath it, by the
+    /// address of their module instance, which is empty for the root module.
+    pub fn resources<'a>(&'a self, resources: &mut HashMap<&'a str, Vec<&'a str>>) {
+        let module = self.address.as_deref().unwrap_or_default();
+        resources.entry(module).or_default().extend(
+            self.resources
+                .iter()
+                .map(|resource| resource.address.as_str()),
+        );
+        for child in &self.child_modules {
+            child.resources(resources);
+        }
+    }
+
+    /// Collect the evaluated instance keys of the module calls beneath this one, by their
+    /// addresses within their module instances, e.g. `module.app[0].module.db`.
+    pub fn instances(&self, instances: &mut HashMap<String, Vec<String>>) {
+        for child in &self.child_modules {
+            if let Some((call, key)) = child.address.as_deref().and_then(split_instance_key) {
+                let keys = instances.entry(call.to_owned()).or_default();
+                if !keys.iter().any(|existing| existing == key) {
+                    keys.push(key.to_owned());
+                }
+            }
+            child.instances(instances);
+        }
+    }
+}
+
+/// A resource instance of the planned values.
+#[derive(Deserialize, Clone, Debug)]
+pub struct PlannedResource {
+    pub address: String,
+}
+
+/// The planned change to a resource instance.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ResourceChange {
+    pub address: String,
+    pub module_address: Option<String>,
+    /// `managed` or `data`.
+    pub mode: String,
+    /// The key of the deposed object this change destroys, left behind by a failed replacement.
+    pub deposed: Option<String>,
+    /// Why the change was planned, e.g. `replace_because_tainted`.
+    pub action_reason: Option<String>,
+    pub change: Change,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Change {
+    pub actions: Vec<Action>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    NoOp,
+    Create,
+    Read,
+    Update,
+    Delete,
+    #[serde(other)]
+    Other,
+}
+
+/// The configuration of the plan, before evaluation.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Configuration<'a> {
+    /// The providers required or configured by every module, by key, e.g. `module.app:aws.west`.
+    #[serde(default)]
+    pub provider_config: HashMap<String, ProviderConfig>,
+    #[serde(borrow = "'a")]
+    pub root_module: Module<'a>,
+}
+
+/// A provider required or configured by a module.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ProviderConfig {
+    /// The local name of the provider, e.g. `aws`.
+    pub name: String,
+    /// The alias of a configuration, e.g. `west`.
+    pub alias: Option<String>,
+    /// The module's address, which is missing for the root module.
+    pub module_address: Option<String>,
+    pub version_constraint: Option<String>,
+}
+
+/// A module of the configuration, shared by every instance of its call.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Module<'a> {
+    /// The module calls, by name.
+    #[serde(borrow = "'a")]
+    pub module_calls: Option<HashMap<Cow<'a, str>, ModuleCall<'a>>>,
+    #[serde(borrow = "'a", default)]
+    pub resources: Vec<Resource<'a>>,
+    /// The input variables, by name.
+    #[serde(default)]
+    pub variables: HashMap<String, Variable>,
+}
+
+/// A `variable` block of the configuration.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct Variable {
+    #[serde(default)]
+    pub sensitive: bool,
+}
+
+impl Module<'_> {
+    /// The number of this module's own resources per provider local name.
+    pub fn providers(&self) -> BTreeMap<&str, usize> {
+        let mut providers = BTreeMap::new();
+        for resource in &self.resources {
+            *providers.entry(resource.provider()).or_default() += 1;
+        }
+        providers
+    }
+
+    /// The module called at `address` beneath this one, e.g. `module.app.module.db`, which has no
+    /// instance keys.
+    pub fn module(&self, address: &str) -> Option<&Self> {
+        let mut module = self;
+        let mut segments = address.split('.').filter(|segment| !segment.is_empty());
+        while let Some(segment) = segments.next() {
+            if segment != "module" {
+                return None;
+            }
+            let name = segments.next()?;
+            module = &module.module_calls.as_ref()?.get(name)?.module;
+        }
+        Some(module)
+    }
+
+    /// Copy the strings borrowed from the JSON.
+    pub fn into_owned(self) -> Module<'static> {
+        Module {
+            module_calls: self.module_calls.map(|calls| {
+                calls
+                    .into_iter()
+                    .map(|(name, call)| (Cow::Owned(name.into_owned()), call.into_owned()))
+                    .collect()
+            }),
+            resources: self
+                .resources
+                .into_iter()
+                .map(|resource| Resource {
+                    address: Cow::Owned(resource.address.into_owned()),
+                    provider_config_key: Cow::Owned(resource.provider_config_key.into_owned()),
+                })
+                .collect(),
+            variables: self.variables,
+        }
+    }
+}
+
+/// A `module` block of the configuration.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ModuleCall<'a> {
+    #[serde(borrow = "'a")]
+    pub module: Module<'a>,
+    #[serde(borrow = "'a")]
+    pub source: Cow<'a, str>,
+    /// The input variables, as expressions.
+    #[serde(default)]
+    pub expressions: serde_json::Value,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    pub version_constraint: Option<String>,
+    pub count_expression: Option<CountExpression>,
+    pub for_each_expression: Option<ForEachExpression>,
+}
+
+impl ModuleCall<'_> {
+    /// The input variables passed to the module, by name, with the constant values of sensitive
+    /// variables, and of expressions the plan marks `"sensitive": true`, masked unless `reveal`.
+    pub fn inputs(&self, reveal: bool) -> Vec<(String, Input)> {
+        let Some(expressions) = self.expressions.as_object() else {
+            return Vec::new();
+        };
+        expressions
+            .iter()
+            .map(|(name, expression)| {
+                let sensitive = self
+                    .module
+                    .variables
+                    .get(name)
+                    .is_some_and(|variable| variable.sensitive)
+                    || expression.get("sensitive") == Some(&serde_json::Value::Bool(true));
+                let references: Vec<String> = expression
+                    .get("references")
+                    .and_then(serde_json::Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|reference| reference.as_str().map(str::to_owned))
+                    .collect();
+                let input = match expression.get("constant_value") {
+                    Some(_) if sensitive && !reveal => Input::Sensitive,
+                    Some(value) => Input::Constant(value.clone()),
+                    None if references.is_empty() => Input::Computed,
+                    None => Input::References(outermost(&references)),
+                };
+                (name.clone(), input)
+            })
+            .collect()
+    }
+
+    /// Whether the `for_each` of the call is marked sensitive, or refers to a sensitive variable of
+    /// `caller`, the module containing the call.
+    pub fn sensitive_for_each(&self, caller: &Module) -> bool {
+        let Some(for_each) = &self.for_each_expression else {
+            return false;
+        };
+        for_each.sensitive
+            || for_each.references.iter().any(|reference| {
+                reference
+                    .strip_prefix("var.")
+                    .and_then(|name| name.split(['.', '[']).next())
+                    .and_then(|name| caller.variables.get(name))
+                    .is_some_and(|variable| variable.sensitive)
+            })
+    }
+
+    /// Copy the strings borrowed from the JSON.
+    pub fn into_owned(self) -> ModuleCall<'static> {
+        ModuleCall {
+            module: self.module.into_owned(),
+            source: Cow::Owned(self.source.into_owned()),
+            expressions: self.expressions,
+            depends_on: self.depends_on,
+            version_constraint: self.version_constraint,
+            count_expression: self.count_expression,
+            for_each_expression: self.for_each_expression,
+        }
+    }
+}
+
+/// A resource block of the configuration.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Resource<'a> {
+    /// The address within the module, e.g. `aws_instance.web` or `data.aws_ami.ubuntu`.
+    #[serde(borrow = "'a")]
+    pub address: Cow<'a, str>,
+    #[serde(borrow = "'a")]
+    pub provider_config_key: Cow<'a, str>,
+}
+
+impl Resource<'_> {
+    /// The local name of the provider, e.g. `aws` for `module.foo:aws.west`.
+    pub fn provider(&self) -> &str {
+        let key = self
+            .provider_config_key
+            .rsplit_once(':')
+            .map_or(&*self.provider_config_key, |(_, key)| key);
+        key.split_once('.').map_or(key, |(name, _)| name)
+    }
+}
+
+/// The value passed to an input variable of a module call.
+#[derive(Clone, Debug)]
+pub enum Input {
+    /// A value known before evaluation.
+    Constant(serde_json::Value),
+    /// What the expression refers to, e.g. `var.region`.
+    References(Vec<String>),
+    /// An expression with neither a constant value nor references, e.g. `timestamp()`.
+    Computed,
+    /// The constant value of a sensitive variable.
+    Sensitive,
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Input::Constant(value) => write!(f, "{value}"),
+            Input::References(references) => f.write_str(&references.join(", ")),
+            Input::Computed => f.write_str("(computed)"),
+            Input::Sensitive => f.write_str("(sensitive)"),
+        }
+    }
+}
+
+/// A `count` meta-argument.
+#[derive(Deserialize, Clone, Debug)]
+pub struct CountExpression {
+    /// The number of instances, when known before evaluation.
+    pub constant_value: Option<usize>,
+    /// What the expression refers to, e.g. `var.replicas`.
+    #[serde(default)]
+    pub references: Vec<String>,
+}
+
+/// A `for_each` meta-argument.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ForEachExpression {
+    /// The value of each instance by instance key, when known before evaluation.
+    #[serde(default, deserialize_with = "ForEachValue::deserialize_instances")]
+    pub constant_value: Option<BTreeMap<String, serde_json::Value>>,
+    /// What the expression refers to, e.g. `local.environments`.
+    #[serde(default)]
+    pub references: Vec<String>,
+    /// Whether the plan marks the expression `"sensitive": true`.
+    #[serde(default)]
+    pub sensitive: bool,
+}
+
+/// The references of an expression, without those covered by a longer one before them, e.g.
+/// `var.settings.replicas` and not `var.settings`.
+pub fn outermost(references: &[String]) -> Vec<String> {
+    references
+        .iter()
+        .enumerate()
+        .filter(|(index, reference)| {
+            !references[..*index].iter().any(|longer| {
+                longer
+                    .strip_prefix(reference.as_str())
+                    .is_some_and(|rest| rest.starts_with(['.', '[']))
+            })
+        })
+        .map(|(_, reference)| reference.clone())
+        .collect()
+}
+
+/// A constant `for_each` value, which is either a map or a set of strings (shown as a list).
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ForEachValue {
+    Map(BTreeMap<String, serde_json::Value>),
+    Set(Vec<serde_json::Value>),
+}
+
+impl ForEachValue {
+    /// Deserialize the value of each instance keyed by its instance key. The elements of sets are
+    /// both key and value, as with `each.key` and `each.value`.
+    fn deserialize_instances<'de, D>(
+        deserializer: D,
+    ) -> Result<Option<BTreeMap<String, serde_json::Value>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Some(match ForEachValue::deserialize(deserializer)? {
+            ForEachValue::Map(map) => map,
+            ForEachValue::Set(set) => set
+                .into_iter()
+                .map(|element| {
+                    let key = match &element {
+                        serde_json::Value::String(key) => key.clone(),
+                        element => element.to_string(),
+                    };
+                    (key, element)
+                })
+                .collect(),
+        }))
+    }
+}
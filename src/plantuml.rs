@@ -0,0 +1,36 @@
+//! Rendering the tree as a PlantUML diagram, e.g. for `plantuml -tsvg`.
+
+use std::fmt::Write as _;
+
+use termtree::Tree;
+
+use crate::{
+    render::{self, Render},
+    TreeNode,
+};
+
+/// A diagram with an arrow from each module to the modules it calls, drawn as rectangles labelled
+/// with their names and with their locations as tooltips.
+pub struct PlantUml;
+
+impl Render for PlantUml {
+    fn render(&self, tree: &Tree<TreeNode>, out: &mut String) -> anyhow::Result<()> {
+        out.push_str("@startuml\n");
+        render::graph(
+            out,
+            tree,
+            &|out, id, label, node| {
+                write!(out, "rectangle \"{}\" as n{id}", label.replace('"', "'"))?;
+                if let Some(location) = node.location() {
+                    // A link without a target is only a tooltip
+                    let tooltip = location.replace(['{', '}', '[', ']'], "_");
+                    write!(out, " [[{{{tooltip}}}]]")?;
+                }
+                writeln!(out)
+            },
+            &|out, id, child| writeln!(out, "n{id} --> n{child}"),
+        )?;
+        out.push_str("@enduml\n");
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+//! A live progress line on stderr while terraform plans, from its machine-readable log.
+
+use std::{
+    io::{self, BufRead as _, BufReader, IsTerminal as _, Read as _, Write as _},
+    process::{self, Stdio},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+use crate::{Args, TreaformError};
+
+/// The frames of the spinner.
+const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// How often the spinner turns while the log is quiet.
+const TICK: Duration = Duration::from_millis(100);
+
+/// Whether to show progress while planning, which needs the machine-readable log.
+pub fn enabled(args: &Args) -> bool {
+    !args.no_progress && io::stderr().is_terminal()
+}
+
+/// A message of the machine-readable log.
+#[derive(Deserialize)]
+struct Message {
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+/// The counts of the operations in the log so far.
+#[derive(Default)]
+struct Progress {
+    refreshing: usize,
+    refreshed: usize,
+    reading: usize,
+    read: usize,
+    changes: usize,
+}
+
+impl Progress {
+    fn update(&mut self, line: &str) {
+        let Ok(message) = serde_json::from_str::<Message>(line) else {
+            return;
+        };
+        match message.kind.as_str() {
+            "refresh_start" => self.refreshing += 1,
+            "refresh_complete" => self.refreshed += 1,
+            // Data sources are read with apply hooks during a plan
+            "apply_start" => self.reading += 1,
+            "apply_complete" | "apply_errored" => self.read += 1,
+            "planned_change" => self.changes += 1,
+            _ => {}
+        }
+    }
+
+    /// The progress line, e.g. `refreshing 412/900 resources…`.
+    fn line(&self) -> String {
+        let mut line = if self.changes > 0 {
+            format!("planning, {} changes so far…", self.changes)
+        } else if self.refreshing > 0 {
+            format!(
+                "refreshing {}/{} resources…",
+                self.refreshed, self.refreshing
+            )
+        } else {
+            "planning…".to_owned()
+        };
+        if self.read < self.reading {
+            line.push_str(&format!(
+                " reading {}/{} data sources",
+                self.read, self.reading
+            ));
+        }
+        line
+    }
+}
+
+/// Run the plan `command`, which writes the machine-readable log to stdout, showing its progress
+/// on stderr until it finishes. Returns its stdout, or fails with its error output.
+///
+/// `name` names the command in errors, e.g. `terraform plan`.
+pub fn output(mut command: process::Command, name: &str) -> Result<String, TreaformError> {
+    let spawn_error = |error| TreaformError::Spawn {
+        command: name.to_owned(),
+        error,
+    };
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(spawn_error)?;
+    let (stdout, mut stderr) = (child.stdout.take(), child.stderr.take());
+    let (sender, receiver) = mpsc::channel();
+    let reader = thread::spawn(move || {
+        for line in BufReader::new(stdout?).lines() {
+            let Ok(line) = line else { break };
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+        Some(())
+    });
+    // Read stderr concurrently, so that terraform never blocks on a full pipe
+    let errors = thread::spawn(move || {
+        let mut errors = String::new();
+        if let Some(stderr) = &mut stderr {
+            let _ = stderr.read_to_string(&mut errors);
+        }
+        errors
+    });
+
+    let mut progress = Progress::default();
+    let mut log = String::new();
+    let mut frame = 0;
+    loop {
+        match receiver.recv_timeout(TICK) {
+            Ok(line) => {
+                progress.update(&line);
+                log.push_str(&line);
+                log.push('\n');
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => frame = (frame + 1) % FRAMES.len(),
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+        let _ = write!(
+            io::stderr(),
+            "\r\x1b[2K{} {}",
+            FRAMES[frame],
+            progress.line()
+        );
+    }
+    let _ = write!(io::stderr(), "\r\x1b[2K");
+    let _ = reader.join();
+    let errors = errors.join().unwrap_or_default();
+    let status = child.wait().map_err(spawn_error)?;
+    if !status.success() {
+        return Err(TreaformError::CommandFailed {
+            command: name.to_owned(),
+            diagnostics: if errors.is_empty() { log } else { errors },
+        });
+    }
+    Ok(log)
+}
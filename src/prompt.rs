@@ -0,0 +1,110 @@
+//! Prompting for the values of required variables which `terraform plan` found unset.
+
+use std::{
+    io::{self, BufRead as _, IsTerminal as _, Write as _},
+    path::Path,
+    process::{self, Stdio},
+};
+
+use anyhow::Context as _;
+
+use crate::{hcl, scan};
+
+/// Whether values can be prompted for, which needs a terminal to read them from and to show the
+/// prompts on.
+pub fn interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+/// The names of the root module variables which `diagnostics` report as having no value.
+pub fn missing_variables(diagnostics: &str) -> Vec<String> {
+    const PREFIX: &str = "The root module input variable \"";
+    let mut names = Vec::new();
+    for (start, _) in diagnostics.match_indices(PREFIX) {
+        let rest = &diagnostics[start + PREFIX.len()..];
+        let Some((name, rest)) = rest.split_once('"') else {
+            continue;
+        };
+        if rest.starts_with(" is not set") && !names.iter().any(|known| known == name) {
+            names.push(name.to_owned());
+        }
+    }
+    names
+}
+
+/// Prompt for the values of the variables `names`, declared in the module at `dir`, returning
+/// them as `TF_VAR_<name>` environment variables, which unlike `-var` arguments don't show in the
+/// process list. Sensitive values aren't echoed.
+pub fn variables(dir: &Path, names: &[String]) -> anyhow::Result<Vec<(String, String)>> {
+    let module = scan::parse_dir(dir)?;
+    let mut vars = Vec::with_capacity(names.len());
+    for name in names {
+        let variable = module
+            .blocks("variable")
+            .find(|block| block.labels.first() == Some(name));
+        let description = variable
+            .and_then(|block| block.body.attribute("description"))
+            .and_then(|attribute| hcl::string_literal(&attribute.expression));
+        let sensitive = variable
+            .and_then(|block| block.body.attribute("sensitive"))
+            .is_some_and(|attribute| attribute.expression.trim() == "true");
+
+        eprintln!("var.{name}");
+        if let Some(description) = description {
+            eprintln!("  {description}");
+        }
+        eprintln!();
+        let value = read_line("  Enter a value: ", sensitive)?;
+        eprintln!();
+        vars.push((format!("TF_VAR_{name}"), value));
+    }
+    Ok(vars)
+}
+
+/// Show `prompt` and read a line from stdin, without echoing it if `hidden`.
+fn read_line(prompt: &str, hidden: bool) -> anyhow::Result<String> {
+    // Stop echoing before the prompt shows, so nothing typed in response is echoed
+    let no_echo = hidden.then(NoEcho::new).transpose()?;
+    eprint!("{prompt}");
+    io::stderr().flush()?;
+    let mut line = String::new();
+    let read = io::stdin().lock().read_line(&mut line);
+    drop(no_echo);
+    read.context("failed to read variable value")?;
+    if line.is_empty() {
+        anyhow::bail!("no value entered")
+    }
+    Ok(line.trim_end_matches(['\n', '\r']).to_owned())
+}
+
+/// Stops the terminal on stdin echoing what's typed until dropped, however reading ends.
+struct NoEcho;
+
+impl NoEcho {
+    fn new() -> anyhow::Result<Self> {
+        stty("-echo")?;
+        Ok(NoEcho)
+    }
+}
+
+impl Drop for NoEcho {
+    fn drop(&mut self) {
+        // There's no way to report a failure from here, and the read's error matters more
+        let _ = stty("echo");
+        // The newline typed wasn't echoed
+        eprintln!();
+    }
+}
+
+/// Change the settings of the terminal on stdin.
+fn stty(setting: &str) -> anyhow::Result<()> {
+    let status = process::Command::new("stty")
+        .arg(setting)
+        .stdin(Stdio::inherit())
+        .status()
+        .context("failed to spawn `stty`")?;
+    if !status.success() {
+        anyhow::bail!("`stty {setting}` failed")
+    }
+    Ok(())
+}
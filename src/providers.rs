@@ -0,0 +1,95 @@
+//! The providers each module requires, and where the configurations of those providers come from.
+
+use std::{collections::HashMap, path::Path};
+
+use termtree::Tree;
+
+use crate::{hcl, intern::Interner, plan::ProviderConfig, scan::Scanner, TreeNode};
+
+/// Mark each module of `tree` with the providers it requires and their version constraints, from
+/// `provider_config`, and with the provider configurations it defines with `provider` blocks,
+/// read from the module at `base` joined with its source.
+///
+/// Child modules are also marked with the default configurations they inherit from their caller,
+/// which are those of their providers that they neither define nor are passed with the
+/// `providers` meta-argument.
+pub fn annotate(
+    scanner: &mut Scanner,
+    tree: &mut Tree<TreeNode>,
+    base: &Path,
+    provider_config: &HashMap<String, ProviderConfig>,
+    interner: &mut Interner,
+) -> anyhow::Result<()> {
+    let node = &mut tree.root;
+    let mut required: Vec<(&str, Option<&str>)> = provider_config
+        .values()
+        .filter(|config| config.module_address.as_deref().unwrap_or_default() == &*node.address)
+        .map(|config| (config.name.as_str(), config.version_constraint.as_deref()))
+        .collect();
+    required.sort_unstable();
+    // Aliased configurations are entries of their own, without the constraint
+    required.dedup_by(|next, first| {
+        next.0 == first.0 && {
+            first.1 = first.1.or(next.1);
+            true
+        }
+    });
+    let configured: Vec<String> = if node.unresolved {
+        Vec::new()
+    } else {
+        scanner
+            .module(&base.join(&*node.source))?
+            .blocks("provider")
+            .filter_map(|block| {
+                let name = block.labels.first()?;
+                let alias = block
+                    .body
+                    .attribute("alias")
+                    .and_then(|alias| hcl::string_literal(&alias.expression));
+                Some(match alias {
+                    Some(alias) => format!("{name}.{alias}"),
+                    None => name.clone(),
+                })
+            })
+            .collect()
+    };
+    if !required.is_empty() {
+        let required: Vec<String> = required
+            .iter()
+            .map(|(name, constraint)| match constraint {
+                Some(constraint) => format!("{name} {constraint}"),
+                None => (*name).to_owned(),
+            })
+            .collect();
+        node.markers
+            .push(interner.str(&format!("requires {}", required.join(", "))));
+    }
+    if !configured.is_empty() {
+        node.markers
+            .push(interner.str(&format!("configures {}", configured.join(", "))));
+    }
+    if !node.address.is_empty() {
+        let mut used: Vec<&str> = required.iter().map(|(name, _)| *name).collect();
+        used.extend(node.providers.keys().map(|name| &**name));
+        used.sort_unstable();
+        used.dedup();
+        let inherited: Vec<&str> = used
+            .into_iter()
+            .filter(|name| !configured.iter().any(|configured| configured == name))
+            .filter(|name| {
+                !node
+                    .provider_mappings
+                    .iter()
+                    .any(|(child, _)| child == name)
+            })
+            .collect();
+        if !inherited.is_empty() {
+            node.markers
+                .push(interner.str(&format!("inherits {}", inherited.join(", "))));
+        }
+    }
+    for leaf in &mut tree.leaves {
+        annotate(scanner, leaf, base, provider_config, interner)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,224 @@
+//! The latest versions of registry modules, from the module registry protocol, cached on disk.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context as _;
+use serde::Deserialize;
+use termtree::Tree;
+
+use crate::{
+    constraints::{self, Version},
+    fetch, installed,
+    intern::Interner,
+    pool,
+    source::{self, Source, DEFAULT_REGISTRY},
+    visit, TreeNode,
+};
+
+/// How long the versions of a module are cached for, unless offline.
+const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A module of the registry, by its host and `namespace/name/provider`.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Module {
+    host: String,
+    path: String,
+}
+
+#[derive(Deserialize)]
+struct Versions {
+    modules: Vec<ModuleVersions>,
+}
+
+#[derive(Deserialize)]
+struct ModuleVersions {
+    versions: Vec<ModuleVersion>,
+}
+
+#[derive(Deserialize)]
+struct ModuleVersion {
+    version: String,
+}
+
+/// The service discovery document of a registry host.
+#[derive(Deserialize)]
+struct Discovery {
+    #[serde(rename = "modules.v1")]
+    modules: Option<String>,
+}
+
+/// Mark the registry module calls of `tree` with the latest version available when it's newer
+/// than the version `terraform init` installed for the project at `dir`, e.g. `1.2.0 → 1.8.3
+/// available`, noting when the call's version constraint excludes it.
+///
+/// Responses are cached in `cache_dir`. When `offline`, the registry isn't queried, and modules
+/// without cached versions aren't marked. Modules whose versions can't be fetched are reported on
+/// stderr.
+pub fn annotate(
+    tree: &mut Tree<TreeNode>,
+    dir: &Path,
+    cache_dir: &Path,
+    offline: bool,
+    parallelism: usize,
+    interner: &mut Interner,
+) -> anyhow::Result<()> {
+    let installed: HashMap<String, String> = installed::load(dir)?
+        .into_iter()
+        .filter_map(|module| Some((module.key, module.version?)))
+        .collect();
+    let mut modules = Vec::new();
+    visit(tree, &mut |node| {
+        if let Some(module) = node.remote.as_deref().and_then(module) {
+            if !modules.contains(&module) {
+                modules.push(module);
+            }
+        }
+    });
+    let latest = pool::map(&modules, parallelism, |module| {
+        latest(module, cache_dir, offline)
+    });
+    let mut versions = HashMap::new();
+    for (module, latest) in modules.into_iter().zip(latest) {
+        match latest {
+            Ok(Some(latest)) => {
+                versions.insert(module, latest);
+            }
+            Ok(None) => {}
+            Err(error) => eprintln!("Warning: {error:#}"),
+        }
+    }
+    visit(tree, &mut |node| {
+        let Some(latest) = node
+            .remote
+            .as_deref()
+            .and_then(module)
+            .and_then(|module| versions.get(&module))
+        else {
+            return;
+        };
+        let key = node
+            .address
+            .strip_prefix("module.")
+            .unwrap_or_default()
+            .replace(".module.", ".");
+        let Some(parsed) = Version::parse(latest) else {
+            return;
+        };
+        let current = installed.get(&key);
+        let newer = current
+            .and_then(|current| Version::parse(current))
+            .is_none_or(|current| parsed.compare(&current).is_gt());
+        if !newer {
+            return;
+        }
+        let mut marker = match current {
+            Some(current) => format!("{current} → {latest} available"),
+            None => format!("{latest} available"),
+        };
+        if let Some(constraint) = &node.version {
+            if constraints::allows(constraint, &parsed) == Some(false) {
+                marker.push_str(&format!(", excluded by {constraint}"));
+            }
+        }
+        node.markers.push(interner.str(&marker));
+    });
+    Ok(())
+}
+
+/// The registry module of the module call source `source`, if it's one.
+fn module(source: &str) -> Option<Module> {
+    match source::parse(source) {
+        Source::Registry {
+            host,
+            namespace,
+            name,
+            provider,
+            ..
+        } => Some(Module {
+            host: host.to_lowercase(),
+            path: format!("{namespace}/{name}/{provider}"),
+        }),
+        _ => None,
+    }
+}
+
+/// The latest version of `module` which isn't a pre-release, if it has any.
+fn latest(module: &Module, cache_dir: &Path, offline: bool) -> anyhow::Result<Option<String>> {
+    let Some(json) = versions(module, cache_dir, offline)? else {
+        return Ok(None);
+    };
+    let versions: Versions = serde_json::from_str(&json).with_context(|| {
+        format!(
+            "failed to deserialize the versions of {}/{}",
+            module.host, module.path
+        )
+    })?;
+    Ok(versions
+        .modules
+        .into_iter()
+        .flat_map(|module| module.versions)
+        .map(|version| version.version)
+        .filter(|version| !version.contains('-'))
+        .filter_map(|version| Some((Version::parse(&version)?, version)))
+        .max_by(|(a, _), (b, _)| a.compare(b))
+        .map(|(_, version)| version))
+}
+
+/// The versions JSON of `module`, cached in `cache_dir`.
+fn versions(module: &Module, cache_dir: &Path, offline: bool) -> anyhow::Result<Option<String>> {
+    let path: PathBuf = cache_dir
+        .join("registry")
+        .join(&module.host)
+        .join(format!("{}.json", module.path));
+    let age = fs::metadata(&path)
+        .and_then(|metadata| metadata.modified())
+        .map(|modified| {
+            SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default()
+        });
+    match age {
+        Ok(age) if offline || age < MAX_AGE => {
+            return fs::read_to_string(&path)
+                .map(Some)
+                .with_context(|| format!("failed to read {}", path.display()))
+        }
+        Err(error) if error.kind() != io::ErrorKind::NotFound => {
+            return Err(error).with_context(|| format!("failed to read {}", path.display()))
+        }
+        _ if offline => return Ok(None),
+        _ => {}
+    }
+    let base = if module.host == DEFAULT_REGISTRY {
+        format!("https://{DEFAULT_REGISTRY}/v1/modules/")
+    } else {
+        discover(&module.host)?
+    };
+    let json = fetch::get(&format!("{base}{}/versions", module.path), None)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(&path, &json).with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(Some(json))
+}
+
+/// The base URL of the modules API of the registry at `host`, from its service discovery document.
+fn discover(host: &str) -> anyhow::Result<String> {
+    let json = fetch::get(&format!("https://{host}/.well-known/terraform.json"), None)?;
+    let discovery: Discovery = serde_json::from_str(&json)
+        .with_context(|| format!("failed to deserialize the services of {host}"))?;
+    let Some(modules) = discovery.modules else {
+        anyhow::bail!("{host} is no module registry")
+    };
+    Ok(if modules.starts_with("https://") {
+        modules
+    } else {
+        format!("https://{host}{modules}")
+    })
+}
@@ -0,0 +1,109 @@
+//! The output formats of the tree.
+
+use std::fmt::{self, Write as _};
+
+use termtree::Tree;
+
+use crate::TreeNode;
+
+/// A way of writing the tree as text.
+pub trait Render {
+    /// Append the rendering of `tree` to `out`.
+    fn render(&self, tree: &Tree<TreeNode>, out: &mut String) -> anyhow::Result<()>;
+}
+
+/// The tree drawn with box-drawing guide lines.
+pub struct Guides {
+    /// Whether to highlight module names, instances and sources with ANSI colors.
+    pub highlight: bool,
+}
+
+impl Render for Guides {
+    fn render(&self, tree: &Tree<TreeNode>, out: &mut String) -> anyhow::Result<()> {
+        if !self.highlight {
+            write!(out, "{tree}")?;
+            return Ok(());
+        }
+        draw(
+            out,
+            tree,
+            &|out, guide, _| out.push_str(guide),
+            &|out, node, _| out.push_str(&node.line(true)),
+        );
+        Ok(())
+    }
+}
+
+/// Write `tree` as a graph with an edge from each module to the modules it calls, numbering the
+/// modules in order from 0. Each module is written with `node`, given its id, its label, including
+/// any chain collapsed into it, and the module itself, and each edge with `edge`, given the ids of
+/// the caller and of the module called.
+pub fn graph(
+    out: &mut String,
+    tree: &Tree<TreeNode>,
+    node: &impl Fn(&mut String, usize, &str, &TreeNode) -> fmt::Result,
+    edge: &impl Fn(&mut String, usize, usize) -> fmt::Result,
+) -> fmt::Result {
+    graph_node(out, tree, node, edge, &mut 0).map(drop)
+}
+
+/// Write the module of `tree` and its descendants as [`graph`] does, returning its id.
+fn graph_node(
+    out: &mut String,
+    tree: &Tree<TreeNode>,
+    node: &impl Fn(&mut String, usize, &str, &TreeNode) -> fmt::Result,
+    edge: &impl Fn(&mut String, usize, usize) -> fmt::Result,
+    next_id: &mut usize,
+) -> Result<usize, fmt::Error> {
+    let id = *next_id;
+    *next_id += 1;
+    let mut label = String::new();
+    for chained in &tree.root.chain {
+        write!(label, "{chained} ▸ ")?;
+    }
+    tree.root.write_label(&mut label)?;
+    node(out, id, &label, &tree.root)?;
+    for leaf in &tree.leaves {
+        let child = graph_node(out, leaf, node, edge, next_id)?;
+        edge(out, id, child)?;
+    }
+    Ok(id)
+}
+
+/// Draw `tree` like [`Tree`]'s `Display` does, writing each guide, e.g. `├── `, with `guide` and
+/// each node with `node`, both given the depth they belong to.
+pub fn draw(
+    out: &mut String,
+    tree: &Tree<TreeNode>,
+    guide: &impl Fn(&mut String, &str, usize),
+    node: &impl Fn(&mut String, &TreeNode, usize),
+) {
+    node(out, &tree.root, 0);
+    out.push('\n');
+    leaves(out, tree, guide, node, &mut Vec::new());
+}
+
+fn leaves(
+    out: &mut String,
+    tree: &Tree<TreeNode>,
+    guide: &impl Fn(&mut String, &str, usize),
+    node: &impl Fn(&mut String, &TreeNode, usize),
+    guides: &mut Vec<bool>,
+) {
+    for (index, leaf) in tree.leaves.iter().enumerate() {
+        let last = index + 1 == tree.leaves.len();
+        for (depth, ancestor_last) in guides.iter().enumerate() {
+            if *ancestor_last {
+                out.push_str("    ");
+            } else {
+                guide(out, "│   ", depth);
+            }
+        }
+        guide(out, if last { "└── " } else { "├── " }, guides.len());
+        node(out, &leaf.root, guides.len() + 1);
+        out.push('\n');
+        guides.push(last);
+        leaves(out, leaf, guide, node, guides);
+        guides.pop();
+    }
+}
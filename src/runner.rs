@@ -0,0 +1,95 @@
+//! The programs which plan and apply projects.
+
+use std::{
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process,
+};
+
+use anyhow::Context as _;
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::output;
+
+/// The program running terraform's commands.
+#[derive(ValueEnum, Clone, Copy, Default, Debug)]
+pub enum Runner {
+    /// The terraform binary itself.
+    #[default]
+    Terraform,
+    /// Terragrunt, wrapping the terraform binary in each unit with a terragrunt.hcl.
+    Terragrunt,
+}
+
+impl Runner {
+    /// The name of the program running `binary`, e.g. `tofu`.
+    pub fn program(self, binary: &Path) -> String {
+        match self {
+            Runner::Terraform => binary
+                .file_name()
+                .unwrap_or(binary.as_os_str())
+                .to_string_lossy()
+                .into_owned(),
+            Runner::Terragrunt => "terragrunt".to_owned(),
+        }
+    }
+
+    /// A command of the program running `binary` in the project at `dir`: with `-chdir` for
+    /// terraform, and from within `dir` for terragrunt, which has no such option.
+    pub fn command(self, binary: &Path, dir: &Path) -> process::Command {
+        match self {
+            Runner::Terraform => {
+                let mut command = process::Command::new(binary);
+                let mut chdir = OsString::from("-chdir=");
+                chdir.push(dir.as_os_str());
+                command.arg(chdir);
+                command
+            }
+            Runner::Terragrunt => {
+                let mut command = process::Command::new("terragrunt");
+                command.current_dir(dir).env("TERRAGRUNT_TFPATH", binary);
+                command
+            }
+        }
+    }
+}
+
+/// The terraform binary to use when none is given: OpenTofu's `tofu` if it's installed, or else
+/// `terraform`.
+pub fn detect() -> PathBuf {
+    let installed = |name: &str| {
+        env::var_os("PATH")
+            .is_some_and(|path| env::split_paths(&path).any(|dir| dir.join(name).is_file()))
+    };
+    PathBuf::from(if installed("tofu") {
+        "tofu"
+    } else {
+        "terraform"
+    })
+}
+
+/// The output of `terraform version -json`, which OpenTofu shares.
+#[derive(Deserialize)]
+struct Version {
+    terraform_version: String,
+}
+
+/// The terraform `binary` with its version, e.g. `tofu 1.8.2`, for verbose output.
+pub fn describe(binary: &Path) -> String {
+    match version(binary) {
+        Ok(version) => format!("{} {version}", binary.display()),
+        Err(error) => format!("{}, of unknown version: {error:#}", binary.display()),
+    }
+}
+
+/// The version of the terraform `binary`, e.g. `1.8.2`.
+fn version(binary: &Path) -> anyhow::Result<String> {
+    let mut command = process::Command::new(binary);
+    command.args(["version", "-json"]);
+    let name = format!("{} version", binary.display());
+    let version: Version = serde_json::from_str(&output(command, &name)?)
+        .with_context(|| format!("failed to read the output of `{name} -json`"))?;
+    Ok(version.terraform_version)
+}
@@ -0,0 +1,178 @@
+//! Parsing the `.tf` files of module directories, once for all the modules sharing them.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+use termtree::Tree;
+
+use crate::{
+    hcl::{self, Block, Body},
+    intern::Interner,
+    pool, TreeNode,
+};
+
+/// Parses and caches the `.tf` files of module directories.
+#[derive(Default)]
+pub struct Scanner {
+    modules: HashMap<PathBuf, Body>,
+}
+
+impl Scanner {
+    /// The combined top-level blocks and attributes of the `.tf` files in `dir`.
+    pub fn module(&mut self, dir: &Path) -> anyhow::Result<&Body> {
+        if !self.modules.contains_key(dir) {
+            let body = parse_dir(dir)?;
+            self.modules.insert(dir.to_owned(), body);
+        }
+        Ok(&self.modules[dir])
+    }
+
+    /// Parse the modules at `dirs` ahead of time, on at most `parallelism` threads. Modules which
+    /// fail to parse are left to report their errors when requested.
+    pub fn preload(&mut self, dirs: &[PathBuf], parallelism: usize) {
+        let dirs: Vec<&PathBuf> = dirs
+            .iter()
+            .filter(|dir| !self.modules.contains_key(dir.as_path()))
+            .collect();
+        let bodies = pool::map(&dirs, parallelism, |dir| parse_dir(dir));
+        for (dir, body) in dirs.into_iter().zip(bodies) {
+            if let Ok(body) = body {
+                self.modules.insert(dir.clone(), body);
+            }
+        }
+    }
+
+    /// The `module "<name>"` block in the module at `dir`.
+    pub fn module_call(&mut self, dir: &Path, name: &str) -> anyhow::Result<Option<&Block>> {
+        Ok(self
+            .module(dir)?
+            .blocks("module")
+            .find(|block| block.labels.first().is_some_and(|label| label == name)))
+    }
+}
+
+/// The paths of the `.tf` files in `dir`, sorted by name.
+pub fn tf_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let entries = fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "tf") && path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether `path` is an override file, `override.tf` or `*_override.tf`, which terraform merges
+/// into the blocks of the other files.
+fn is_override(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| stem == "override" || stem.ends_with("_override"))
+}
+
+/// The combined top-level blocks and attributes of the `.tf` files in `dir`, with the override
+/// files merged into the blocks they override.
+pub fn parse_dir(dir: &Path) -> anyhow::Result<Body> {
+    let mut combined = Body::default();
+    let (overrides, files): (Vec<PathBuf>, Vec<PathBuf>) = tf_files(dir)?
+        .into_iter()
+        .partition(|path| is_override(path));
+    for path in files.into_iter().chain(overrides) {
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let mut body =
+            hcl::parse(&source).with_context(|| format!("failed to parse {}", path.display()))?;
+        for block in &mut body.blocks {
+            block.file.clone_from(&path);
+        }
+        if !is_override(&path) {
+            combined.attributes.extend(body.attributes);
+            combined.blocks.extend(body.blocks);
+            continue;
+        }
+        let file = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        for block in body.blocks {
+            let original = combined
+                .blocks
+                .iter_mut()
+                .find(|original| original.kind == block.kind && original.labels == block.labels);
+            match original {
+                Some(original) => merge(original, block, &file),
+                // Terraform rejects these, but keep the block to show it
+                None => combined.blocks.push(block),
+            }
+        }
+    }
+    Ok(combined)
+}
+
+/// Merge the `block` of the override file `file` into `original`. Attributes replace those of the
+/// same name, and nested blocks replace every nested block of their kind.
+fn merge(original: &mut Block, block: Block, file: &str) {
+    let mut replaced = Vec::new();
+    for attribute in block.body.attributes {
+        replaced.push(attribute.name.clone());
+        original
+            .body
+            .attributes
+            .retain(|existing| existing.name != attribute.name);
+        original.body.attributes.push(attribute);
+    }
+    let kinds: Vec<String> = block
+        .body
+        .blocks
+        .iter()
+        .map(|nested| nested.kind.clone())
+        .collect();
+    original
+        .body
+        .blocks
+        .retain(|nested| !kinds.contains(&nested.kind));
+    original.body.blocks.extend(block.body.blocks);
+    for kind in kinds {
+        if !replaced.contains(&kind) {
+            replaced.push(kind);
+        }
+    }
+    original.overrides.push((file.to_owned(), replaced));
+}
+
+/// Annotate the module calls beneath `tree` with the meta-arguments of their `module` blocks,
+/// read from the module at `base` joined with the source of `tree`, and mark those modified by
+/// override files.
+pub fn annotate(
+    scanner: &mut Scanner,
+    tree: &mut Tree<TreeNode>,
+    base: &Path,
+    interner: &mut Interner,
+) -> anyhow::Result<()> {
+    if tree.root.unresolved {
+        return Ok(());
+    }
+    let dir = base.join(&*tree.root.source);
+    for leaf in &mut tree.leaves {
+        if let Some(block) = scanner.module_call(&dir, &leaf.root.name)? {
+            if let Some(providers) = block.body.attribute("providers") {
+                leaf.root.provider_mappings =
+                    hcl::object_entries(&providers.expression).unwrap_or_default();
+            }
+            for (file, replaced) in &block.overrides {
+                leaf.root
+                    .markers
+                    .push(interner.str(&format!("{} overridden in {file}", replaced.join(", "))));
+            }
+        }
+        annotate(scanner, leaf, base, interner)?;
+    }
+    Ok(())
+}
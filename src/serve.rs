@@ -0,0 +1,174 @@
+//! A long-running HTTP server answering requests for the tree, so that dashboards don't need to
+//! run treaform once per request.
+
+use std::{
+    io::{BufRead as _, BufReader, Write as _},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+use clap::ValueEnum as _;
+
+use crate::{color::Coloring, report, Args, Format};
+
+/// Serve the tree on `listen` until killed. Requests take the options of `args`, with the `path`
+/// and `format` query arguments overriding `--path` and `--format`. The `path` is a directory
+/// within the project of `--path`, so that no other directory is planned.
+pub fn run(args: &Args, listen: &str) -> anyhow::Result<()> {
+    let listener =
+        TcpListener::bind(listen).with_context(|| format!("failed to listen on {listen}"))?;
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+    for stream in listener.incoming() {
+        let result = stream
+            .context("failed to accept connection")
+            .and_then(|stream| handle(args, stream));
+        if let Err(error) = result {
+            eprintln!("Error: {error:?}");
+        }
+    }
+    Ok(())
+}
+
+/// Answer the request on `stream`.
+fn handle(args: &Args, mut stream: TcpStream) -> anyhow::Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers, as nothing depends on them
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim_end().is_empty() {
+        header.clear();
+    }
+
+    let (status, content_type, body) = match request_line.split_whitespace().collect::<Vec<_>>()[..]
+    {
+        ["GET", target, _] => {
+            let (path, query) = target.split_once('?').unwrap_or((target, ""));
+            match path {
+                "/tree" => tree(args, query),
+                _ => (
+                    "404 Not Found",
+                    "text/plain",
+                    format!("no such endpoint `{path}`\n"),
+                ),
+            }
+        }
+        [_, _, _] => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "only GET is supported\n".to_owned(),
+        ),
+        _ => (
+            "400 Bad Request",
+            "text/plain",
+            "malformed request\n".to_owned(),
+        ),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// The status, content type and body answering `GET /tree?<query>`.
+fn tree(args: &Args, query: &str) -> (&'static str, &'static str, String) {
+    let mut args = Args {
+        command: None,
+        // Keep using the plan while the project is unchanged
+        reuse_plan: Some(None),
+        color: Coloring::Never,
+        hyperlinks: false,
+        ..args.clone()
+    };
+    for (key, value) in query
+        .split('&')
+        .filter(|argument| !argument.is_empty())
+        .map(|argument| argument.split_once('=').unwrap_or((argument, "")))
+    {
+        let Some(value) = decode(value) else {
+            return (
+                "400 Bad Request",
+                "text/plain",
+                format!("malformed `{key}`\n"),
+            );
+        };
+        match key {
+            "path" => match within_project(args.project(), &value) {
+                Ok(path) => {
+                    args.path = vec![path];
+                    args.budgets = None;
+                }
+                Err(body) => return ("403 Forbidden", "text/plain", body),
+            },
+            "format" => match Format::from_str(&value, true) {
+                Ok(format) => args.format = format,
+                Err(error) => return ("400 Bad Request", "text/plain", format!("{error}\n")),
+            },
+            _ => {
+                return (
+                    "400 Bad Request",
+                    "text/plain",
+                    format!("unknown query argument `{key}`\n"),
+                )
+            }
+        }
+    }
+    let mut body = String::new();
+    match report(&mut args, &mut body) {
+        Ok(()) => {
+            let content_type = match args.format {
+                Format::Tree => "text/plain",
+                Format::Json => "application/json",
+                Format::Dot => "text/vnd.graphviz",
+                Format::Mermaid | Format::Markdown => "text/markdown",
+                Format::Html => "text/html",
+                Format::D2 | Format::Plantuml => "text/plain",
+            };
+            ("200 OK", content_type, body)
+        }
+        Err(error) => (
+            "500 Internal Server Error",
+            "text/plain",
+            format!("Error: {error:?}\n"),
+        ),
+    }
+}
+
+/// The directory `path` within the project at `project`, which must not lead out of it, or the
+/// body of the response refusing it.
+fn within_project(project: &Path, path: &str) -> Result<PathBuf, String> {
+    let root = project
+        .canonicalize()
+        .map_err(|error| format!("failed to resolve the project: {error}\n"))?;
+    let dir = root
+        .join(path)
+        .canonicalize()
+        .map_err(|error| format!("failed to resolve `{path}`: {error}\n"))?;
+    if !dir.starts_with(&root) {
+        return Err(format!("`{path}` is outside the project\n"));
+    }
+    Ok(dir)
+}
+
+/// Decode a percent-encoded query argument.
+fn decode(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let [byte, tail @ ..] = rest {
+        rest = tail;
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = std::str::from_utf8(rest.get(..2)?).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+                rest = &rest[2..];
+            }
+            byte => bytes.push(*byte),
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
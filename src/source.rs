@@ -0,0 +1,153 @@
+//! Classifying module sources, as written in the `source` argument of module calls.
+
+/// Where a module's source comes from.
+#[derive(Debug, PartialEq)]
+pub enum Source<'a> {
+    /// A path relative to the calling module, e.g. `./network`.
+    Local(&'a str),
+    /// A module registry address, e.g. `terraform-aws-modules/vpc/aws`.
+    Registry {
+        host: &'a str,
+        namespace: &'a str,
+        name: &'a str,
+        provider: &'a str,
+        subdir: Option<&'a str>,
+    },
+    /// A git repository, e.g. `git::https://example.com/network.git//vpc?ref=v1.2.0`.
+    Git {
+        /// The URL of the repository, without the `git::` prefix.
+        url: &'a str,
+        subdir: Option<&'a str>,
+        /// The `ref` query argument, a branch, tag or commit.
+        reference: Option<&'a str>,
+    },
+    /// Any other source, e.g. an archive over HTTP or in a bucket.
+    Other(&'a str),
+}
+
+/// The default host of registry addresses.
+pub const DEFAULT_REGISTRY: &str = "registry.terraform.io";
+
+/// Classify `source` as terraform would.
+pub fn parse(source: &str) -> Source<'_> {
+    if source.starts_with("./") || source.starts_with("../") {
+        return Source::Local(source);
+    }
+    if let Some(url) = source.strip_prefix("git::") {
+        return git(url);
+    }
+    if source.contains("::") {
+        return Source::Other(source);
+    }
+    if source.starts_with("github.com/")
+        || source.starts_with("bitbucket.org/")
+        || source.starts_with("git@")
+    {
+        return git(source);
+    }
+    registry(source).unwrap_or(Source::Other(source))
+}
+
+/// Split the `//subdir` off `address`, ignoring the `//` of a URL scheme.
+fn split_subdir(address: &str) -> (&str, Option<&str>) {
+    let start = address.find("://").map_or(0, |scheme| scheme + 3);
+    match address[start..].find("//") {
+        Some(index) => (
+            &address[..start + index],
+            Some(&address[start + index + 2..]),
+        ),
+        None => (address, None),
+    }
+}
+
+fn git(source: &str) -> Source<'_> {
+    let (address, reference) = match source.split_once('?') {
+        Some((address, query)) => (
+            address,
+            query
+                .split('&')
+                .find_map(|argument| argument.strip_prefix("ref=")),
+        ),
+        None => (source, None),
+    };
+    let (url, subdir) = split_subdir(address);
+    Source::Git {
+        url,
+        subdir,
+        reference,
+    }
+}
+
+fn registry(source: &str) -> Option<Source<'_>> {
+    let (address, subdir) = split_subdir(source);
+    let parts: Vec<&str> = address.split('/').collect();
+    let (host, parts) = match parts.as_slice() {
+        [host, rest @ ..] if rest.len() == 3 && host.contains('.') => (*host, rest),
+        parts if parts.len() == 3 => (DEFAULT_REGISTRY, parts),
+        _ => return None,
+    };
+    let [namespace, name, provider] = parts else {
+        return None;
+    };
+    let is_name = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+    if !parts.iter().all(|part| is_name(part)) {
+        return None;
+    }
+    Some(Source::Registry {
+        host,
+        namespace,
+        name,
+        provider,
+        subdir,
+    })
+}
+
+/// The web page of the git repository at `url`, e.g. `https://github.com/org/repo` for
+/// `git@github.com:org/repo.git`.
+pub fn repository_url(url: &str) -> Option<String> {
+    let (host, path) = if let Some(rest) = url.strip_prefix("git@") {
+        rest.split_once(':')?
+    } else {
+        let rest = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"))
+            .or_else(|| url.strip_prefix("ssh://"))
+            .unwrap_or(url);
+        let (host, path) = rest.split_once('/')?;
+        // Drop the user and port, e.g. of `ssh://git@example.com:2222/org/repo.git`
+        let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+        (host.split_once(':').map_or(host, |(host, _)| host), path)
+    };
+    let path = path.trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    Some(format!("https://{host}/{path}"))
+}
+
+/// Whether `a` and `b` address the same module, e.g. `terraform-aws-modules/vpc/aws` and
+/// `registry.terraform.io/terraform-aws-modules/vpc/aws`.
+pub fn same(a: &str, b: &str) -> bool {
+    match (parse(a), parse(b)) {
+        (
+            Source::Git {
+                url: a,
+                subdir: a_subdir,
+                reference: a_reference,
+            },
+            Source::Git {
+                url: b,
+                subdir: b_subdir,
+                reference: b_reference,
+            },
+        ) => {
+            repository_url(a) == repository_url(b)
+                && a_subdir == b_subdir
+                && a_reference == b_reference
+        }
+        (a, b) => a == b,
+    }
+}
@@ -0,0 +1,208 @@
+//! The module instances and resources recorded in a state file.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::Context as _;
+use serde::Deserialize;
+use serde_json::Value;
+use termtree::Tree;
+
+/// A version 4 state file, as written by terraform 0.12 and later.
+#[derive(Deserialize)]
+struct State {
+    version: u64,
+    #[serde(default)]
+    resources: Vec<Resource>,
+}
+
+#[derive(Deserialize)]
+struct Resource {
+    /// The address of the module instance, absent for the root module.
+    module: Option<String>,
+    mode: String,
+    #[serde(rename = "type")]
+    kind: String,
+    name: String,
+    #[serde(default)]
+    instances: Vec<Instance>,
+}
+
+#[derive(Deserialize)]
+struct Instance {
+    index_key: Option<Value>,
+    /// `tainted` if the object must be replaced, e.g. after failing to be created.
+    status: Option<String>,
+    /// The key of an object left behind by a failed replacement, pending destruction.
+    deposed: Option<String>,
+}
+
+/// The state as output by `terraform show -json`.
+#[derive(Deserialize)]
+struct Show {
+    /// Absent when nothing has been deployed.
+    values: Option<Values>,
+}
+
+#[derive(Deserialize)]
+struct Values {
+    root_module: ShownModule,
+}
+
+/// A module instance of `terraform show -json` output.
+#[derive(Deserialize)]
+struct ShownModule {
+    /// The module instance's address, which is missing for the root module.
+    address: Option<String>,
+    #[serde(default)]
+    resources: Vec<ShownResource>,
+    #[serde(default)]
+    child_modules: Vec<ShownModule>,
+}
+
+#[derive(Deserialize)]
+struct ShownResource {
+    address: String,
+    #[serde(default)]
+    tainted: bool,
+    deposed_key: Option<String>,
+}
+
+/// The resources of a module instance and its child module instances.
+#[derive(Default)]
+struct ModuleInstance {
+    resources: Vec<String>,
+    children: BTreeMap<String, ModuleInstance>,
+}
+
+impl ModuleInstance {
+    fn into_tree(mut self, name: String) -> Tree<String> {
+        self.resources.sort();
+        let mut tree = Tree::new(name);
+        tree.leaves = self
+            .children
+            .into_iter()
+            .map(|(name, child)| child.into_tree(name))
+            .collect();
+        tree.leaves
+            .extend(self.resources.into_iter().map(Tree::new));
+        tree
+    }
+}
+
+impl ShownModule {
+    fn into_instance(self) -> ModuleInstance {
+        let prefix = self
+            .address
+            .as_deref()
+            .map_or_else(String::new, |address| format!("{address}."));
+        let resources = self
+            .resources
+            .into_iter()
+            .map(|resource| {
+                let mut line = resource
+                    .address
+                    .strip_prefix(&prefix)
+                    .unwrap_or(&resource.address)
+                    .to_owned();
+                if resource.tainted {
+                    line.push_str(" [tainted]");
+                }
+                if let Some(deposed) = &resource.deposed_key {
+                    line.push_str(&format!(" [deposed {deposed}]"));
+                }
+                line
+            })
+            .collect();
+        let children = self
+            .child_modules
+            .into_iter()
+            .map(|child| {
+                let address = child.address.as_deref().unwrap_or_default();
+                let name = module_segments(address)
+                    .last()
+                    .copied()
+                    .unwrap_or(address)
+                    .to_owned();
+                (name, child.into_instance())
+            })
+            .collect();
+        ModuleInstance {
+            resources,
+            children,
+        }
+    }
+}
+
+/// The module instances and resources of the current state of the project, from the output of
+/// `terraform show -json`.
+pub fn deployed(json: &str) -> anyhow::Result<String> {
+    let show: Show = serde_json::from_str(json).context("failed to deserialize the state")?;
+    let root = show
+        .values
+        .map(|values| values.root_module.into_instance())
+        .unwrap_or_default();
+    Ok(root.into_tree("*".to_owned()).to_string())
+}
+
+/// Print the module instances and resources recorded in the state file at `path`.
+pub fn run(path: &Path) -> anyhow::Result<()> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let state: State = serde_json::from_str(&contents)
+        .with_context(|| format!("failed to deserialize {}", path.display()))?;
+    if state.version != 4 {
+        anyhow::bail!(
+            "unsupported state version {}, only version 4 is supported",
+            state.version
+        )
+    }
+    let mut root = ModuleInstance::default();
+    for resource in state.resources {
+        let module = resource
+            .module
+            .as_deref()
+            .map_or_else(Vec::new, module_segments)
+            .into_iter()
+            .fold(&mut root, |module, segment| {
+                module.children.entry(segment.to_owned()).or_default()
+            });
+        let prefix = if resource.mode == "data" { "data." } else { "" };
+        let address = format!("{prefix}{}.{}", resource.kind, resource.name);
+        for instance in &resource.instances {
+            let mut line = match &instance.index_key {
+                Some(key) => format!("{address}[{key}]"),
+                None => address.clone(),
+            };
+            if instance.status.as_deref() == Some("tainted") {
+                line.push_str(" [tainted]");
+            }
+            if let Some(deposed) = &instance.deposed {
+                line.push_str(&format!(" [deposed {deposed}]"));
+            }
+            module.resources.push(line);
+        }
+        if resource.instances.is_empty() {
+            module.resources.push(address);
+        }
+    }
+    print!("{}", root.into_tree("*".to_owned()));
+    Ok(())
+}
+
+/// Split a module instance address, e.g. `module.app[0].module.db`, into one segment per module.
+fn module_segments(address: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let (mut start, mut in_string) = (0, false);
+    for (index, c) in address.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '.' if !in_string && index > start && address[index..].starts_with(".module.") => {
+                segments.push(&address[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    segments.push(&address[start..]);
+    segments
+}
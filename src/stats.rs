@@ -0,0 +1,152 @@
+//! Aggregate statistics of a module tree, for reviewing how modules are composed and reused.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    fs,
+    hash::{DefaultHasher, Hash as _, Hasher as _},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use anyhow::Context as _;
+use termtree::Tree;
+
+use crate::{scan, TreeNode};
+
+/// The number of most reused modules listed.
+const MOST_REUSED: usize = 5;
+
+/// The statistics of `tree`, whose modules are at `base` joined with their sources, and of
+/// `expanded`, the same tree with its calls expanded into their instances.
+///
+/// Besides the most reused directories, directories with identical `.tf` files are listed, as
+/// they're likely copies of one another.
+pub fn report(
+    tree: &Tree<TreeNode>,
+    expanded: &Tree<TreeNode>,
+    base: &Path,
+) -> anyhow::Result<String> {
+    let mut calls = 0;
+    let mut sources: HashMap<Rc<PathBuf>, (String, usize)> = HashMap::new();
+    let mut deepest = (0, String::new());
+    let mut widest = (0, String::new());
+    visit(tree, 0, &mut |node, depth, children| {
+        // The root module is no call
+        if !node.address.is_empty() {
+            calls += 1;
+            let location = node
+                .location()
+                .unwrap_or_else(|| node.source.display().to_string());
+            sources
+                .entry(node.source.clone())
+                .or_insert((location, 0))
+                .1 += 1;
+        }
+        if depth > deepest.0 {
+            deepest = (depth, node.address.to_string());
+        }
+        if children > widest.0 {
+            widest = (children, node.address.to_string());
+        }
+    });
+    let instances = instances(expanded);
+
+    let mut out = String::new();
+    let _ = writeln!(out, "Module calls: {calls}");
+    let _ = writeln!(out, "Unique sources: {}", sources.len());
+    let factor = if calls == 0 {
+        1.0
+    } else {
+        instances as f64 / calls as f64
+    };
+    let _ = writeln!(
+        out,
+        "Module instances: {instances} ({factor:.1}× the calls)"
+    );
+    let _ = writeln!(out, "Maximum depth: {} ({})", deepest.0, deepest.1);
+    let widest_address = if widest.1.is_empty() {
+        "the root module"
+    } else {
+        &widest.1
+    };
+    let _ = writeln!(
+        out,
+        "Widest fan-out: {} calls in {widest_address}",
+        widest.0
+    );
+
+    let mut reused: Vec<&(String, usize)> =
+        sources.values().filter(|(_, count)| *count > 1).collect();
+    reused.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    if !reused.is_empty() {
+        out.push_str("Most reused:\n");
+        for (location, count) in reused.into_iter().take(MOST_REUSED) {
+            let _ = writeln!(out, "  {location}: {count} calls");
+        }
+    }
+
+    // Remote modules which aren't installed have no directory to compare
+    let mut by_contents: BTreeMap<u64, Vec<&str>> = BTreeMap::new();
+    let mut dirs: Vec<(&Rc<PathBuf>, &String)> = sources
+        .iter()
+        .filter(|(source, _)| base.join(source.as_path()).is_dir())
+        .map(|(source, (location, _))| (source, location))
+        .collect();
+    dirs.sort_by(|a, b| a.1.cmp(b.1));
+    for (source, location) in dirs {
+        if let Some(hash) = contents_hash(&base.join(source.as_path()))? {
+            by_contents.entry(hash).or_default().push(location);
+        }
+    }
+    let identical: Vec<&Vec<&str>> = by_contents
+        .values()
+        .filter(|locations| locations.len() > 1)
+        .collect();
+    if !identical.is_empty() {
+        out.push_str("Identical directories:\n");
+        for locations in identical {
+            let _ = writeln!(out, "  {}", locations.join(", "));
+        }
+    }
+    Ok(out)
+}
+
+/// Call `f` on every module of `tree` with its depth and its number of children.
+fn visit(tree: &Tree<TreeNode>, depth: usize, f: &mut impl FnMut(&TreeNode, usize, usize)) {
+    f(&tree.root, depth, tree.leaves.len());
+    for leaf in &tree.leaves {
+        visit(leaf, depth + 1, f);
+    }
+}
+
+/// The number of module instances beneath the root of `tree`, whose calls are expanded into
+/// their instances.
+fn instances(tree: &Tree<TreeNode>) -> usize {
+    tree.leaves
+        .iter()
+        .map(|leaf| {
+            let node = &leaf.root;
+            let expanded = node.count.is_some()
+                || node.for_each.is_some()
+                || leaf.leaves.iter().any(|leaf| leaf.root.instance.is_some());
+            !expanded as usize + instances(leaf)
+        })
+        .sum()
+}
+
+/// A hash of the names and contents of the `.tf` files in `dir`, if it has any.
+fn contents_hash(dir: &Path) -> anyhow::Result<Option<u64>> {
+    let files = scan::tf_files(dir)?;
+    if files.is_empty() {
+        return Ok(None);
+    }
+    let mut hasher = DefaultHasher::new();
+    for file in files {
+        file.file_name().hash(&mut hasher);
+        fs::read(&file)
+            .with_context(|| format!("failed to read {}", file.display()))?
+            .hash(&mut hasher);
+    }
+    Ok(Some(hasher.finish()))
+}
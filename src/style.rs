@@ -0,0 +1,44 @@
+//! Layouts of the tree as text.
+
+use std::fmt::Write as _;
+
+use clap::ValueEnum;
+use termtree::Tree;
+
+use crate::{render::Render, TreeNode};
+
+/// How the hierarchy is drawn.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Style {
+    /// Box-drawing guide lines.
+    Tree,
+    /// Plain indentation with each line prefixed by its depth, e.g. '[2]', for screen readers and
+    /// plain-text email.
+    Indent,
+}
+
+/// The tree indented by two spaces per level, with each module prefixed by its depth.
+pub struct Indent {
+    /// Whether to highlight module names, instances and sources with ANSI colors.
+    pub highlight: bool,
+}
+
+impl Render for Indent {
+    fn render(&self, tree: &Tree<TreeNode>, out: &mut String) -> anyhow::Result<()> {
+        write_indented(out, tree, 0, self.highlight);
+        Ok(())
+    }
+}
+
+fn write_indented(out: &mut String, tree: &Tree<TreeNode>, depth: usize, highlight: bool) {
+    let _ = writeln!(
+        out,
+        "{:indent$}[{depth}] {}",
+        "",
+        tree.root.line(highlight),
+        indent = depth * 2
+    );
+    for leaf in &tree.leaves {
+        write_indented(out, leaf, depth + 1, highlight);
+    }
+}
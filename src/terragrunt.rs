@@ -0,0 +1,134 @@
+//! Terragrunt units, and the dependencies between them.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context as _;
+use serde::Deserialize;
+use termtree::Tree;
+
+use crate::{hcl, output, Args};
+
+/// The configuration file of a unit.
+const CONFIG: &str = "terragrunt.hcl";
+
+/// The output of `terragrunt terragrunt-info`.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Info {
+    /// The directory, within terragrunt's cache, where terraform runs.
+    working_dir: PathBuf,
+}
+
+/// The directory where terragrunt, run as `args` select, runs terraform for the unit at `dir`, which has the unit's
+/// configuration and the modules installed by `terraform init`.
+pub fn working_dir(args: &Args, dir: &Path) -> anyhow::Result<PathBuf> {
+    let mut command = args.terraform(dir);
+    command.arg("terragrunt-info");
+    let info: Info = serde_json::from_str(&output(command, "terragrunt terragrunt-info")?)
+        .context("failed to read the output of `terragrunt terragrunt-info`")?;
+    Ok(info.working_dir)
+}
+
+/// The units beneath `dir`, sorted: the directories with a terragrunt.hcl. Hidden directories,
+/// e.g. `.terragrunt-cache`, are skipped.
+pub fn units(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut units = Vec::new();
+    walk(dir, &mut units)?;
+    units.sort_unstable();
+    Ok(units)
+}
+
+fn walk(dir: &Path, units: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    if dir.join(CONFIG).is_file() {
+        units.push(dir.to_owned());
+    }
+    let entries = fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry?;
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if !hidden && entry.file_type()?.is_dir() {
+            walk(&entry.path(), units)?;
+        }
+    }
+    Ok(())
+}
+
+/// The units the unit at `unit` depends on, through its `dependency` blocks' `config_path` and its
+/// `dependencies` block's `paths`, resolved against the unit.
+fn dependencies(unit: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let path = unit.join(CONFIG);
+    let source =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let body =
+        hcl::parse(&source).with_context(|| format!("failed to parse {}", path.display()))?;
+    let config_paths = body.blocks("dependency").filter_map(|dependency| {
+        let config_path = dependency.body.attribute("config_path")?;
+        hcl::string_literal(&config_path.expression)
+    });
+    let paths = body
+        .blocks("dependencies")
+        .filter_map(|dependencies| dependencies.body.attribute("paths"))
+        .filter_map(|paths| hcl::list_items(&paths.expression))
+        .flatten()
+        .filter_map(|path| hcl::string_literal(&path));
+    Ok(config_paths
+        .chain(paths)
+        .map(|path| {
+            let dependency = unit.join(path);
+            dependency.canonicalize().unwrap_or(dependency)
+        })
+        .collect())
+}
+
+/// The tree of the units beneath `dir`, each above the units it depends on, starting from those
+/// which no other unit depends on. Units are named by their path relative to `dir`.
+pub fn stack(dir: &Path) -> anyhow::Result<String> {
+    let dir = dir
+        .canonicalize()
+        .with_context(|| format!("failed to resolve {}", dir.display()))?;
+    let mut graph = BTreeMap::new();
+    for unit in units(&dir)? {
+        let unit = unit.canonicalize().unwrap_or(unit);
+        let dependencies = dependencies(&unit)?;
+        graph.insert(unit, dependencies);
+    }
+    let depended_on: BTreeSet<&PathBuf> = graph.values().flatten().collect();
+    let mut tree = Tree::new(format!("{}", dir.display()));
+    for unit in graph.keys().filter(|unit| !depended_on.contains(unit)) {
+        tree.push(subtree(unit, &dir, &graph, &mut Vec::new()));
+    }
+    Ok(tree.to_string())
+}
+
+/// The tree of `unit` above its dependencies, marking those depending on themselves through the
+/// units in `ancestors`, and those which aren't units beneath the stack's directory `dir`.
+fn subtree(
+    unit: &Path,
+    dir: &Path,
+    graph: &BTreeMap<PathBuf, Vec<PathBuf>>,
+    ancestors: &mut Vec<PathBuf>,
+) -> Tree<String> {
+    let mut name = match unit.strip_prefix(dir) {
+        Ok(relative) => format!("./{}", relative.display()),
+        Err(_) => unit.display().to_string(),
+    };
+    if ancestors.iter().any(|ancestor| ancestor == unit) {
+        name.push_str(" [cycle]");
+        return Tree::new(name);
+    }
+    let Some(dependencies) = graph.get(unit) else {
+        name.push_str(" [outside the stack]");
+        return Tree::new(name);
+    };
+    ancestors.push(unit.to_owned());
+    let leaves: Vec<_> = dependencies
+        .iter()
+        .map(|dependency| subtree(dependency, dir, graph, ancestors))
+        .collect();
+    ancestors.pop();
+    Tree::new(name).with_leaves(leaves)
+}
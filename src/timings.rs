@@ -0,0 +1,158 @@
+//! How long terraform spent planning each module, from the machine-readable log of `terraform plan
+//! -json`.
+
+use std::{collections::HashMap, time::Duration};
+
+use serde::Deserialize;
+use termtree::Tree;
+
+use crate::{intern::Interner, strip_instance_keys, TreeNode};
+
+/// A message of the machine-readable log.
+#[derive(Deserialize)]
+struct Message {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(rename = "@timestamp")]
+    timestamp: Option<String>,
+    hook: Option<Hook>,
+}
+
+#[derive(Deserialize)]
+struct Hook {
+    resource: Option<Resource>,
+}
+
+#[derive(Deserialize)]
+struct Resource {
+    addr: String,
+    #[serde(default)]
+    module: String,
+}
+
+/// The time spent refreshing resources and reading data sources in each module, by address
+/// without instance keys.
+///
+/// Operations running concurrently are each counted in full.
+pub fn parse(log: &str) -> HashMap<String, Duration> {
+    let mut started: HashMap<(&'static str, String), f64> = HashMap::new();
+    let mut timings: HashMap<String, Duration> = HashMap::new();
+    for message in messages(log) {
+        let (operation, is_start) = match message.kind.as_str() {
+            "refresh_start" => ("refresh", true),
+            "refresh_complete" => ("refresh", false),
+            // Data sources are read with apply hooks during a plan
+            "apply_start" => ("apply", true),
+            "apply_complete" | "apply_errored" => ("apply", false),
+            _ => continue,
+        };
+        let (Some(resource), Some(time)) = (
+            message.hook.and_then(|hook| hook.resource),
+            message.timestamp.as_deref().and_then(seconds),
+        ) else {
+            continue;
+        };
+        let key = (operation, resource.addr);
+        if is_start {
+            started.insert(key, time);
+        } else if let Some(start) = started.remove(&key) {
+            let elapsed = Duration::from_secs_f64((time - start).max(0.0));
+            *timings
+                .entry(strip_instance_keys(&resource.module))
+                .or_default() += elapsed;
+        }
+    }
+    timings
+}
+
+/// Mark each module of `tree` with the time spent planning its subtree, returning that of `tree`.
+pub fn annotate(
+    tree: &mut Tree<TreeNode>,
+    timings: &HashMap<String, Duration>,
+    interner: &mut Interner,
+) -> Duration {
+    let mut total = timings
+        .get(&*tree.root.address)
+        .copied()
+        .unwrap_or_default();
+    for leaf in &mut tree.leaves {
+        total += annotate(leaf, timings, interner);
+    }
+    let marker = if total < Duration::from_secs(1) {
+        format!("{}ms planning", total.as_millis())
+    } else {
+        format!("{:.1}s planning", total.as_secs_f64())
+    };
+    tree.root.markers.push(interner.str(&marker));
+    total
+}
+
+/// The messages of the log, skipping any lines which aren't messages.
+fn messages(log: &str) -> impl Iterator<Item = Message> + '_ {
+    log.lines()
+        .filter_map(|line| serde_json::from_str::<Message>(line).ok())
+}
+
+/// The seconds since the Unix epoch of an RFC 3339 timestamp, e.g.
+/// `2024-05-01T12:30:00.123456+01:00`.
+fn seconds(timestamp: &str) -> Option<f64> {
+    let (date, time) = timestamp.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    let (time, offset) = match time.find(['Z', 'z', '+', '-']) {
+        Some(index) => time.split_at(index),
+        None => (time, ""),
+    };
+    let mut time = time.splitn(3, ':');
+    let hours: f64 = time.next()?.parse().ok()?;
+    let minutes: f64 = time.next()?.parse().ok()?;
+    let secs: f64 = time.next()?.parse().ok()?;
+    let offset = match offset.split_at_checked(1) {
+        Some((sign, offset)) if sign == "+" || sign == "-" => {
+            let (offset_hours, offset_minutes) = offset.split_once(':')?;
+            let offset = offset_hours.parse::<f64>().ok()? * 3600.0
+                + offset_minutes.parse::<f64>().ok()? * 60.0;
+            if sign == "-" {
+                -offset
+            } else {
+                offset
+            }
+        }
+        _ => 0.0,
+    };
+    let days = days_from_civil(year, month, day) as f64;
+    Some(days * 86400.0 + hours * 3600.0 + minutes * 60.0 + secs - offset)
+}
+
+/// The number of days since 1970-01-01 of a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_since_the_epoch() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(days_from_civil(2000, 3, 1), 11017);
+        assert_eq!(days_from_civil(2024, 2, 29), 19782);
+        assert_eq!(days_from_civil(1600, 1, 1), -135140);
+    }
+
+    #[test]
+    fn seconds_of_timestamps() {
+        assert_eq!(seconds("1970-01-01T00:00:00Z"), Some(0.0));
+        assert_eq!(seconds("2024-05-01T12:30:00.5+01:00"), Some(1714563000.5));
+        assert_eq!(seconds("2024-05-01T12:30:00-05:30"), Some(1714586400.0));
+        assert_eq!(seconds("2024-05-01 12:30:00Z"), None);
+        assert_eq!(seconds("2024-05-01T12:30Z"), None);
+    }
+}
@@ -0,0 +1,409 @@
+//! A parser for the subset of TOML used by configuration files.
+//!
+//! Tables, arrays of tables, dotted and quoted keys, strings, integers, floats, booleans, arrays and
+//! inline tables are supported. Dates and multi-line strings are not.
+
+use std::{iter::Peekable, str::Chars};
+
+use serde_json::{Map, Value};
+
+/// Parse a TOML document into a JSON object, which can then be deserialized with `serde_json`.
+pub fn parse(contents: &str) -> anyhow::Result<Value> {
+    let mut root = Map::new();
+    // The path of the current table
+    let mut table: Vec<String> = Vec::new();
+    let mut lines = contents.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let mut parser = Parser {
+            chars: line.chars().peekable(),
+        };
+        let result = (|| {
+            parser.skip_whitespace();
+            match parser.chars.peek() {
+                None | Some('#') => {}
+                Some('[') => {
+                    parser.chars.next();
+                    let is_array = parser.chars.next_if_eq(&'[').is_some();
+                    table = parser.key()?;
+                    parser.expect(']')?;
+                    if is_array {
+                        parser.expect(']')?;
+                    }
+                    parser.end()?;
+                    let parent = descend(&mut root, &table[..table.len() - 1])?;
+                    let last = &table[table.len() - 1];
+                    if is_array {
+                        let array = parent
+                            .entry(last.clone())
+                            .or_insert_with(|| Value::Array(Vec::new()));
+                        let Value::Array(array) = array else {
+                            anyhow::bail!("`{last}` is not an array of tables")
+                        };
+                        array.push(Value::Object(Map::new()));
+                    } else {
+                        let existing = parent
+                            .entry(last.clone())
+                            .or_insert_with(|| Value::Object(Map::new()));
+                        if !existing.is_object() {
+                            anyhow::bail!("`{last}` is not a table")
+                        }
+                    }
+                }
+                Some(_) => {
+                    // Multi-line arrays continue onto the following lines
+                    let key = parser.key()?;
+                    parser.expect('=')?;
+                    let rest: String = parser.chars.collect();
+                    let mut rest_lines = lines.by_ref().map(|(_, line)| line);
+                    let value = Parser::value_spanning(&rest, &mut rest_lines)?;
+                    let target = descend(&mut root, &table)?;
+                    let parent = descend(target, &key[..key.len() - 1])?;
+                    let last = &key[key.len() - 1];
+                    if parent.insert(last.clone(), value).is_some() {
+                        anyhow::bail!("duplicate key `{last}`")
+                    }
+                }
+            }
+            Ok(())
+        })();
+        result.map_err(|error| error.context(format!("line {}", index + 1)))?;
+    }
+    Ok(Value::Object(root))
+}
+
+/// Walk down `path` from `table`, creating tables as required. Arrays of tables resolve to their
+/// last element.
+fn descend<'a>(
+    mut table: &'a mut Map<String, Value>,
+    path: &[String],
+) -> anyhow::Result<&'a mut Map<String, Value>> {
+    for key in path {
+        let value = table
+            .entry(key.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        let value = match value {
+            Value::Array(array) => array
+                .last_mut()
+                .ok_or_else(|| anyhow::anyhow!("`{key}` is empty"))?,
+            value => value,
+        };
+        table = value
+            .as_object_mut()
+            .ok_or_else(|| anyhow::anyhow!("`{key}` is not a table"))?;
+    }
+    Ok(table)
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Parser<'_> {
+    /// Parse the value starting at `first`, pulling further lines from `rest` while brackets
+    /// remain open.
+    fn value_spanning<'l>(
+        first: &str,
+        rest: &mut impl Iterator<Item = &'l str>,
+    ) -> anyhow::Result<Value> {
+        let mut text = first.to_owned();
+        loop {
+            let mut parser = Parser {
+                chars: text.chars().peekable(),
+            };
+            let result = parser
+                .value()
+                .and_then(|value| parser.end().map(|()| value));
+            match result {
+                Err(error) if error.is::<Incomplete>() => match rest.next() {
+                    Some(line) => {
+                        text.push('\n');
+                        text.push_str(line);
+                    }
+                    None => anyhow::bail!("unterminated value"),
+                },
+                result => return result,
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    /// Skip whitespace, newlines and comments inside arrays and inline tables.
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_whitespace();
+            if self.chars.peek() != Some(&'#') {
+                return;
+            }
+            while self.chars.next_if(|c| *c != '\n').is_some() {}
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> anyhow::Result<()> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => anyhow::bail!("expected `{expected}`, found `{c}`"),
+            None => Err(Incomplete.into()),
+        }
+    }
+
+    /// Ensure nothing but a comment remains.
+    fn end(&mut self) -> anyhow::Result<()> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            None | Some('#') => Ok(()),
+            Some(c) => anyhow::bail!("unexpected `{c}`"),
+        }
+    }
+
+    /// Parse a possibly dotted key.
+    fn key(&mut self) -> anyhow::Result<Vec<String>> {
+        let mut key = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let part = match self.chars.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let mut part = String::new();
+                    while let Some(c) = self
+                        .chars
+                        .next_if(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+                    {
+                        part.push(c);
+                    }
+                    if part.is_empty() {
+                        anyhow::bail!("expected a key")
+                    }
+                    part
+                }
+            };
+            key.push(part);
+            self.skip_whitespace();
+            if self.chars.next_if_eq(&'.').is_none() {
+                return Ok(key);
+            }
+        }
+    }
+
+    fn value(&mut self) -> anyhow::Result<Value> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            None => Err(Incomplete.into()),
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => {
+                self.chars.next();
+                let mut array = Vec::new();
+                loop {
+                    self.skip_blank();
+                    if self.chars.next_if_eq(&']').is_some() {
+                        return Ok(Value::Array(array));
+                    }
+                    array.push(self.value()?);
+                    self.skip_blank();
+                    match self.chars.next() {
+                        Some(',') => {}
+                        Some(']') => return Ok(Value::Array(array)),
+                        Some(c) => anyhow::bail!("expected `,` or `]`, found `{c}`"),
+                        None => return Err(Incomplete.into()),
+                    }
+                }
+            }
+            Some('{') => {
+                self.chars.next();
+                let mut table = Map::new();
+                loop {
+                    self.skip_whitespace();
+                    if self.chars.next_if_eq(&'}').is_some() {
+                        return Ok(Value::Object(table));
+                    }
+                    let key = self.key()?;
+                    self.expect('=')?;
+                    let value = self.value()?;
+                    let parent = descend(&mut table, &key[..key.len() - 1])?;
+                    parent.insert(key[key.len() - 1].clone(), value);
+                    self.skip_whitespace();
+                    match self.chars.next() {
+                        Some(',') => {}
+                        Some('}') => return Ok(Value::Object(table)),
+                        Some(c) => anyhow::bail!("expected `,` or `}}`, found `{c}`"),
+                        None => anyhow::bail!("unterminated inline table"),
+                    }
+                }
+            }
+            Some(_) => {
+                let mut word = String::new();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+' | '.'))
+                {
+                    word.push(c);
+                }
+                match word.as_str() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "" => anyhow::bail!("expected a value"),
+                    number => {
+                        let number = number.replace('_', "");
+                        if let Ok(integer) = number.parse::<i64>() {
+                            Ok(Value::from(integer))
+                        } else if let Ok(float) = number.parse::<f64>() {
+                            Ok(Value::from(float))
+                        } else {
+                            anyhow::bail!("unsupported value `{word}`")
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn basic_string(&mut self) -> anyhow::Result<String> {
+        self.chars.next();
+        let mut string = String::new();
+        loop {
+            match self.chars.next() {
+                None | Some('\n') => anyhow::bail!("unterminated string"),
+                Some('"') => return Ok(string),
+                Some('\\') => match self.chars.next() {
+                    Some('n') => string.push('\n'),
+                    Some('t') => string.push('\t'),
+                    Some('r') => string.push('\r'),
+                    Some(c @ ('"' | '\\')) => string.push(c),
+                    Some('u') => {
+                        let hex: String = (0..4).filter_map(|_| self.chars.next()).collect();
+                        let c = u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| anyhow::anyhow!("invalid escape `\\u{hex}`"))?;
+                        string.push(c);
+                    }
+                    Some(c) => anyhow::bail!("invalid escape `\\{c}`"),
+                    None => anyhow::bail!("unterminated string"),
+                },
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> anyhow::Result<String> {
+        self.chars.next();
+        let mut string = String::new();
+        loop {
+            match self.chars.next() {
+                None | Some('\n') => anyhow::bail!("unterminated string"),
+                Some('\'') => return Ok(string),
+                Some(c) => string.push(c),
+            }
+        }
+    }
+}
+
+/// The value continues onto the next line.
+#[derive(Debug)]
+struct Incomplete;
+
+impl std::fmt::Display for Incomplete {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("unexpected end of line")
+    }
+}
+
+impl std::error::Error for Incomplete {}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn tables_and_values() {
+        let document = r#"
+# A comment
+title = "tree" # trailing comment
+parallelism = 1_000
+ratio = 0.5
+enabled = true
+
+[paths]
+cache-dir = 'C:\cache'
+"quoted key" = "a \"b\" \u00e9"
+nested.key = -3
+"#;
+        assert_eq!(
+            parse(document).unwrap(),
+            json!({
+                "title": "tree",
+                "parallelism": 1000,
+                "ratio": 0.5,
+                "enabled": true,
+                "paths": {
+                    "cache-dir": r"C:\cache",
+                    "quoted key": "a \"b\" é",
+                    "nested": { "key": -3 },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn arrays_of_tables() {
+        let document = r#"
+[[budgets]]
+module = "module.app"
+max_resources = 10
+
+[[budgets]]
+module = "module.db*"
+max_resources = 5
+level = "warn"
+"#;
+        assert_eq!(
+            parse(document).unwrap(),
+            json!({
+                "budgets": [
+                    { "module": "module.app", "max_resources": 10 },
+                    { "module": "module.db*", "max_resources": 5, "level": "warn" },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn multi_line_arrays_and_inline_tables() {
+        let document = r#"
+filter = [
+    "vpc", # the network
+    "dns",
+]
+labels = { tier = "critical", owner.team = "platform" }
+"#;
+        assert_eq!(
+            parse(document).unwrap(),
+            json!({
+                "filter": ["vpc", "dns"],
+                "labels": { "tier": "critical", "owner": { "team": "platform" } },
+            })
+        );
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let error = parse("a = 1\na = 2").unwrap_err();
+        assert_eq!(format!("{error:#}"), "line 2: duplicate key `a`");
+        let error = parse("a = 1\n\nb = 2024-01-01").unwrap_err();
+        assert_eq!(
+            format!("{error:#}"),
+            "line 3: unsupported value `2024-01-01`"
+        );
+        let error = parse("a = [1,\n2").unwrap_err();
+        assert_eq!(format!("{error:#}"), "line 1: unterminated value");
+        let error = parse("a = 1\n[a]").unwrap_err();
+        assert_eq!(format!("{error:#}"), "line 2: `a` is not a table");
+    }
+}
@@ -0,0 +1,109 @@
+//! Checking the modules vendored by `terraform init` against the sources they were declared with.
+
+use std::path::Path;
+
+use anyhow::Context as _;
+
+use crate::{
+    git, hcl, installed,
+    scan::Scanner,
+    source::{self, Source},
+    TreaformError,
+};
+
+/// Report whether each remote module installed for the project at `dir` still matches its
+/// declared source and ref, and is unmodified. Fails if any module doesn't.
+pub fn run(dir: &Path) -> anyhow::Result<()> {
+    let installed = installed::load(dir)?;
+    if installed.is_empty() {
+        anyhow::bail!(
+            "no modules are installed in {}, run `terraform init` first",
+            installed::data_dir(dir).display()
+        )
+    }
+    let mut scanner = Scanner::default();
+    let mut failed = 0;
+    for module in &installed {
+        if module.key.is_empty() || matches!(source::parse(&module.source), Source::Local(_)) {
+            continue;
+        }
+        let address = format!("module.{}", module.key.replace('.', ".module."));
+        let (parent, name) = module.key.rsplit_once('.').unwrap_or(("", &module.key));
+        let parent_dir = installed
+            .iter()
+            .find(|installed| installed.key == parent)
+            .map_or_else(|| dir.to_owned(), |installed| dir.join(&installed.dir));
+        let block = scanner.module_call(&parent_dir, name)?;
+        let literal = |attribute| {
+            block
+                .and_then(|block| block.body.attribute(attribute))
+                .and_then(|attribute| hcl::string_literal(&attribute.expression))
+        };
+
+        let mut problems = Vec::new();
+        match literal("source") {
+            None if block.is_none() => problems.push("no longer called".to_owned()),
+            Some(declared) if !source::same(&declared, &module.source) => {
+                problems.push(format!(
+                    "installed from `{}`, declared as `{declared}`",
+                    module.source
+                ));
+            }
+            _ => {}
+        }
+        let constraint = literal("version");
+        if let (Some(version), Some(constraint)) = (&module.version, &constraint) {
+            let exact = constraint.trim().trim_start_matches('=').trim();
+            if exact.starts_with(|c: char| c.is_ascii_digit()) && exact != version {
+                problems.push(format!("version {version} installed, {exact} declared"));
+            }
+        }
+
+        let checkout = dir.join(&module.dir);
+        let is_git = checkout.join(".git").exists();
+        if is_git {
+            let modified = git::modified_count(&checkout)
+                .with_context(|| format!("failed to check {}", checkout.display()))?;
+            if modified > 0 {
+                problems.push(format!("{modified} locally modified file(s)"));
+            }
+            let references = match source::parse(&module.source) {
+                Source::Git { reference, .. } => reference.map(str::to_owned).into_iter().collect(),
+                // Registry modules are usually published from a git tag of their version
+                Source::Registry { .. } => module
+                    .version
+                    .iter()
+                    .flat_map(|version| [format!("v{version}"), version.clone()])
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let expected = references
+                .iter()
+                .find_map(|reference| Some((reference, git::resolve(&checkout, reference)?)));
+            if let (Some((reference, expected)), Some(head)) =
+                (expected, git::resolve(&checkout, "HEAD"))
+            {
+                if expected != head {
+                    problems.push(format!(
+                        "checked out at {}, but `{reference}` is {}",
+                        &head[..head.len().min(12)],
+                        &expected[..expected.len().min(12)]
+                    ));
+                }
+            }
+        }
+
+        if !problems.is_empty() {
+            failed += 1;
+            println!("{address}: {}", problems.join("; "));
+        } else if is_git {
+            println!("{address}: ok");
+        } else {
+            println!("{address}: unverified, not a git checkout");
+        }
+    }
+    if failed > 0 {
+        return Err(TreaformError::VerificationFailed { modules: failed }.into());
+    }
+    Ok(())
+}
@@ -0,0 +1,77 @@
+//! The warnings of the human readable output of `terraform plan`, shown alongside the tree.
+
+use std::fmt;
+
+/// A warning diagnostic emitted by `terraform plan -no-color`.
+pub struct Warning {
+    pub summary: String,
+    detail: Vec<String>,
+}
+
+impl Warning {
+    /// Parse the warnings out of the human readable `terraform plan -no-color` output.
+    ///
+    /// Both the full format (`Warning: <summary>` followed by its detail) and the list produced by
+    /// `-compact-warnings` are understood.
+    pub fn parse(output: &str) -> Vec<Self> {
+        let mut warnings: Vec<Self> = Vec::new();
+        let mut in_warning = false;
+        let mut in_compact = false;
+        for line in output.lines() {
+            if let Some(summary) = line.strip_prefix("Warning: ") {
+                warnings.push(Warning {
+                    summary: summary.to_owned(),
+                    detail: Vec::new(),
+                });
+                in_warning = true;
+                in_compact = false;
+            } else if line == "Warnings:" {
+                in_warning = false;
+                in_compact = true;
+            } else if line.starts_with("Error: ") || line.starts_with('─') {
+                in_warning = false;
+                in_compact = false;
+            } else if in_compact {
+                if let Some(summary) = line.strip_prefix("- ") {
+                    warnings.push(Warning {
+                        summary: summary.to_owned(),
+                        detail: Vec::new(),
+                    });
+                } else if line.starts_with("  ") {
+                    if let Some(warning) = warnings.last_mut() {
+                        warning.detail.push(line.to_owned());
+                    }
+                } else if !line.is_empty() {
+                    in_compact = false;
+                }
+            } else if in_warning {
+                if let Some(warning) = warnings.last_mut() {
+                    warning.detail.push(line.to_owned());
+                }
+            }
+        }
+        for warning in &mut warnings {
+            while warning.detail.first().is_some_and(|line| line.is_empty()) {
+                warning.detail.remove(0);
+            }
+            while warning.detail.last().is_some_and(|line| line.is_empty()) {
+                warning.detail.pop();
+            }
+        }
+        warnings
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Warning: {}", self.summary)?;
+        if !self.detail.is_empty() {
+            writeln!(f)?;
+        }
+        for line in &self.detail {
+            writeln!(f)?;
+            f.write_str(line)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+//! Repainting the tree whenever the configuration of a project changes.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write as _,
+    fs,
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context as _;
+
+use crate::{color, report, Args};
+
+/// How often the files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long the files must stay unchanged after a change before the tree is repainted, so that
+/// saving several files at once repaints it once.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// The modification time and length of each watched file.
+type Snapshot = BTreeMap<PathBuf, (SystemTime, u64)>;
+
+/// Paint the tree of the project of `args`, as it would be printed without a command, and paint it
+/// again whenever a `.tf` or `.tfvars` file beneath the project changes, until killed.
+///
+/// Lines which weren't in the previous tree beneath the same ancestors are marked in the gutter, so
+/// that modules added or changed by an edit stand out. Errors are painted in place of the tree.
+pub fn run(args: &Args) -> anyhow::Result<()> {
+    let dir = args.project().to_owned();
+    let highlight = args.color.enabled();
+    let mut snapshot = snapshot(&dir)?;
+    let mut previous: Option<HashSet<String>> = None;
+    loop {
+        let mut run_args = Args {
+            command: None,
+            // The spinner would draw over the tree being repainted
+            no_progress: true,
+            ..args.clone()
+        };
+        let mut out = String::new();
+        let result = report(&mut run_args, &mut out);
+        let mut screen = String::from("\x1b[2J\x1b[H");
+        match result {
+            Ok(()) => {
+                let paths = paths(&out);
+                let mut changed = 0;
+                for (line, path) in out.lines().zip(&paths) {
+                    let new = previous
+                        .as_ref()
+                        .is_some_and(|previous| !previous.contains(path));
+                    changed += new as usize;
+                    let gutter = if new { "+ " } else { "  " };
+                    let _ = color::styled(&mut screen, highlight && new, color::CHANGED, gutter);
+                    let _ = writeln!(screen, "{line}");
+                }
+                if previous.is_some() {
+                    let plural = if changed == 1 { "" } else { "s" };
+                    let _ = writeln!(screen, "\n{changed} line{plural} changed");
+                }
+                previous = Some(paths.into_iter().collect());
+            }
+            Err(error) => {
+                let _ = writeln!(screen, "Error: {error:?}");
+            }
+        }
+        let _ = writeln!(screen, "Watching {} for changes…", dir.display());
+        print!("{screen}");
+        io::stdout().flush().context("failed to write stdout")?;
+
+        // Wait for a change, then for the files to settle
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let next = self::snapshot(&dir)?;
+            if next != snapshot {
+                snapshot = next;
+                break;
+            }
+        }
+        loop {
+            thread::sleep(DEBOUNCE);
+            let next = self::snapshot(&dir)?;
+            if next == snapshot {
+                break;
+            }
+            snapshot = next;
+        }
+    }
+}
+
+/// Each line of the tree `out` without its guide lines, after those of its ancestors, so that lines
+/// are compared regardless of the guides drawn for their siblings.
+fn paths(out: &str) -> Vec<String> {
+    // The indentation and content of the ancestors of the current line
+    let mut ancestors: Vec<(usize, &str)> = Vec::new();
+    let mut paths = Vec::new();
+    for line in out.lines() {
+        let content = line.trim_start_matches(['│', '├', '└', '─', ' ']);
+        let indentation = line.len() - content.len();
+        while ancestors
+            .last()
+            .is_some_and(|(ancestor, _)| *ancestor >= indentation)
+        {
+            ancestors.pop();
+        }
+        ancestors.push((indentation, content));
+        let path: Vec<&str> = ancestors.iter().map(|(_, content)| *content).collect();
+        paths.push(path.join("\n"));
+    }
+    paths
+}
+
+/// The `.tf` and `.tfvars` files beneath `dir`, skipping hidden directories such as `.terraform`.
+fn snapshot(dir: &Path) -> anyhow::Result<Snapshot> {
+    let mut files = Snapshot::new();
+    walk(dir, &mut files)?;
+    Ok(files)
+}
+
+fn walk(dir: &Path, files: &mut Snapshot) -> anyhow::Result<()> {
+    let entries = fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk(&entry.path(), files)?;
+        } else if name.ends_with(".tf") || name.ends_with(".tfvars") {
+            // Files may be replaced rather than written to, and vanish in between
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            files.insert(entry.path(), (modified, metadata.len()));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,39 @@
+//! Explaining where a module comes from, by the chains of calls leading to it.
+
+use std::fmt::Write as _;
+
+use termtree::Tree;
+
+use crate::{filter, glob, TreeNode};
+
+/// Every chain of module calls from the root of `tree` to a module matching the glob `pattern`, by
+/// name, source directory, remote source or the `source` argument as written, one per line, e.g.
+/// `* → platform → networking → vpc`. Chains stop at the first match, rather than continuing to
+/// matching modules within it.
+pub fn report(tree: &Tree<TreeNode>, pattern: &str) -> anyhow::Result<String> {
+    let mut out = String::new();
+    chains(tree, pattern, &mut Vec::new(), &mut out);
+    if out.is_empty() {
+        anyhow::bail!("no module matches `{pattern}`")
+    }
+    Ok(out)
+}
+
+fn chains<'a>(tree: &'a Tree<TreeNode>, pattern: &str, chain: &mut Vec<&'a str>, out: &mut String) {
+    chain.push(&tree.root.name);
+    let node = &tree.root;
+    let matches = !node.address.is_empty()
+        && (filter::matches(node, pattern)
+            || node
+                .call_source
+                .as_ref()
+                .is_some_and(|call_source| glob::matches(pattern, call_source)));
+    if matches {
+        let _ = writeln!(out, "{}", chain.join(" → "));
+    } else {
+        for leaf in &tree.leaves {
+            chains(leaf, pattern, chain, out);
+        }
+    }
+    chain.pop();
+}
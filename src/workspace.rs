@@ -0,0 +1,41 @@
+//! Terraform workspaces, which hold separate states of the same configuration.
+
+use std::{path::Path, process};
+
+use crate::{output, Args};
+
+/// The command listing the workspaces of the project at `dir`.
+pub fn list_command(args: &Args, dir: &Path) -> process::Command {
+    let mut command = args.runner.command(args.binary(), dir);
+    command.args(["workspace", "list"]);
+    command
+}
+
+/// The names of the workspaces of the project at `dir`.
+pub fn list(args: &Args, dir: &Path) -> anyhow::Result<Vec<String>> {
+    let name = format!("{} workspace list", args.program());
+    let stdout = output(list_command(args, dir), &name)?;
+    // The selected workspace is marked with an asterisk
+    Ok(stdout
+        .lines()
+        .map(|line| line.trim_start_matches('*').trim())
+        .filter(|line| !line.is_empty())
+        .map(str::to_owned)
+        .collect())
+}
+
+/// The command selecting the workspace `workspace` of the project at `dir`, creating it if it
+/// doesn't exist.
+pub fn create_command(args: &Args, dir: &Path, workspace: &str) -> process::Command {
+    // Not `Args::terraform`, as terraform refuses to select a workspace while TF_WORKSPACE is set
+    let mut command = args.runner.command(args.binary(), dir);
+    command.args(["workspace", "select", "-or-create=true", workspace]);
+    command
+}
+
+/// Select the workspace `workspace` of the project at `dir`, creating it if it doesn't exist.
+pub fn create(args: &Args, dir: &Path, workspace: &str) -> anyhow::Result<()> {
+    let name = format!("{} workspace select", args.program());
+    output(create_command(args, dir, workspace), &name)?;
+    Ok(())
+}
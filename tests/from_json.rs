@@ -0,0 +1,113 @@
+//! Building the tree of the example project from a fixture plan, with `--from-json`.
+
+use std::{
+    io::Write as _,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+const PLAN: &str = include_str!("fixtures/example.json");
+
+/// The output of running treaform on the example project with `args`, writing `stdin` to it.
+fn treaform(args: &[&str], stdin: &str) -> String {
+    let project = Path::new(env!("CARGO_MANIFEST_DIR")).join("example");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_treaform"))
+        .args(args)
+        .current_dir(project)
+        .env_remove("TREAFORM_CACHE_DIR")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// The output of running treaform on the example project's fixture plan with `args`.
+fn from_json(args: &[&str]) -> String {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/example.json");
+    let fixture = fixture.to_str().unwrap();
+    treaform(&[&["--from-json", fixture], args].concat(), "")
+}
+
+#[test]
+fn tree() {
+    assert_eq!(
+        from_json(&[]),
+        "\
+* (./)
+├── bar{x y z} (./bar)
+│   └── baz×3 (./bar/baz)
+└── foo (./foo)
+"
+    );
+}
+
+#[test]
+fn stdin() {
+    assert_eq!(treaform(&["--from-json", "-"], PLAN), from_json(&[]));
+}
+
+#[test]
+fn expanded_instances() {
+    assert_eq!(
+        from_json(&["--expand-instances", "--root", "module.bar[\"y\"]"]),
+        "\
+[\"y\"]
+└── baz×3 (./bar/baz)
+    ├── [0]
+    ├── [1]
+    └── [2]
+"
+    );
+}
+
+#[test]
+fn changes_and_resources() {
+    assert_eq!(
+        from_json(&["--changes", "--wide"]),
+        "\
+* (./) | random: 10
+├── bar{x y z} (./bar) | random: 9
+│   └── baz×3 (./bar/baz) | random: 9 [+9 ~0 -0]
+└── foo (./foo) | random: 1 [+1 ~0 -0]
+"
+    );
+}
+
+#[test]
+fn addresses() {
+    assert_eq!(
+        from_json(&["addresses", "module.bar[\"y\"]"]),
+        "\
+module.bar[\"y\"]
+module.bar[\"y\"].module.baz[0]
+module.bar[\"y\"].module.baz[1]
+module.bar[\"y\"].module.baz[2]
+"
+    );
+}
+
+#[test]
+fn json() {
+    let tree: serde_json::Value = serde_json::from_str(&from_json(&["--format", "json"])).unwrap();
+    let children = tree["children"].as_array().unwrap();
+    assert_eq!(children.len(), 2);
+    assert_eq!(children[0]["address"], "module.bar");
+    assert_eq!(children[0]["for_each"], serde_json::json!(["x", "y", "z"]));
+    assert_eq!(children[0]["children"][0]["count"], 3);
+    assert_eq!(children[0]["children"][0]["source"], "bar/baz");
+    assert_eq!(children[1]["address"], "module.foo");
+}